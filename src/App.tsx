@@ -35,9 +35,14 @@ function App() {
 	]);
 
 	const [graphs, setGraphs] = useState<any[]>([]);
+	const [dashed, setDashed] = useState<any[]>([]);
+	const [regions, setRegions] = useState<any[]>([]);
 
 	const [intercepts, setIntercepts] = useState<number[][]>([]);
 
+	// scatter plots of lists, drawn as points rather than lines
+	const [points, setPoints] = useState<number[][]>([]);
+
 	const [eq1, setEq1] = useState("");
 	const [eq2, setEq2] = useState("");
 
@@ -49,6 +54,21 @@ function App() {
 
 	const [graphNames, setGraphNames] = useState<String[]>([])
 
+	// the evaluator is kept between renders so only the lines that changed are re-evaluated
+	const evaluator = useRef<Evaluator | null>(null);
+	const evaluatedLines = useRef<string[]>([]);
+	const evaluatedViewport = useRef<number[]>([]);
+	const lineResults = useRef<Map<number, any>>(new Map());
+
+	// free the wasm memory on unmount, and forget what it evaluated so a remount starts fresh
+	useEffect(() => () => {
+		evaluator.current?.free();
+		evaluator.current = null;
+		evaluatedLines.current = [];
+		evaluatedViewport.current = [];
+		lineResults.current.clear();
+	}, []);
+
 	// update answers and graphs when equations change
 	useEffect(() => {
 
@@ -62,26 +82,80 @@ function App() {
 			return;
 		}
 
-		let evaluator = new Evaluator();
-		let data = [...equations];
+		if (!evaluator.current) {
+			evaluator.current = new Evaluator();
+		}
+
+		let e = evaluator.current;
+
+		const storeResults = (results: any[]) => {
+			results.forEach(result => lineResults.current.set(result.id, result));
+		};
+
+		let viewport = [minX, maxX, minY, maxY];
+		if (viewport.some((v, i) => v !== evaluatedViewport.current[i])) {
+			storeResults(e.set_viewport(minX, maxX, minY, maxY));
+			evaluatedViewport.current = viewport;
+		}
+
+		equations.forEach((eq, i) => {
+			if (eq !== evaluatedLines.current[i]) {
+				storeResults(e.set_line(i, eq));
+			}
+		});
+
+		for (let i = equations.length; i < evaluatedLines.current.length; i++) {
+			storeResults(e.remove_line(i));
+			lineResults.current.delete(i);
+		}
+
+		evaluatedLines.current = [...equations];
 
 		let new_answers: any[] = [];
 		let new_graphs: any[] = [];
+		let new_dashed: any[] = [];
+		let new_regions: any[] = [];
+		let new_points: number[][] = [];
 
-		data.forEach(eq => {
-			let e = evaluator.evaluate(eq, minX, maxX);
+		equations.forEach((_, i) => {
+			let response = lineResults.current.get(i)?.response;
 
-			if (!e) {
+			if (!response) {
+				new_answers.push(undefined);
+			} else if (response.type == "Graph") {
+				// each segment is drawn separately so the line breaks where the graph is undefined
+				response.segments.forEach((segment: number[][]) => {
+					new_graphs.push(segment);
+				});
 				new_answers.push(undefined);
-			} else if (e.type == "Graph") {
-				new_graphs.push(e.points);
+			} else if (response.type == "Region") {
+				new_regions.push({raster: response.raster, columns: response.columns, rows: response.rows});
+
+				// strict boundaries aren't part of the region so they are dashed
+				response.boundaries.forEach((boundary: any) => {
+					boundary.segments.forEach((segment: number[][]) => {
+						(boundary.strict ? new_dashed : new_graphs).push(segment);
+					});
+				});
 				new_answers.push(undefined);
+			} else if (response.type == "Scatter") {
+				new_points.push(...response.points);
+				new_answers.push(undefined);
+			} else if (response.type == "Fit") {
+				new_answers.push({names: response.names, parameters: response.parameters, r_squared: response.r_squared, formatted: response.formatted});
+			} else if (response.type == "List") {
+				new_answers.push({values: response.values, formatted: response.formatted, var_name: response.var_name});
+			} else if (response.type == "Matrix") {
+				new_answers.push({rows: response.rows, formatted: response.formatted, var_name: response.var_name});
+			} else if (response.type == "Decomposition") {
+				new_answers.push({decomposition: response.decomposition, formatted: response.formatted, var_name: response.var_name});
 			} else {
-				new_answers.push({value: e.value, var_name: e.var_name});
+				// response.slider holds the range for variables defined like a = 2 {0..10 step 0.5}
+				new_answers.push({value: response.value, imaginary: response.imaginary, fraction: response.fraction, mixed: response.mixed, precise: response.precise, formatted: response.formatted, unit: response.unit, var_name: response.var_name, slider: response.slider});
 			}
 		});
 
-		let raw_intercept_list = evaluator.find_intercepts(eq1, eq2, minX, maxX);
+		let raw_intercept_list = e.find_intercepts(eq1, eq2, minX, maxX);
 
 		if (raw_intercept_list && raw_intercept_list.length > 0) {
 			let raw_intercept_list2 = [...raw_intercept_list];
@@ -102,14 +176,14 @@ function App() {
 
 		setAnswers(new_answers);
 		setGraphs(new_graphs);
+		setDashed(new_dashed);
+		setRegions(new_regions);
+		setPoints(new_points);
 
-		let graph_name_list: String[] = [...evaluator.get_graph_names()].map((x) => String.fromCharCode(x));
+		let graph_name_list: String[] = [...e.get_graph_names()].map((x) => String.fromCharCode(x));
 		setGraphNames(graph_name_list);
 
-		// to avoid memory leaks as wasm does not automatically free structs
-		evaluator.free();
-
-	}, [equations, eq1, eq2, minX, maxX, wasmLoaded]);
+	}, [equations, eq1, eq2, minX, maxX, minY, maxY, wasmLoaded]);
 
   return (
 	<>
@@ -130,7 +204,10 @@ function App() {
 				<Graph
 
 					graphs={graphs}
+					dashed={dashed}
+					regions={regions}
 					intercepts={intercepts}
+					points={points}
 
 					minX={minX}
 					maxX={maxX}
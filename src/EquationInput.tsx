@@ -52,7 +52,20 @@ function EquationInput({
 
 				{a &&
 				<div className="result">
-					<span>{a.var_name} = {a.value.toFixed(4)}</span>
+					{a.parameters ?
+						<span>{a.formatted}</span> :
+					a.rows ?
+						<span>{a.var_name} = {a.formatted}</span> :
+					a.decomposition ?
+						<span>{a.var_name} {a.formatted}</span> :
+					a.values ?
+						<span>{a.var_name} = {a.formatted}</span> :
+					a.precise != null ?
+						<span>{a.var_name} = {a.precise}</span> :
+					a.fraction != null ?
+						<span>{a.var_name} = {a.mixed ?? a.fraction}</span> :
+						<span>{a.var_name} = {a.formatted}{a.unit != null ? ` ${a.unit}` : ""}</span>
+					}
 				</div>
 				}
 			</div>
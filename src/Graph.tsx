@@ -7,7 +7,10 @@ import { evaluate_graph } from "./wasm-graph-calc/pkg/wasm_graph_calc.js";
 
 function Graph({
 	graphs,
+	dashed,
+	regions,
 	intercepts,
+	points,
 
 	minX,
 	maxX,
@@ -16,7 +19,10 @@ function Graph({
 	maxY,
 }: {
 	graphs: any[],
+	dashed: any[],
+	regions: any[],
 	intercepts: number[][],
+	points: number[][],
 
 	minX: number,
 	maxX: number,
@@ -109,7 +115,12 @@ function Graph({
 		d3.selectAll(".plotted_line")
 			.remove();
 
-		graphs.forEach(g => {
+		const drawn = [
+			...graphs.map(g => [g, false]),
+			...dashed.map(g => [g, true])
+		];
+
+		drawn.forEach(([g, isDashed]) => {
 			let adjusted_values: number[][] = [];
 
 			g.forEach((point: number[]) => {
@@ -134,10 +145,50 @@ function Graph({
 					.attr('transform', `translate(${margin_width}, ${margin_height})`)
 					.attr('fill', 'none')
 					.attr("stroke", "black")
-					.attr("stroke-width", 1.5);	
+					.attr("stroke-width", 1.5)
+					.attr("stroke-dasharray", isDashed ? "6 4" : null);
+		});
+
+	}, [graphs, dashed, minX, maxX, minY, maxY]);
+
+	// shade the cells of each inequality's region, the raster goes row by row from the bottom
+	useEffect(() => {
+
+		d3.selectAll(".region")
+			.remove();
+
+		regions.forEach(region => {
+			let cell_width = inner_width / region.columns;
+			let cell_height = inner_height / region.rows;
+
+			for (let row = 0; row < region.rows; row++) {
+				let column = 0;
+
+				// a run of shaded cells along a row is one rectangle
+				while (column < region.columns) {
+					let run = 0;
+					while (column + run < region.columns && region.raster[row * region.columns + column + run]) {
+						run++;
+					}
+
+					if (run > 0) {
+						d3.select("#svgMain")
+							.insert("rect", ":first-child")
+								.attr('class', 'region')
+								.attr('x', column * cell_width + margin_width)
+								.attr('y', inner_height - (row + 1) * cell_height + margin_height)
+								.attr('width', run * cell_width)
+								.attr('height', cell_height)
+								.style('fill', 'steelblue')
+								.style('fill-opacity', 0.25);
+					}
+
+					column += Math.max(run, 1);
+				}
+			}
 		});
 
-	}, [graphs, minX, maxX, minY, maxY]);
+	}, [regions, minX, maxX, minY, maxY]);
 
 	// update intercept points when intercepts change
 	useEffect(() => {
@@ -146,9 +197,10 @@ function Graph({
 		}
 
 		d3.select('#svgMain')
-			.selectAll('circle')
+			.selectAll('circle.intercept')
 			.data(intercepts)
 			.join('circle')
+			.attr('class', 'intercept')
 			.attr('cx', function(i) {
 				return xScale(i[0]) + margin_width;
 			})
@@ -160,6 +212,26 @@ function Graph({
 
 	}, [intercepts, minX, maxX, minY, maxY])
 
+	// update scatter points when lists change
+	useEffect(() => {
+		let visible = points.filter(p => p[0] >= minX && p[0] <= maxX && p[1] >= minY && p[1] <= maxY);
+
+		d3.select('#svgMain')
+			.selectAll('circle.scatter')
+			.data(visible)
+			.join('circle')
+			.attr('class', 'scatter')
+			.attr('cx', function(p) {
+				return xScale(p[0]) + margin_width;
+			})
+			.attr('cy', function(p) {
+				return yScale(p[1]) + margin_height;
+			})
+			.attr('r', 3)
+			.style('fill', 'steelblue');
+
+	}, [points, minX, maxX, minY, maxY])
+
 	
 
 	
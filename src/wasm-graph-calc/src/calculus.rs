@@ -1,4 +1,5 @@
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
 
 #[wasm_bindgen]
 extern "C" {
@@ -40,3 +41,183 @@ pub fn integrate<F>(f: F, start: f64, end: f64, squares_amt: usize) -> f64
 
     (left_estimate + right_estimate) / 2.0
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitSide {
+    Left,
+    Right
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum LimitEstimate {
+    Value {
+        value: f64
+    },
+    PositiveInfinity,
+    NegativeInfinity,
+
+    // the function is undefined or oscillates near the point
+    DoesNotExist
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Limit {
+    pub left: LimitEstimate,
+    pub right: LimitEstimate,
+
+    // two sided limit, only exists if both sides agree
+    pub limit: LimitEstimate,
+    pub sides_agree: bool
+}
+
+// amount of times the step towards the point is halved
+const LIMIT_SAMPLES: usize = 12;
+
+// differences this small are rounding, so sides that far apart at most still agree near 0
+const LIMIT_NOISE: f64 = 1e-12;
+
+// successive steps of a diverging function shrink by no more than rounding, ln(x) takes equal steps
+const DIVERGENCE_RATIO: f64 = 1.0 - 1e-9;
+
+// accelerate a converging sequence with wynn's epsilon algorithm
+// returns the best estimate along with the difference to the estimate before it
+fn wynn_epsilon(sequence: &[f64]) -> (f64, f64) {
+    let n = sequence.len();
+
+    let mut best = sequence[n - 1];
+    let mut best_error = (sequence[n - 1] - sequence[n - 2]).abs();
+
+    let mut previous: Vec<f64> = vec![0.0; n + 1];
+    let mut current: Vec<f64> = sequence.to_vec();
+
+    for column in 1..n {
+        let mut next: Vec<f64> = Vec::with_capacity(current.len() - 1);
+
+        for k in 0..current.len() - 1 {
+            let difference = current[k + 1] - current[k];
+
+            // the column has fully converged, further columns would divide by zero
+            if difference == 0.0 || !difference.is_finite() {
+                if column % 2 == 1 {
+                    return (current[k + 1], 0.0);
+                }
+
+                return (best, best_error);
+            }
+
+            next.push(previous[k + 1] + 1.0 / difference);
+        }
+
+        previous = current;
+        current = next;
+
+        // only even columns are estimates of the limit
+        if column % 2 == 0 && current.len() >= 2 {
+            let last = current.len() - 1;
+            let error = (current[last] - current[last - 1]).abs();
+
+            if error < best_error {
+                best = current[last];
+                best_error = error;
+            }
+        }
+    }
+
+    (best, best_error)
+}
+
+// approach the point from one side by repeatedly halving the step
+pub fn one_sided_limit<F>(f: F, a: f64, side: LimitSide) -> LimitEstimate
+    where F: Fn(f64) -> f64
+{
+    let direction = match side {
+        LimitSide::Left => -1.0,
+        LimitSide::Right => 1.0
+    };
+
+    let start_step = 0.1 * a.abs().max(1.0);
+
+    let samples: Vec<f64> = (0..LIMIT_SAMPLES)
+        .map(|k| f(a + direction * start_step / 2f64.powi(k as i32)))
+        .collect();
+
+    if samples.iter().any(|y| y.is_nan()) {
+        return LimitEstimate::DoesNotExist;
+    }
+
+    if let Some(infinite) = samples.iter().find(|y| y.is_infinite()) {
+        return if *infinite > 0.0 {
+            LimitEstimate::PositiveInfinity
+        } else {
+            LimitEstimate::NegativeInfinity
+        };
+    }
+
+    // a diverging function grows in size and keeps moving at least as fast in the same direction,
+    // where a converging one slows down as the step shrinks, however slowly, like x^0.1
+    let tail = &samples[LIMIT_SAMPLES - 6..];
+    let differences: Vec<f64> = tail.windows(2)
+        .map(|w| w[1] - w[0])
+        .collect();
+
+    let growing = tail.windows(2).all(|w| w[1].abs() > w[0].abs());
+
+    let diverging = growing && differences.windows(2)
+        .all(|d| d[0] != 0.0 && d[0].signum() == d[1].signum() && d[1].abs() >= DIVERGENCE_RATIO * d[0].abs());
+
+    if diverging {
+        return if differences[0] > 0.0 {
+            LimitEstimate::PositiveInfinity
+        } else {
+            LimitEstimate::NegativeInfinity
+        };
+    }
+
+    let (estimate, error) = wynn_epsilon(&samples);
+
+    if !estimate.is_finite() || error > 1e-6 * estimate.abs().max(1.0) {
+        return LimitEstimate::DoesNotExist;
+    }
+
+    // remove floating point noise from limits that land on an integer,
+    // but only when the estimate is too uncertain to tell it apart from the integer
+    let uncertainty = error.max(LIMIT_NOISE * estimate.abs().max(1.0));
+
+    let value = if (estimate - estimate.round()).abs() <= uncertainty {
+        estimate.round()
+    } else {
+        estimate
+    };
+
+    LimitEstimate::Value { value }
+}
+
+pub fn limit<F>(f: F, a: f64) -> Limit
+    where F: Fn(f64) -> f64
+{
+    let left = one_sided_limit(&f, a, LimitSide::Left);
+    let right = one_sided_limit(&f, a, LimitSide::Right);
+
+    let sides_agree = match (left, right) {
+        // compared by their size, so that a small jump such as ±1e-7 still counts
+        (LimitEstimate::Value { value: l }, LimitEstimate::Value { value: r }) =>
+            (l - r).abs() <= 1e-6 * l.abs().max(r.abs()) + LIMIT_NOISE,
+
+        (LimitEstimate::PositiveInfinity, LimitEstimate::PositiveInfinity) |
+        (LimitEstimate::NegativeInfinity, LimitEstimate::NegativeInfinity) => true,
+
+        _ => false
+    };
+
+    let limit = match (left, right) {
+        _ if !sides_agree => LimitEstimate::DoesNotExist,
+
+        (LimitEstimate::Value { value: l }, LimitEstimate::Value { value: r }) =>
+            LimitEstimate::Value { value: (l + r) / 2.0 },
+
+        (estimate, _) => estimate
+    };
+
+    Limit { left, right, limit, sides_agree }
+}
@@ -0,0 +1,157 @@
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+use serde::{Serialize, Deserialize};
+
+// the largest whole power worked out by repeated multiplication, so (-2)^2 stays exactly 4
+const MAX_WHOLE_POWER: f64 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64
+}
+
+pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im == 0.0
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    // in (-pi, pi], so negative real numbers give pi even when the imaginary part is -0
+    pub fn arg(&self) -> f64 {
+        match self.is_real() {
+            true => 0.0_f64.atan2(self.re),
+            false => self.im.atan2(self.re)
+        }
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn exp(&self) -> Complex {
+        let r = self.re.exp();
+
+        Complex::new(r * self.im.cos(), r * self.im.sin())
+    }
+
+    // principal branch, with the cut along the negative real axis
+    pub fn ln(&self) -> Complex {
+        Complex::new(self.abs().ln(), self.arg())
+    }
+
+    // principal root, with a non negative real part
+    pub fn sqrt(&self) -> Complex {
+        let r = self.abs();
+
+        let re = ((r + self.re) / 2.0).sqrt();
+        let im = ((r - self.re) / 2.0).sqrt();
+
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    pub fn sin(&self) -> Complex {
+        Complex::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh())
+    }
+
+    pub fn cos(&self) -> Complex {
+        Complex::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh())
+    }
+
+    pub fn tan(&self) -> Complex {
+        self.sin() / self.cos()
+    }
+
+    // principal value of self^power, exp(power * ln(self))
+    pub fn pow(&self, power: Complex) -> Complex {
+        if power.is_real() && power.re.fract() == 0.0 && power.re.abs() <= MAX_WHOLE_POWER {
+            let mut result = Complex::from(1.0);
+
+            for _ in 0..power.re.abs() as u32 {
+                result = result * *self;
+            }
+
+            return match power.re < 0.0 {
+                true => Complex::from(1.0) / result,
+                false => result
+            };
+        }
+
+        if self.re == 0.0 && self.im == 0.0 {
+            return match power.re > 0.0 {
+                true => Complex::from(0.0),
+                false => Complex::new(f64::NAN, f64::NAN)
+            };
+        }
+
+        (power * self.ln()).exp()
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Complex {
+        Complex::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        // real divisors keep the signs of zeros and infinities the same as f64
+        if other.is_real() {
+            return Complex::new(self.re / other.re, self.im / other.re);
+        }
+
+        let denominator = other.re * other.re + other.im * other.im;
+
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
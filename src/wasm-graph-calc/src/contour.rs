@@ -0,0 +1,299 @@
+use std::collections::hash_map::HashMap;
+use std::collections::HashSet;
+
+// an edge of the fine grid, the point (i, j) and whether it runs along x
+type EdgeKey = (usize, usize, bool);
+
+const CROSSING_ITERATIONS: usize = 4;
+
+struct Grid<F>
+    where F: Fn(f64, f64) -> f64
+{
+    f: F,
+    values: HashMap<(usize, usize), f64>,
+
+    min_x: f64,
+    min_y: f64,
+    dx: f64,
+    dy: f64
+}
+
+impl<F> Grid<F>
+    where F: Fn(f64, f64) -> f64
+{
+    fn position(&self, i: usize, j: usize) -> [f64; 2] {
+        [self.min_x + i as f64 * self.dx, self.min_y + j as f64 * self.dy]
+    }
+
+    // every corner is only ever evaluated once, so shared edges line up exactly
+    fn value(&mut self, i: usize, j: usize) -> f64 {
+        if let Some(v) = self.values.get(&(i, j)) {
+            return *v;
+        }
+
+        let [x, y] = self.position(i, j);
+        let v = (self.f)(x, y);
+
+        self.values.insert((i, j), v);
+        v
+    }
+
+    // where the function crosses zero along an edge, if it really does
+    fn crossing(&mut self, edge: EdgeKey) -> Option<[f64; 2]> {
+        let (i, j, along_x) = edge;
+        let (i2, j2) = if along_x { (i + 1, j) } else { (i, j + 1) };
+
+        let a = self.value(i, j);
+        let b = self.value(i2, j2);
+
+        let pa = self.position(i, j);
+        let pb = self.position(i2, j2);
+
+        let along = |t: f64| [pa[0] + t * (pb[0] - pa[0]), pa[1] + t * (pb[1] - pa[1])];
+
+        // linear interpolation, tightened with a few steps of false position
+        let (mut t0, mut v0) = (0.0, a);
+        let (mut t1, mut v1) = (1.0, b);
+
+        let mut t = t0 - v0 * (t1 - t0) / (v1 - v0);
+        let mut at_point = f64::NAN;
+
+        for _ in 0..CROSSING_ITERATIONS {
+            let point = along(t);
+            at_point = (self.f)(point[0], point[1]);
+
+            if !at_point.is_finite() || at_point == 0.0 {
+                break;
+            }
+
+            if (at_point > 0.0) == (v0 > 0.0) {
+                t0 = t;
+                v0 = at_point;
+            } else {
+                t1 = t;
+                v1 = at_point;
+            }
+
+            t = t0 - v0 * (t1 - t0) / (v1 - v0);
+        }
+
+        // a sign change across an asymptote, such as y = 1/x at x = 0, is not a crossing
+        if at_point.is_nan() || at_point.abs() > 0.5 * a.abs().max(b.abs()) {
+            return None;
+        }
+
+        Some(along(t))
+    }
+
+    // marching squares on a single fine cell, returns the edges joined by each line
+    fn cell_segments(&mut self, i: usize, j: usize) -> Vec<(EdgeKey, EdgeKey)> {
+        let corners = [
+            self.value(i, j),
+            self.value(i + 1, j),
+            self.value(i + 1, j + 1),
+            self.value(i, j + 1)
+        ];
+
+        if corners.iter().any(|v| !v.is_finite()) {
+            return Vec::new();
+        }
+
+        // edges in order bottom, right, top, left, each between consecutive corners
+        let edges: [EdgeKey; 4] = [
+            (i, j, true),
+            (i + 1, j, false),
+            (i, j + 1, true),
+            (i, j, false)
+        ];
+
+        let positive: Vec<bool> = corners.iter()
+            .map(|v| *v > 0.0)
+            .collect();
+
+        let crossed: Vec<usize> = (0..4)
+            .filter(|&e| positive[e] != positive[(e + 1) % 4])
+            .collect();
+
+        match crossed.len() {
+            2 => vec![(edges[crossed[0]], edges[crossed[1]])],
+
+            // a saddle, the centre decides which corners are cut off from the others
+            4 => {
+                let [x, y] = self.position(i, j);
+                let centre = (self.f)(x + self.dx / 2.0, y + self.dy / 2.0) > 0.0;
+
+                (0..4)
+                    .filter(|&corner| positive[corner] != centre)
+                    .map(|corner| (edges[(corner + 3) % 4], edges[corner]))
+                    .collect()
+            },
+
+            _ => Vec::new()
+        }
+    }
+}
+
+// join line pieces that share an edge into polylines
+fn join_segments(pieces: &[([f64; 2], [f64; 2], EdgeKey, EdgeKey)]) -> Vec<Vec<[f64; 2]>> {
+    let mut by_edge: HashMap<EdgeKey, Vec<usize>> = HashMap::new();
+
+    for (index, (_, _, a, b)) in pieces.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(index);
+        by_edge.entry(*b).or_default().push(index);
+    }
+
+    let mut used: Vec<bool> = vec![false; pieces.len()];
+    let mut polylines: Vec<Vec<[f64; 2]>> = Vec::new();
+
+    let next_piece = |edge: EdgeKey, used: &Vec<bool>| -> Option<usize> {
+        by_edge.get(&edge)?
+            .iter()
+            .copied()
+            .find(|&index| !used[index])
+    };
+
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+
+        used[start] = true;
+
+        let (start_a, start_b, start_edge_a, start_edge_b) = pieces[start];
+
+        // walk forwards from the end of the first piece
+        let mut forward: Vec<[f64; 2]> = vec![start_a, start_b];
+        let mut edge = start_edge_b;
+        while let Some(index) = next_piece(edge, &used) {
+            used[index] = true;
+
+            let (a, b, edge_a, edge_b) = pieces[index];
+            if edge_a == edge {
+                forward.push(b);
+                edge = edge_b;
+            } else {
+                forward.push(a);
+                edge = edge_a;
+            }
+        }
+
+        // then backwards from its start
+        let mut backward: Vec<[f64; 2]> = Vec::new();
+        let mut edge = start_edge_a;
+        while let Some(index) = next_piece(edge, &used) {
+            used[index] = true;
+
+            let (a, b, edge_a, edge_b) = pieces[index];
+            if edge_a == edge {
+                backward.push(b);
+                edge = edge_b;
+            } else {
+                backward.push(a);
+                edge = edge_a;
+            }
+        }
+
+        backward.reverse();
+        backward.extend(forward);
+
+        polylines.push(backward);
+    }
+
+    polylines
+}
+
+// trace f(x, y) = 0 across the viewport with marching squares
+// the viewport is split into cells along each axis, and any cell the curve passes
+// near is subdivided 2^refinement times along each axis before tracing
+pub fn trace_contour<F>(
+    f: F,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    cells: usize,
+    refinement: u32
+) -> Vec<Vec<[f64; 2]>>
+    where F: Fn(f64, f64) -> f64
+{
+    let cells = cells.max(1);
+    let subdivisions: usize = 1 << refinement;
+    let fine_cells = cells * subdivisions;
+
+    let mut grid = Grid {
+        f,
+        values: HashMap::new(),
+
+        min_x,
+        min_y,
+        dx: (max_x - min_x) / fine_cells as f64,
+        dy: (max_y - min_y) / fine_cells as f64
+    };
+
+    // find the coarse cells the curve could pass through
+    let mut near_curve: HashSet<(usize, usize)> = HashSet::new();
+
+    for ci in 0..cells {
+        for cj in 0..cells {
+            let i = ci * subdivisions;
+            let j = cj * subdivisions;
+            let half = subdivisions / 2;
+
+            let samples = [
+                grid.value(i, j),
+                grid.value(i + subdivisions, j),
+                grid.value(i + subdivisions, j + subdivisions),
+                grid.value(i, j + subdivisions),
+                grid.value(i + half, j + half)
+            ];
+
+            // infinities still have a sign, which matters next to an asymptote
+            let defined: Vec<f64> = samples.iter()
+                .copied()
+                .filter(|v| !v.is_nan())
+                .collect();
+
+            let sign_change = defined.iter().any(|v| *v > 0.0) &&
+                defined.iter().any(|v| *v <= 0.0);
+
+            // the curve can hide in the detail around a pole
+            let near_pole = defined.iter().any(|v| v.is_infinite());
+
+            if !sign_change && !near_pole {
+                continue;
+            }
+
+            // neighbours as well, in case the curve dips into them between their corners
+            for ni in ci.saturating_sub(1)..=(ci + 1).min(cells - 1) {
+                for nj in cj.saturating_sub(1)..=(cj + 1).min(cells - 1) {
+                    near_curve.insert((ni, nj));
+                }
+            }
+        }
+    }
+
+    let mut pieces: Vec<([f64; 2], [f64; 2], EdgeKey, EdgeKey)> = Vec::new();
+    let mut crossings: HashMap<EdgeKey, Option<[f64; 2]>> = HashMap::new();
+
+    let mut coarse_cells: Vec<(usize, usize)> = near_curve.into_iter().collect();
+    coarse_cells.sort();
+
+    for (ci, cj) in coarse_cells {
+        for i in ci * subdivisions..(ci + 1) * subdivisions {
+            for j in cj * subdivisions..(cj + 1) * subdivisions {
+                for (edge_a, edge_b) in grid.cell_segments(i, j) {
+                    let a = *crossings.entry(edge_a)
+                        .or_insert_with(|| grid.crossing(edge_a));
+                    let b = *crossings.entry(edge_b)
+                        .or_insert_with(|| grid.crossing(edge_b));
+
+                    if let (Some(a), Some(b)) = (a, b) {
+                        pieces.push((a, b, edge_a, edge_b));
+                    }
+                }
+            }
+        }
+    }
+
+    join_segments(&pieces)
+}
@@ -0,0 +1,120 @@
+use serde::{Serialize, Deserialize};
+
+use crate::table::Table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CsvOptions {
+    pub delimiter: char,
+
+    // decimal places, or as many as needed to read the value back exactly
+    pub precision: Option<usize>,
+    pub header: bool
+}
+
+impl CsvOptions {
+    // None for a delimiter that can't be told apart from a quoted field or a line break
+    pub fn new(delimiter: char, precision: Option<usize>, header: bool) -> Option<CsvOptions> {
+        if matches!(delimiter, '"' | '\r' | '\n') {
+            return None;
+        }
+
+        Some(CsvOptions { delimiter, precision, header })
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            precision: None,
+            header: true
+        }
+    }
+}
+
+// quoted when it holds the delimiter, a quote or a line break, with quotes doubled
+fn escape_field(field: &str, delimiter: char) -> String {
+    let needs_quotes = field.contains(delimiter) ||
+        field.contains('"') ||
+        field.contains('\r') ||
+        field.contains('\n');
+
+    if needs_quotes {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// undefined values are left empty
+fn format_number(value: Option<f64>, precision: Option<usize>) -> String {
+    match (value, precision) {
+        (Some(v), Some(p)) => format!("{:.*}", p, v),
+        (Some(v), None) => v.to_string(),
+        (None, _) => String::new()
+    }
+}
+
+// RFC 4180, with every record ending in CRLF
+fn write_records(header: &[String], rows: &[Vec<String>], options: &CsvOptions) -> String {
+    let delimiter = options.delimiter.to_string();
+    let mut out = String::new();
+
+    let header = Some(header).filter(|_| options.header);
+
+    for record in header.into_iter().chain(rows.iter().map(|r| r.as_slice())) {
+        let fields: Vec<String> = record.iter()
+            .map(|field| escape_field(field, options.delimiter))
+            .collect();
+
+        out.push_str(&fields.join(&delimiter));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+pub fn write_csv(header: &[String], rows: &[Vec<Option<f64>>], options: &CsvOptions) -> String {
+    let rows: Vec<Vec<String>> = rows.iter()
+        .map(|row| row.iter().map(|v| format_number(*v, options.precision)).collect())
+        .collect();
+
+    write_records(header, &rows, options)
+}
+
+// samples of a graph, numbering the segments so breaks in the line survive
+pub fn segments_to_csv(segments: &[Vec<[f64; 2]>], options: &CsvOptions) -> String {
+    let rows: Vec<Vec<String>> = segments.iter()
+        .enumerate()
+        .flat_map(|(i, segment)| {
+            segment.iter().map(move |p| vec![
+                i.to_string(),
+                format_number(Some(p[0]), options.precision),
+                format_number(Some(p[1]), options.precision)
+            ])
+        })
+        .collect();
+
+    write_records(&["segment", "x", "y"].map(String::from), &rows, options)
+}
+
+// points laid out as [x1, y1, x2, y2...], like intercepts and turning points
+pub fn points_to_csv(points: &[f64], options: &CsvOptions) -> String {
+    let rows: Vec<Vec<Option<f64>>> = points.chunks(2)
+        .map(|p| p.iter().map(|v| Some(*v).filter(|v| v.is_finite())).collect())
+        .collect();
+
+    write_csv(&["x", "y"].map(String::from), &rows, options)
+}
+
+pub fn table_to_csv(table: &Table, options: &CsvOptions) -> String {
+    let header: Vec<String> = std::iter::once("x".to_string())
+        .chain(table.columns.iter().cloned())
+        .collect();
+
+    let rows: Vec<Vec<Option<f64>>> = table.rows.iter()
+        .map(|row| std::iter::once(Some(row.x)).chain(row.values.iter().copied()).collect())
+        .collect();
+
+    write_csv(&header, &rows, options)
+}
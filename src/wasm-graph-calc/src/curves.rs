@@ -0,0 +1,247 @@
+use std::collections::hash_map::HashMap;
+
+use crate::{lex, AngleMode, LexerTokenType, ParseTree, ParseError, EvaluateError};
+use crate::calculus::integrate;
+use crate::sampling::sample_curve;
+use crate::contour::trace_contour;
+
+// split on a separator, ignoring any inside of brackets
+pub fn split_top_level(input: &str, separator: char) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+
+    let mut bracket_depth: u32 = 0;
+
+    for c in input.chars() {
+        match c {
+            '(' | '[' | '{' => bracket_depth += 1,
+            ')' | ']' | '}' => bracket_depth = bracket_depth.saturating_sub(1),
+
+            _ => {}
+        }
+
+        if c == separator && bracket_depth == 0 {
+            parts.push(current);
+            current = String::new();
+
+            continue;
+        }
+
+        current.push(c);
+    }
+
+    parts.push(current);
+
+    parts
+}
+
+// turns "(a, b)" into ["a", "b"], only if the outer brackets wrap the whole input
+pub fn split_tuple(input: &str) -> Option<Vec<String>> {
+    let inner = input.trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    // "(a)+(b)" starts and ends with brackets but isn't a tuple
+    let mut bracket_depth: u32 = 0;
+    for c in inner.chars() {
+        match c {
+            '(' => bracket_depth += 1,
+            ')' => {
+                if bracket_depth == 0 {
+                    return None;
+                }
+
+                bracket_depth -= 1;
+            },
+
+            _ => {}
+        }
+    }
+
+    let parts = split_top_level(inner, ',');
+
+    if parts.len() < 2 {
+        return None;
+    }
+
+    Some(parts)
+}
+
+// a curve (x(t), y(t)) traced out as t moves through a range
+#[derive(Debug, Clone)]
+pub struct ParametricCurve {
+    x: ParseTree,
+    y: ParseTree
+}
+
+impl ParametricCurve {
+    pub fn new(input: &str, graphs: &HashMap<char, ParseTree>) -> Option<ParametricCurve> {
+        let parts = split_tuple(input)?;
+
+        if parts.len() != 2 {
+            return None;
+        }
+
+        let trees: Vec<ParseTree> = parts.iter()
+            .map(|part| {
+                let tokens = lex(part).map_err(|_| ParseError)?;
+                ParseTree::new(&tokens, graphs)
+            })
+            .collect::<Result<Vec<ParseTree>, ParseError>>()
+            .ok()?;
+
+        let mut trees = trees.into_iter();
+
+        Some(ParametricCurve {
+            x: trees.next()?,
+            y: trees.next()?
+        })
+    }
+
+    pub fn references(&self, name: char) -> bool {
+        self.x.references(name) || self.y.references(name)
+    }
+
+    pub fn evaluate(&self, t: f64, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<[f64; 2], EvaluateError> {
+        let mut inner_vars = vars.clone();
+        inner_vars.insert('t', t);
+
+        Ok([
+            self.x.evaluate(None, &inner_vars, angle)?,
+            self.y.evaluate(None, &inner_vars, angle)?
+        ])
+    }
+
+    pub fn sample(
+        &self,
+        vars: &HashMap<char, f64>,
+        angle: AngleMode,
+        t_min: f64,
+        t_max: f64,
+        samples: usize,
+        tolerance: f64
+    ) -> Vec<Vec<[f64; 2]>> {
+        sample_curve(|t: f64| self.evaluate(t, vars, angle).ok(), t_min, t_max, samples, tolerance)
+    }
+}
+
+// a curve r = f(θ) around the origin
+#[derive(Debug, Clone)]
+pub struct PolarCurve {
+    r: ParseTree
+}
+
+impl PolarCurve {
+    pub fn new(input: &str, graphs: &HashMap<char, ParseTree>) -> Option<PolarCurve> {
+        let tokens = lex(input).ok()?;
+        let r = ParseTree::new(&tokens, graphs).ok()?;
+
+        Some(PolarCurve { r })
+    }
+
+    pub fn references(&self, name: char) -> bool {
+        self.r.references(name)
+    }
+
+    pub fn radius(&self, theta: f64, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<f64, EvaluateError> {
+        let mut inner_vars = vars.clone();
+        // θ is given in radians, but written in whichever unit trig functions expect
+        inner_vars.insert('θ', theta / angle.radians_per_unit());
+
+        self.r.evaluate(None, &inner_vars, angle)
+    }
+
+    pub fn evaluate(&self, theta: f64, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<[f64; 2], EvaluateError> {
+        let r = self.radius(theta, vars, angle)?;
+
+        Ok([r * theta.cos(), r * theta.sin()])
+    }
+
+    pub fn sample(
+        &self,
+        vars: &HashMap<char, f64>,
+        angle: AngleMode,
+        theta_min: f64,
+        theta_max: f64,
+        samples: usize,
+        tolerance: f64
+    ) -> Vec<Vec<[f64; 2]>> {
+        sample_curve(|theta: f64| self.evaluate(theta, vars, angle).ok(), theta_min, theta_max, samples, tolerance)
+    }
+
+    // area swept out between two angles, 1/2 ∫ r² dθ
+    pub fn area(&self, vars: &HashMap<char, f64>, angle: AngleMode, theta_start: f64, theta_end: f64) -> f64 {
+        let f = |theta: f64| match self.radius(theta, vars, angle) {
+            Ok(r) if r.is_finite() => r * r,
+            _ => 0.0
+        };
+
+        integrate(f, theta_start, theta_end, 10000) / 2.0
+    }
+}
+
+// a relation between x and y such as x^2 + y^2 = 25, drawn wherever both sides are equal
+#[derive(Debug, Clone)]
+pub struct ImplicitCurve {
+    lhs: ParseTree,
+    rhs: ParseTree
+}
+
+impl ImplicitCurve {
+    // None when a name other than y isn't a variable, rather than tracing nothing
+    pub fn new(lhs: &str, rhs: &str, graphs: &HashMap<char, ParseTree>, vars: &HashMap<char, f64>) -> Option<ImplicitCurve> {
+        let lhs_tokens = lex(lhs).ok()?;
+        let rhs_tokens = lex(rhs).ok()?;
+
+        // without x or y there is nothing to draw
+        let mentions_xy = lhs_tokens.iter()
+            .chain(rhs_tokens.iter())
+            .any(|t| matches!(t.token_type, LexerTokenType::X | LexerTokenType::Var('y')));
+
+        if !mentions_xy {
+            return None;
+        }
+
+        let lhs = ParseTree::new(&lhs_tokens, graphs).ok()?;
+        let rhs = ParseTree::new(&rhs_tokens, graphs).ok()?;
+
+        let defined = lhs.names().iter()
+            .chain(rhs.names().iter())
+            .all(|name| *name == 'y' || vars.contains_key(name));
+
+        if !defined {
+            return None;
+        }
+
+        Some(ImplicitCurve { lhs, rhs })
+    }
+
+    // lhs - rhs, which is zero on the curve
+    pub fn evaluate(&self, x: f64, y: f64, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<f64, EvaluateError> {
+        let mut inner_vars = vars.clone();
+        inner_vars.insert('y', y);
+
+        Ok(self.lhs.evaluate(Some(x), &inner_vars, angle)? - self.rhs.evaluate(Some(x), &inner_vars, angle)?)
+    }
+
+    pub fn trace(
+        &self,
+        vars: &HashMap<char, f64>,
+        angle: AngleMode,
+        viewport: [f64; 4],
+        cells: usize,
+        refinement: u32
+    ) -> Vec<Vec<[f64; 2]>> {
+        let [min_x, max_x, min_y, max_y] = viewport;
+
+        trace_contour(
+            |x: f64, y: f64| self.evaluate(x, y, vars, angle).unwrap_or(f64::NAN),
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            cells,
+            refinement
+        )
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+use crate::{builtin_functions, check_numbers, lex, split_definition, split_domain, LexError, LexerToken, LexerTokenType};
+use crate::region::is_inequality;
+use crate::statistics::STATISTICS;
+
+// why a line could not be evaluated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DefinitionError {
+    // the names that depend on each other in a loop
+    Circular { names: Vec<String> },
+
+    // another line defines the same name
+    Duplicate { name: String },
+
+    // a number like 1.2.3, from start up to end by character in the line
+    MalformedNumber { literal: String, start: usize, end: usize },
+
+    // units that can't be added or compared, like kg and m
+    IncompatibleUnits { left: String, right: String },
+
+    // matrices of the wrong shape, or a singular matrix that was inverted
+    Matrix { reason: String },
+
+    // i can't be given a value while it is the imaginary unit
+    ImaginaryUnit,
+
+    Invalid
+}
+
+impl std::fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinitionError::Circular { names } => write!(f, "circular definition of {}", names.join(", ")),
+            DefinitionError::Duplicate { name } => write!(f, "{} is defined more than once", name),
+            DefinitionError::MalformedNumber { literal, start, end } => write!(f, "malformed number {} at {}..{}", literal, start, end),
+            DefinitionError::IncompatibleUnits { left, right } => write!(f, "can't combine {} with {}", left, right),
+            DefinitionError::Matrix { reason } => write!(f, "{}", reason),
+            DefinitionError::ImaginaryUnit => write!(f, "i is the imaginary unit in complex mode"),
+            DefinitionError::Invalid => write!(f, "invalid expression")
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+// why a line that gave nothing back is invalid, pointing at a malformed number when there is one
+pub fn invalid_reason(input: &str) -> DefinitionError {
+    // a slider range like {0..10} isn't a number
+    let (definition, _) = split_domain(input);
+
+    match check_numbers(definition) {
+        Err(LexError::MalformedNumber { literal, start, end }) => DefinitionError::MalformedNumber { literal, start, end },
+        _ => DefinitionError::Invalid
+    }
+}
+
+// every single letter name an expression uses, leaving out built in function names
+// so sin(a) gives a, and the coordinates x and y which are never definitions
+pub fn referenced_names(input: &str) -> HashSet<char> {
+    let mut names: HashSet<char> = HashSet::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_alphabetic() {
+            continue;
+        }
+
+        let mut word: String = c.to_string();
+        while let Some(next) = chars.peek() {
+            if !next.is_ascii_alphabetic() {
+                break;
+            }
+
+            word.push(*next);
+            chars.next();
+        }
+
+        // the same way the lexer splits ab sin( into a, b and sin
+        if chars.peek() == Some(&'(') {
+            if let Some(pos) = builtin_functions().filter_map(|fun| word.find(fun)).min() {
+                word.truncate(pos);
+            }
+        }
+
+        names.extend(word.chars());
+    }
+
+    names.remove(&'x');
+    names.remove(&'y');
+
+    names
+}
+
+// whether x or y appear as coordinates, so not as the variable of int or lim, or a statistic
+// of a list, which all give a single number like max(L) or int(f(x), 0, 1)
+fn uses_coordinates(tokens: &[LexerToken]) -> bool {
+    tokens.iter().any(|token| match &token.token_type {
+        LexerTokenType::X | LexerTokenType::Var('y') => true,
+
+        LexerTokenType::Func(_, name) if name == "int" || name == "lim" || STATISTICS.contains(&name.as_str()) => false,
+        LexerTokenType::Func(args, _) | LexerTokenType::List(args) => args.iter().any(|arg| uses_coordinates(arg)),
+
+        LexerTokenType::Piecewise(branches) => branches.iter().any(|(condition, value)| {
+            condition.as_ref().is_some_and(|c| uses_coordinates(c)) || uses_coordinates(value)
+        }),
+
+        LexerTokenType::Index(_, tokens) | LexerTokenType::Call(_, tokens) => uses_coordinates(tokens),
+
+        _ => false
+    })
+}
+
+// a single line of the equation list, along with the name it defines and what it uses
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub input: String,
+    pub name: Option<char>,
+    pub dependencies: HashSet<char>
+}
+
+impl Definition {
+    pub fn new(input: &str) -> Definition {
+        let parts = split_definition(input);
+
+        let other = Definition {
+            input: input.to_string(),
+            name: None,
+            dependencies: referenced_names(input)
+        };
+
+        if parts.len() != 2 || is_inequality(input) {
+            return other;
+        }
+
+        let lhs: String = parts[0].chars().filter(|c| *c != ' ').collect();
+        let rhs = parts[1];
+
+        let fn_re = Regex::new(r"^([a-zA-Z])\((x|t|θ)\)$")
+            .expect("regex failed");
+
+        if let Some(captures) = fn_re.captures(&lhs) {
+            let name = captures[1].chars().next();
+            let mut dependencies = referenced_names(rhs);
+
+            // the parameter of a parametric curve isn't a variable
+            if &captures[2] == "t" {
+                dependencies.remove(&'t');
+            }
+
+            return Definition { input: input.to_string(), name, dependencies };
+        }
+
+        if lhs == "r" && rhs.contains('θ') {
+            return Definition {
+                input: input.to_string(),
+                name: Some('r'),
+                dependencies: referenced_names(rhs)
+            };
+        }
+
+        let var_re = Regex::new(r"^[a-wzA-Z]$")
+            .expect("regex failed");
+
+        // a = x^2 is an implicit curve rather than a variable
+        let (definition, range) = split_domain(rhs);
+        let has_coordinates = lex(definition).is_ok_and(|tokens| uses_coordinates(&tokens));
+
+        if var_re.is_match(&lhs) && !has_coordinates {
+            let mut dependencies = referenced_names(definition);
+
+            if let Some(range) = range {
+                dependencies.extend(referenced_names(&range.replace("step", " ")));
+            }
+
+            return Definition {
+                input: input.to_string(),
+                name: lhs.chars().next(),
+                dependencies
+            };
+        }
+
+        other
+    }
+}
+
+// the order to evaluate lines in so that every definition comes after the ones it uses,
+// along with the lines that can't be evaluated at all
+pub fn evaluation_order(lines: &BTreeMap<u32, Definition>) -> (Vec<u32>, HashMap<u32, DefinitionError>) {
+    let mut errors: HashMap<u32, DefinitionError> = HashMap::new();
+
+    let mut definers: HashMap<char, Vec<u32>> = HashMap::new();
+    for (id, line) in lines {
+        if let Some(name) = line.name {
+            definers.entry(name).or_default().push(*id);
+        }
+    }
+
+    for (name, ids) in &definers {
+        if ids.len() > 1 {
+            for id in ids {
+                errors.insert(*id, DefinitionError::Duplicate { name: name.to_string() });
+            }
+        }
+    }
+
+    // the lines each line uses
+    let uses = |id: u32| -> Vec<u32> {
+        let mut used: Vec<u32> = lines[&id].dependencies.iter()
+            .filter_map(|name| definers.get(name))
+            .flatten()
+            .copied()
+            .collect();
+
+        used.sort_unstable();
+        used
+    };
+
+    // every line each line leads to by following what it uses
+    let reachable: HashMap<u32, HashSet<u32>> = lines.keys()
+        .map(|id| {
+            let mut seen: HashSet<u32> = HashSet::new();
+            let mut stack: Vec<u32> = uses(*id);
+
+            while let Some(next) = stack.pop() {
+                if seen.insert(next) {
+                    stack.extend(uses(next));
+                }
+            }
+
+            (*id, seen)
+        })
+        .collect();
+
+    // lines that lead back to themselves are circular
+    for (id, seen) in &reachable {
+        if !seen.contains(id) {
+            continue;
+        }
+
+        let mut names: Vec<String> = seen.iter()
+            .filter(|other| reachable[*other].contains(id))
+            .filter_map(|other| lines[other].name)
+            .map(|c| c.to_string())
+            .collect();
+
+        names.sort();
+        names.dedup();
+
+        errors.entry(*id).or_insert(DefinitionError::Circular { names });
+    }
+
+    // depth first, with each line placed after everything it uses
+    let mut order: Vec<u32> = Vec::new();
+    let mut placed: HashSet<u32> = HashSet::new();
+
+    for id in lines.keys().copied() {
+        let mut stack: Vec<(u32, bool)> = vec![(id, false)];
+
+        while let Some((next, expanded)) = stack.pop() {
+            if placed.contains(&next) || errors.contains_key(&next) {
+                continue;
+            }
+
+            if expanded {
+                placed.insert(next);
+                order.push(next);
+                continue;
+            }
+
+            stack.push((next, true));
+
+            for used in uses(next) {
+                if !placed.contains(&used) {
+                    stack.push((used, false));
+                }
+            }
+        }
+    }
+
+    (order, errors)
+}
@@ -0,0 +1,165 @@
+use serde::{Serialize, Deserialize};
+
+// significant figures shown by auto, which switches to scientific outside of the plain range
+const AUTO_FIGURES: usize = 10;
+const AUTO_PLAIN_MAX: f64 = 1e10;
+const AUTO_PLAIN_MIN: f64 = 1e-6;
+
+// integers above this can't all be held exactly by an f64
+const MAX_EXACT_INTEGER: f64 = 9007199254740992.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum NumberFormat {
+    #[default]
+    Auto,
+
+    // a set number of digits after the point
+    Fixed { decimals: usize },
+
+    Significant { figures: usize },
+    Scientific { figures: usize },
+
+    // scientific with the exponent a multiple of 3
+    Engineering { figures: usize },
+
+    // integers in another base, anything else is shown as auto
+    Hex,
+    Binary,
+    Octal
+}
+
+impl NumberFormat {
+    // the digits are the decimals or figures for the modes that need them
+    pub fn parse(mode: &str, digits: Option<usize>) -> Option<NumberFormat> {
+        Some(match (mode.trim().to_lowercase().as_str(), digits) {
+            ("auto", None) => NumberFormat::Auto,
+            ("fixed", Some(decimals)) => NumberFormat::Fixed { decimals },
+            ("significant", Some(figures)) if figures > 0 => NumberFormat::Significant { figures },
+            ("scientific", Some(figures)) if figures > 0 => NumberFormat::Scientific { figures },
+            ("engineering", Some(figures)) if figures > 0 => NumberFormat::Engineering { figures },
+            ("hex", None) => NumberFormat::Hex,
+            ("binary", None) => NumberFormat::Binary,
+            ("octal", None) => NumberFormat::Octal,
+
+            _ => {
+                return None;
+            }
+        })
+    }
+}
+
+// the mantissa and exponent after rounding to the significant figures, so 9.99 to 2 figures is 1.0 and 1
+fn rounded_parts(value: f64, figures: usize) -> (f64, i32) {
+    let scientific = format!("{:.*e}", figures.max(1) - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+
+    (mantissa.parse().unwrap(), exponent.parse().unwrap())
+}
+
+fn trim_zeros(number: String) -> String {
+    match number.contains('.') {
+        true => number.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => number
+    }
+}
+
+fn significant(value: f64, figures: usize) -> String {
+    let figures = figures.max(1);
+    let (_, exponent) = rounded_parts(value, figures);
+
+    let decimals = (figures as i32 - 1 - exponent).max(0) as usize;
+    let rounded: f64 = format!("{:.*e}", figures - 1, value).parse().unwrap();
+
+    format!("{:.*}", decimals, rounded)
+}
+
+fn scientific(value: f64, figures: usize) -> String {
+    let (mantissa, exponent) = rounded_parts(value, figures);
+
+    format!("{:.*}e{}", figures.max(1) - 1, mantissa, exponent)
+}
+
+fn engineering(value: f64, figures: usize) -> String {
+    let (mantissa, exponent) = rounded_parts(value, figures);
+    let shift = exponent.rem_euclid(3);
+
+    let decimals = (figures.max(1) as i32 - 1 - shift).max(0) as usize;
+
+    format!("{:.*}e{}", decimals, mantissa * 10f64.powi(shift), exponent - shift)
+}
+
+fn in_base(value: f64, format: NumberFormat) -> Option<String> {
+    if value.fract() != 0.0 || value.abs() > MAX_EXACT_INTEGER {
+        return None;
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let magnitude = value.abs() as u64;
+
+    Some(match format {
+        NumberFormat::Hex => format!("{}0x{:X}", sign, magnitude),
+        NumberFormat::Binary => format!("{}0b{:b}", sign, magnitude),
+        NumberFormat::Octal => format!("{}0o{:o}", sign, magnitude),
+
+        _ => unreachable!()
+    })
+}
+
+pub fn format_number(value: f64, format: NumberFormat) -> String {
+    if value.is_nan() {
+        return "undefined".to_string();
+    }
+
+    if value.is_infinite() {
+        return if value > 0.0 { "∞" } else { "-∞" }.to_string();
+    }
+
+    match format {
+        NumberFormat::Auto => {
+            let magnitude = value.abs();
+
+            if magnitude != 0.0 && !(AUTO_PLAIN_MIN..AUTO_PLAIN_MAX).contains(&magnitude) {
+                let scientific = scientific(value, AUTO_FIGURES);
+                let (mantissa, exponent) = scientific.split_once('e').unwrap();
+
+                return format!("{}e{}", trim_zeros(mantissa.to_string()), exponent);
+            }
+
+            trim_zeros(significant(value, AUTO_FIGURES))
+        },
+
+        NumberFormat::Fixed { decimals } => format!("{:.*}", decimals, value),
+        NumberFormat::Significant { figures } => significant(value, figures),
+        NumberFormat::Scientific { figures } => scientific(value, figures),
+        NumberFormat::Engineering { figures } => engineering(value, figures),
+
+        NumberFormat::Hex | NumberFormat::Binary | NumberFormat::Octal => in_base(value, format)
+            .unwrap_or_else(|| format_number(value, NumberFormat::Auto))
+    }
+}
+
+// a + bi, with the sign of the imaginary part between them
+pub fn format_complex(re: f64, im: f64, format: NumberFormat) -> String {
+    let sign = if im < 0.0 { "-" } else { "+" };
+
+    format!("{} {} {}i", format_number(re, format), sign, format_number(im.abs(), format))
+}
+
+// [1, 2, 3], with each number in the format
+pub fn format_list(values: &[f64], format: NumberFormat) -> String {
+    let numbers: Vec<String> = values.iter()
+        .map(|v| format_number(*v, format))
+        .collect();
+
+    format!("[{}]", numbers.join(", "))
+}
+
+// [[1, 2], [3, 4]], one list for each row
+pub fn format_rows(rows: &[Vec<f64>], format: NumberFormat) -> String {
+    let rows: Vec<String> = rows.iter()
+        .map(|row| format_list(row, format))
+        .collect();
+
+    format!("[{}]", rows.join(", "))
+}
@@ -1,4 +1,30 @@
+mod utils;
+pub mod calculus;
+pub mod roots;
+pub mod curves;
+pub mod sampling;
+pub mod contour;
+pub mod region;
+pub mod slider;
+pub mod definitions;
+pub mod session;
+pub mod table;
+pub mod csv;
+pub mod svg;
+pub mod value;
+pub mod statistics;
+pub mod regression;
+pub mod complex;
+pub mod rational;
+pub mod format;
+pub mod units;
+pub mod matrix;
+pub mod linalg;
+#[cfg(feature = "precision")]
+pub mod precise;
+
 use std::collections::hash_map::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use wasm_bindgen::prelude::*;
 use serde::{Serialize, Deserialize};
 
@@ -6,6 +32,26 @@ use regex::Regex;
 
 use calculus::*;
 use roots::*;
+use curves::*;
+use sampling::*;
+use region::*;
+use slider::*;
+use definitions::*;
+use session::*;
+use table::*;
+use csv::*;
+use svg::*;
+use value::*;
+use statistics::STATISTICS;
+use regression::*;
+use complex::*;
+use rational::Rational;
+use format::*;
+use units::*;
+use matrix::{Matrix, MatrixError, MATRIX_FUNCTIONS};
+use linalg::{Decomposition, DECOMPOSITIONS};
+#[cfg(feature = "precision")]
+use precise::BigFloat;
 
 #[wasm_bindgen]
 pub fn setup() {
@@ -36,22 +82,67 @@ pub enum LexerTokenType {
     Sub,
     Div,
     Mul,
+    Pow,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    // joins a chain of comparisons such as 0 <= x <= 3, never lexed directly
+    And,
+
+    // a minus sign with nothing on its left, as in -x or 2*-3
+    Neg,
     Func(Vec<Vec<LexerToken>>, String), 
+
+    // {condition: value, ...}, a branch without a condition is taken if no others are
+    Piecewise(Vec<(Option<Vec<LexerToken>>, Vec<LexerToken>)>),
+
+    // a list literal [a, b, c]
+    List(Vec<Vec<LexerToken>>),
+
+    // an element of a list, L[i] counting from 1
+    Index(char, Vec<LexerToken>),
+
+    // f(a) is the graph f at a, or f times a when there is no graph called f
+    Call(char, Vec<LexerToken>),
     X,
     Var(char),
     Num(f64),
-    IndefiniteFunction(char)
+    IndefiniteFunction(char),
+
+    // a unit raised to a whole power, like km or s^-2, only lexed with units on
+    Unit(String, i32)
+}
+
+fn is_comparison(input: &LexerTokenType) -> bool {
+    matches!(
+        input,
+        LexerTokenType::Less | LexerTokenType::LessEqual | LexerTokenType::Greater | LexerTokenType::GreaterEqual
+    )
 }
 
 fn value_operator(input: &LexerTokenType) -> u32 {
     match input {
         LexerTokenType::Func(..) => 0,
-        LexerTokenType::Mul => 1,
-        LexerTokenType::Div => 1,
-        LexerTokenType::Add => 2,
-        LexerTokenType::Sub => 2,
-
-        LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::X | LexerTokenType::IndefiniteFunction(_) => panic!()
+        LexerTokenType::Piecewise(_) => 0,
+        LexerTokenType::List(_) => 0,
+        LexerTokenType::Index(..) => 0,
+        LexerTokenType::Call(..) => 0,
+        LexerTokenType::Pow => 1,
+        LexerTokenType::Neg => 2,
+        LexerTokenType::Mul => 3,
+        LexerTokenType::Div => 3,
+        LexerTokenType::Add => 4,
+        LexerTokenType::Sub => 4,
+        LexerTokenType::Less => 5,
+        LexerTokenType::LessEqual => 5,
+        LexerTokenType::Greater => 5,
+        LexerTokenType::GreaterEqual => 5,
+        LexerTokenType::And => 6,
+
+        LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::X | LexerTokenType::IndefiniteFunction(_) |
+        LexerTokenType::Unit(..) => panic!()
     }
 }
 
@@ -62,20 +153,32 @@ pub struct LexerToken {
     bracket_depth: u32
 }
 
-const FUNCTIONS: [&str; 7] = ["ln", "log", "sin", "cos", "tan", "sqrt", "int"];
+const FUNCTIONS: [&str; 13] = ["ln", "log", "sin", "cos", "tan", "sqrt", "int", "lim", "abs", "conj", "arg", "re", "im"];
 
-fn string_to_token(s: &str) -> Option<LexerTokenType> {
+// every name the lexer reads as a function
+fn builtin_functions() -> impl Iterator<Item = &'static str> {
+    FUNCTIONS.iter()
+        .chain(STATISTICS.iter())
+        .chain(MATRIX_FUNCTIONS.iter())
+        .chain(DECOMPOSITIONS.iter())
+        .copied()
+}
 
-    Some(match s {
-        "+" => LexerTokenType::Add,
-        "-" => LexerTokenType::Sub,
-        "*" => LexerTokenType::Mul,
-        "/" => LexerTokenType::Div,
+// how trig functions read their angles, passed along with the variables to every evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AngleMode {
+    Radians,
+    Degrees
+}
 
-        _ => {
-            return None;
+impl AngleMode {
+    pub fn radians_per_unit(&self) -> f64 {
+        match self {
+            AngleMode::Radians => 1.0,
+            AngleMode::Degrees => std::f64::consts::PI / 180.0
         }
-    })
+    }
 }
 
 // return the variables, and the function name
@@ -85,7 +188,7 @@ fn find_function(input: &str) -> Option<(Vec<char>, &str)> {
         return None;
     }
 
-    for fun in FUNCTIONS {
+    for fun in builtin_functions() {
         if let Some(pos) = input.find(fun) {
             let leftover_chars: Vec<char> = input[..pos]
                 .chars()
@@ -100,12 +203,15 @@ fn find_function(input: &str) -> Option<(Vec<char>, &str)> {
     None
 }
 
-fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenType, LexError>
+fn generate_function<T>(i: &mut T, function_name: String, units: Option<&HashSet<char>>) -> Result<LexerTokenType, LexError>
     where T: Iterator<Item = char>
 {
 
     let mut bracket_depth: u32 = 1;
 
+    // depth of braces and square brackets, so commas inside a piecewise or list argument don't split it
+    let mut brace_depth: u32 = 0;
+
     let mut args_sets: Vec<Vec<char>> = Vec::new();
     let mut current_set: Vec<char> = Vec::new();
 
@@ -113,12 +219,18 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
 
     for c in i {
 
-        println!("C: {}", c);
-
         if c == '(' {
             bracket_depth += 1;
         }
 
+        if c == '{' || c == '[' {
+            brace_depth += 1;
+        }
+
+        if c == '}' || c == ']' {
+            brace_depth = brace_depth.saturating_sub(1);
+        }
+
         if c == ')' {
             bracket_depth -= 1;
 
@@ -128,7 +240,7 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
             }
         }
 
-        if c == ',' {
+        if c == ',' && bracket_depth == 1 && brace_depth == 0 {
             args_sets.push(current_set);
             current_set = Vec::new();
 
@@ -145,15 +257,22 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
     }
 
     // this is a bad solution, but integration will replace the first function with the relevant tree
+    // limits use the same trick so that lim(f(x), x, a) works on a defined graph
 
     // TODO: check argument amount in lexer instead of evaluator
 
     let int_function_name: Option<char>;
-    if function_name == "int" {
+    if function_name == "int" || function_name == "lim" {
         // regex for function of x
 
-        if args_sets.len() != 3 {
-            return Err(LexError);
+        let valid_arg_count = match function_name.as_str() {
+            "int" => args_sets.len() == 3,
+            // lim(f, x, a) or lim(f, x, a, direction)
+            _ => args_sets.len() == 3 || args_sets.len() == 4,
+        };
+
+        if !valid_arg_count {
+            return Err(LexError::Invalid);
         }
 
         let first_argument: String = args_sets[0]
@@ -166,7 +285,7 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
             let first_char = match first_argument.chars().next() {
                 Some(c) => c,
                 None => {
-                    return Err(LexError);
+                    return Err(LexError::Invalid);
                 }
             };
 
@@ -179,15 +298,13 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
         int_function_name = None;
     }
 
-    println!("{:?}", int_function_name);
-
     let parsed_sets: Vec<Result<Vec<LexerToken>, LexError>> = args_sets.into_iter()
         .map(|set| set.iter().collect::<String>())
-        .map(|set_string| {println!("SET: {}", set_string); lex(&set_string)})
+        .map(|set_string| lex_tokens(&set_string, units))
         .collect();
 
     if parsed_sets.iter().any(|x| x.is_err()) {
-        return Err(LexError);
+        return Err(LexError::Invalid);
     }
 
     let mut parsed_sets: Vec<Vec<LexerToken>> = parsed_sets.into_iter()
@@ -206,6 +323,112 @@ fn generate_function<T>(i: &mut T, function_name: String) -> Result<LexerTokenTy
     Ok(LexerTokenType::Func(parsed_sets, function_name))
 }
 
+// called after an opening bracket, reads up to the matching closing one
+fn read_group<T>(i: &mut T, open: char, close: char) -> Result<String, LexError>
+    where T: Iterator<Item = char>
+{
+    let mut depth: u32 = 1;
+    let mut inner: String = String::new();
+
+    for c in i {
+        if c == open {
+            depth += 1;
+        }
+
+        if c == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return Ok(inner);
+            }
+        }
+
+        inner.push(c);
+    }
+
+    Err(LexError::Invalid)
+}
+
+// called after the opening square bracket of a list literal
+fn generate_list<T>(i: &mut T, units: Option<&HashSet<char>>) -> Result<LexerTokenType, LexError>
+    where T: Iterator<Item = char>
+{
+    let inner = read_group(i, '[', ']')?;
+
+    if inner.trim().is_empty() {
+        return Ok(LexerTokenType::List(Vec::new()));
+    }
+
+    let elements = split_top_level(&inner, ',')
+        .iter()
+        .map(|element| {
+            let tokens = lex_tokens(element, units)?;
+
+            if tokens.is_empty() {
+                return Err(LexError::Invalid);
+            }
+
+            Ok(tokens)
+        })
+        .collect::<Result<Vec<Vec<LexerToken>>, LexError>>()?;
+
+    Ok(LexerTokenType::List(elements))
+}
+
+// called after the opening brace, reads up to the matching closing brace
+fn generate_piecewise<T>(i: &mut T, units: Option<&HashSet<char>>) -> Result<LexerTokenType, LexError>
+    where T: Iterator<Item = char>
+{
+    let mut brace_depth: u32 = 1;
+    let mut inner: String = String::new();
+
+    let mut closing_found: bool = false;
+
+    for c in i {
+        if c == '{' {
+            brace_depth += 1;
+        }
+
+        if c == '}' {
+            brace_depth -= 1;
+
+            if brace_depth == 0 {
+                closing_found = true;
+                break;
+            }
+        }
+
+        inner.push(c);
+    }
+
+    if !closing_found {
+        return Err(LexError::Invalid);
+    }
+
+    let mut branches: Vec<(Option<Vec<LexerToken>>, Vec<LexerToken>)> = Vec::new();
+
+    for branch in split_top_level(&inner, ',') {
+        let parts = split_top_level(&branch, ':');
+
+        let (condition, value) = match parts.as_slice() {
+            [value] => (None, lex_tokens(value, units)?),
+            [condition, value] => (Some(lex_tokens(condition, units)?), lex_tokens(value, units)?),
+
+            _ => {
+                return Err(LexError::Invalid);
+            }
+        };
+
+        if value.is_empty() {
+            return Err(LexError::Invalid);
+        }
+
+        branches.push((condition, value));
+    }
+
+    Ok(LexerTokenType::Piecewise(branches))
+}
+
 fn is_valid_brackets(input: &str) -> bool {
     let mut bracket_depth: u32 = 0;
 
@@ -227,34 +450,278 @@ fn is_valid_brackets(input: &str) -> bool {
     bracket_depth == 0
 }
 
-#[derive(Debug)]
-pub struct LexError;
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    Invalid,
+
+    // a number that can't be read, like 1.2.3, from start up to end by character in the input
+    MalformedNumber { literal: String, start: usize, end: usize }
+}
 
 impl std::fmt::Display for LexError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "failed to lex expression")
+        match self {
+            LexError::Invalid => write!(f, "failed to lex expression"),
+            LexError::MalformedNumber { literal, start, end } => write!(f, "malformed number {} at {}..{}", literal, start, end)
+        }
     }
 }
 
 impl std::error::Error for LexError {}
 
+fn is_operand(token_type: &LexerTokenType) -> bool {
+    matches!(
+        token_type,
+        LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::X | LexerTokenType::Unit(..) |
+        LexerTokenType::Func(..) | LexerTokenType::Piecewise(_) |
+        LexerTokenType::List(_) | LexerTokenType::Index(..) | LexerTokenType::Call(..)
+    )
+}
+
+// two operands next to each other such as 3t, 2sin(x) or (x+1)(x-1) are multiplied,
+// the multiplication sits at the shallowest bracket depth between them
+fn push_token(out: &mut Vec<LexerToken>, token_type: LexerTokenType, bracket_depth: u32, gap_depth: &mut u32) {
+    if let Some(last) = out.last() {
+        let starts_operand = is_operand(&token_type) || matches!(token_type, LexerTokenType::Neg);
+
+        if is_operand(&last.token_type) && starts_operand {
+            out.push(LexerToken {
+                token_type: LexerTokenType::Mul,
+                bracket_depth: (*gap_depth).min(bracket_depth)
+            });
+        }
+    }
+
+    out.push(LexerToken { token_type, bracket_depth });
+    *gap_depth = bracket_depth;
+}
+
+// a number or unit right before a unit belongs to it, so 5 m / 2 s is (5 m) / (2 s),
+// the multiplication is put a bracket deeper so that it is split on after everything around it
+fn push_unit(out: &mut Vec<LexerToken>, token_type: LexerTokenType, bracket_depth: u32, gap_depth: &mut u32) {
+    let binds = match out.last() {
+        Some(LexerToken { token_type: LexerTokenType::Num(_) | LexerTokenType::Unit(..), bracket_depth: depth }) => {
+            *depth == bracket_depth && *gap_depth == bracket_depth
+        },
+
+        _ => false
+    };
+
+    // but 2^3 m is 8 m
+    let after_power = out.len() >= 2 && matches!(out[out.len() - 2].token_type, LexerTokenType::Pow);
+
+    if !binds || after_power {
+        push_token(out, token_type, bracket_depth, gap_depth);
+        return;
+    }
+
+    out.push(LexerToken { token_type: LexerTokenType::Mul, bracket_depth: bracket_depth + 1 });
+    out.push(LexerToken { token_type, bracket_depth });
+    *gap_depth = bracket_depth;
+}
+
+// a whole power straight after a unit, like the 2 in m^2 or the -1 in s^-1, which is part of the unit
+fn unit_power(iter: &mut std::iter::Peekable<std::str::Chars>) -> i32 {
+    let mut lookahead = iter.clone();
+
+    if lookahead.next() != Some('^') {
+        return 1;
+    }
+
+    let mut literal: String = String::new();
+
+    if lookahead.peek() == Some(&'-') {
+        literal.push('-');
+        lookahead.next();
+    }
+
+    while let Some(digit) = lookahead.peek().filter(|c| c.is_ascii_digit()) {
+        literal.push(*digit);
+        lookahead.next();
+    }
+
+    // m^1.5 is left as an ordinary power
+    if lookahead.peek() == Some(&'.') {
+        return 1;
+    }
+
+    match literal.parse::<i32>() {
+        Ok(power) => {
+            *iter = lookahead;
+            power
+        },
+
+        Err(_) => 1
+    }
+}
+
+// whether every digit is separated by at most single underscores, like 1_000
+fn is_separated_digits(part: &[char], radix: u32) -> bool {
+    !part.is_empty() &&
+    part.first() != Some(&'_') &&
+    part.last() != Some(&'_') &&
+    !part.windows(2).any(|pair| pair == ['_', '_']) &&
+    part.iter().all(|c| *c == '_' || c.is_digit(radix))
+}
+
+// the end of the number literal starting at start, and its value if it is well formed:
+// decimals like 6.02e23 and 1_000_000, and integers like 0xFF, 0b1010 and 0o17
+fn scan_number(chars: &[char], start: usize) -> (usize, Option<f64>) {
+    let at = |i: usize| chars.get(i).copied();
+
+    let radix = match (at(start), at(start + 1).map(|c| c.to_ascii_lowercase())) {
+        (Some('0'), Some('x')) => Some(16),
+        (Some('0'), Some('b')) => Some(2),
+        (Some('0'), Some('o')) => Some(8),
+        _ => None
+    };
+
+    // 0x on its own is still 0 times x
+    if let Some(radix) = radix.filter(|r| at(start + 2).is_some_and(|c| c.is_digit(*r))) {
+        let mut end = start + 2;
+        while at(end).is_some_and(|c| c.is_digit(radix) || c.is_ascii_digit() || c == '_' || c == '.') {
+            end += 1;
+        }
+
+        let digits = &chars[start + 2..end];
+        let value = is_separated_digits(digits, radix)
+            .then(|| digits.iter().filter_map(|c| c.to_digit(radix)).fold(0.0, |acc, d| acc * radix as f64 + d as f64));
+
+        return (end, value);
+    }
+
+    let mut end = start;
+    while at(end).is_some_and(|c| c.is_ascii_digit() || c == '_' || c == '.') {
+        end += 1;
+    }
+
+    let mantissa_end = end;
+
+    // an exponent needs a digit after the e, so 2e is still 2 times e
+    let exponent_digit = match at(end + 1) {
+        Some('+') | Some('-') => at(end + 2),
+        other => other
+    };
+
+    if at(end).is_some_and(|c| c == 'e' || c == 'E') && exponent_digit.is_some_and(|c| c.is_ascii_digit()) {
+        end += 1;
+        if at(end).is_some_and(|c| c == '+' || c == '-') {
+            end += 1;
+        }
+
+        while at(end).is_some_and(|c| c.is_ascii_digit() || c == '_' || c == '.') {
+            end += 1;
+        }
+    }
+
+    let mantissa = &chars[start..mantissa_end];
+    let exponent = &chars[(mantissa_end + 1).min(end)..end];
+    let exponent_digits = exponent.strip_prefix(&['+']).or_else(|| exponent.strip_prefix(&['-'])).unwrap_or(exponent);
+
+    // at most one point, with digits on at least one side of it
+    let parts: Vec<&[char]> = mantissa.split(|c| *c == '.').collect();
+    let well_formed = match parts[..] {
+        [whole] => is_separated_digits(whole, 10),
+        [whole, fraction] => (whole.is_empty() || is_separated_digits(whole, 10)) &&
+            (fraction.is_empty() || is_separated_digits(fraction, 10)) &&
+            !(whole.is_empty() && fraction.is_empty()),
+        _ => false
+    } && (end == mantissa_end || is_separated_digits(exponent_digits, 10));
+
+    let value = well_formed
+        .then(|| chars[start..end].iter().filter(|c| **c != '_').collect::<String>())
+        .and_then(|literal| literal.parse::<f64>().ok());
+
+    (end, value)
+}
+
+// every number literal is checked before lexing, so the error can say where the bad one is
+pub fn check_numbers(input: &str) -> Result<(), LexError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !(chars[i].is_ascii_digit() || chars[i] == '.') {
+            i += 1;
+            continue;
+        }
+
+        let (end, value) = scan_number(&chars, i);
+
+        if value.is_none() {
+            return Err(LexError::MalformedNumber { literal: chars[i..end].iter().collect(), start: i, end });
+        }
+
+        i = end;
+    }
+
+    Ok(())
+}
+
 pub fn lex(input: &str) -> Result<Vec<LexerToken>, LexError> {
+    lex_tokens(input, None)
+}
+
+// like lex, but names of units such as kg and km/h are read as units rather than variables,
+// except for the single letter names given, which stay variables or graphs
+pub fn lex_units(input: &str, names: &HashSet<char>) -> Result<Vec<LexerToken>, LexError> {
+    lex_tokens(input, Some(names))
+}
+
+fn lex_tokens(input: &str, units: Option<&HashSet<char>>) -> Result<Vec<LexerToken>, LexError> {
 
     if !is_valid_brackets(input) {
-        return Err(LexError);
+        return Err(LexError::Invalid);
     }
 
+    check_numbers(input)?;
+
     let mut iter = input.chars().peekable();
 
     let mut bracket_depth: u32 = 0;
 
+    // lowest bracket depth since the last token, used for implicit multiplication
+    let mut gap_depth: u32 = 0;
+
     let mut out: Vec<LexerToken> = Vec::new();
 
+    // whether the last thing read was a name, so that L[2] is an index rather than L times [2]
+    let mut after_name: bool = false;
+
     while let Some(character) = iter.next() {
+        let follows_name = after_name;
+        after_name = false;
+
         if character == ' ' {
             continue;
         }
 
+        if character == '[' {
+            let last_var = match out.last() {
+                Some(LexerToken { token_type: LexerTokenType::Var(c), .. }) if follows_name => Some(*c),
+                _ => None
+            };
+
+            match last_var {
+                Some(name) => {
+                    let index = lex_tokens(&read_group(&mut iter, '[', ']')?, units)?;
+
+                    if index.is_empty() {
+                        return Err(LexError::Invalid);
+                    }
+
+                    out.last_mut().unwrap().token_type = LexerTokenType::Index(name, index);
+                },
+
+                None => {
+                    let list = generate_list(&mut iter, units)?;
+                    push_token(&mut out, list, bracket_depth, &mut gap_depth);
+                }
+            }
+
+            continue;
+        }
+
         if character == '(' {
             bracket_depth += 1;
             continue;
@@ -262,50 +729,71 @@ pub fn lex(input: &str) -> Result<Vec<LexerToken>, LexError> {
 
         if character == ')' {
             bracket_depth -= 1;
+            gap_depth = gap_depth.min(bracket_depth);
             continue;
         }
 
-        if ['+', '-', '*', '/'].contains(&character) {
+        if ['+', '-', '*', '/', '^'].contains(&character) {
+            // nothing to subtract from, either at the start, after an operator or after an opening bracket
+            let is_negation = match out.last() {
+                Some(last) => !is_operand(&last.token_type) || gap_depth < bracket_depth,
+                None => true
+            };
+
             let token_type: LexerTokenType = match character {
                 '+' => LexerTokenType::Add,
+                '-' if is_negation => LexerTokenType::Neg,
                 '-' => LexerTokenType::Sub,
                 '*' => LexerTokenType::Mul,
                 '/' => LexerTokenType::Div,
+                '^' => LexerTokenType::Pow,
 
                 _ => unreachable!()
             };
 
-            out.push(LexerToken {token_type, bracket_depth});
+            push_token(&mut out, token_type, bracket_depth, &mut gap_depth);
             continue;
         }
 
+        if character == '{' {
+            let piecewise = generate_piecewise(&mut iter, units)?;
 
-        if character.is_numeric() || character == '.' {
-            let mut num_buf: Vec<char> = vec![character];
-            
-            while let Some(c) = iter.peek() {
-                if !(c.is_numeric() || *c == '.') {
-                    break;
-                }
+            push_token(&mut out, piecewise, bracket_depth, &mut gap_depth);
+            continue;
+        }
 
-                num_buf.push(*c);
+        if character == '<' || character == '>' {
+            let or_equal = iter.peek() == Some(&'=');
+            if or_equal {
                 iter.next();
             }
 
-            if num_buf[0] == '.' {
-                num_buf.insert(0, '0');
+            let token_type: LexerTokenType = match (character, or_equal) {
+                ('<', false) => LexerTokenType::Less,
+                ('<', true) => LexerTokenType::LessEqual,
+                ('>', false) => LexerTokenType::Greater,
+                ('>', true) => LexerTokenType::GreaterEqual,
+
+                _ => unreachable!()
+            };
+
+            push_token(&mut out, token_type, bracket_depth, &mut gap_depth);
+            continue;
+        }
+
+
+        if character.is_ascii_digit() || character == '.' {
+            // the literal can need a few characters of lookahead, like the digit after 1e-
+            let rest: Vec<char> = std::iter::once(character).chain(iter.clone()).collect();
+            let (end, number) = scan_number(&rest, 0);
+
+            for _ in 1..end {
+                iter.next();
             }
 
-            let number: f64 = num_buf
-                .iter()
-                .collect::<String>()
-                .parse()
-                .unwrap();
+            let number = number.ok_or(LexError::Invalid)?;
 
-            out.push(LexerToken {
-                token_type: LexerTokenType::Num(number),
-                bracket_depth
-            });
+            push_token(&mut out, LexerTokenType::Num(number), bracket_depth, &mut gap_depth);
 
             continue;
         }
@@ -326,21 +814,16 @@ pub fn lex(input: &str) -> Result<Vec<LexerToken>, LexError> {
                     iter.next();
 
                     for v in vars {
-                        out.push(LexerToken {
-                            token_type: LexerTokenType::Var(v),
-                            bracket_depth
-                        });
+                        push_token(&mut out, LexerTokenType::Var(v), bracket_depth, &mut gap_depth);
                     }
 
                     let function_type = generate_function(
                         &mut iter,
-                        function_name.to_string()
+                        function_name.to_string(),
+                        units
                     )?;
 
-                    out.push(LexerToken {
-                        token_type: function_type,
-                        bracket_depth
-                    });
+                    push_token(&mut out, function_type, bracket_depth, &mut gap_depth);
 
                     buffer = Vec::new();
                     break;
@@ -361,11 +844,43 @@ pub fn lex(input: &str) -> Result<Vec<LexerToken>, LexError> {
             */
         }
 
+        // with units on, a whole name like km is a unit rather than k times m,
+        // unless it is a defined name like a list L
+        let unit_name: String = buffer.iter().collect();
+
+        let is_unit = units.is_some_and(|names| match buffer[..] {
+            [name] if names.contains(&name) => false,
+            _ => find_unit(&unit_name).is_some()
+        });
+
+        if is_unit && iter.peek() != Some(&'(') {
+            let power = unit_power(&mut iter);
+
+            push_unit(&mut out, LexerTokenType::Unit(unit_name, power), bracket_depth, &mut gap_depth);
+            continue;
+        }
+
+        // the last letter before a bracket might be a graph, as in f(2) or af(x)
+        let call_name = match iter.peek() {
+            Some('(') => buffer.pop(),
+            _ => None
+        };
+
         for new_var in buffer.iter() {
-            out.push(LexerToken {
-                token_type: LexerTokenType::Var(*new_var),
-                bracket_depth
-            });
+            push_token(&mut out, LexerTokenType::Var(*new_var), bracket_depth, &mut gap_depth);
+        }
+
+        match call_name {
+            Some(name) => {
+                iter.next();
+                let argument = lex_tokens(&read_group(&mut iter, '(', ')')?, units)?;
+
+                push_token(&mut out, LexerTokenType::Call(name, argument), bracket_depth, &mut gap_depth);
+            },
+
+            None => {
+                after_name = !buffer.is_empty();
+            }
         }
     }
 
@@ -386,7 +901,10 @@ pub fn lex(input: &str) -> Result<Vec<LexerToken>, LexError> {
 struct OperatorOrdering {
     bracket_depth: u32,
     operator_val: u32,
-    position: usize
+    position: usize,
+
+    // 2^3^2 is 2^(3^2), so the leftmost power is split on first
+    right_associative: bool
 }
 
 impl OperatorOrdering {
@@ -401,49 +919,216 @@ impl OperatorOrdering {
 
         assert_ne!(self.position, other.position);
 
+        if self.right_associative {
+            return self.position < other.position;
+        }
+
         self.position > other.position
     }
 }
 
-fn find_next_op(items: &[LexerToken]) -> Option<usize> {
+// functions that act on each number by itself, so they can also be applied element by element
+const ELEMENTWISE_FUNCTIONS: [&str; 11] = ["ln", "log", "sin", "cos", "tan", "sqrt", "abs", "conj", "arg", "re", "im"];
+
+fn apply_function(name: &str, args: &[f64], angle: AngleMode) -> Option<f64> {
+    Some(match (name, args) {
+        ("ln", [a]) => a.ln(),
+        ("log", [a]) => a.log(10.0),
+        ("log", [a, base]) => a.log(*base),
+        ("sin", [a]) => (a * angle.radians_per_unit()).sin(),
+        ("cos", [a]) => (a * angle.radians_per_unit()).cos(),
+        ("tan", [a]) => (a * angle.radians_per_unit()).tan(),
+        ("sqrt", [a]) => a.sqrt(),
+
+        // a real number is its own real part and conjugate
+        ("abs", [a]) => a.abs(),
+        ("conj", [a]) | ("re", [a]) => *a,
+        ("im", [_]) => 0.0,
+        ("arg", [a]) => 0.0_f64.atan2(*a) / angle.radians_per_unit(),
 
-    let mut next_op_pos: Option<usize> = None;
-    let mut lowest_precedence: Option<OperatorOrdering> = None;
+        _ => {
+            return None;
+        }
+    })
+}
 
-    for (pos, item) in items.iter().enumerate() {
-        match item.token_type {
-            LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::X => {
-                continue;
-            }
+// the principal branch of each function
+fn apply_complex_function(name: &str, args: &[Complex], angle: AngleMode) -> Option<Complex> {
+    let unit = Complex::from(angle.radians_per_unit());
+
+    Some(match (name, args) {
+        ("ln", [a]) => a.ln(),
+        ("log", [a]) => a.ln() / Complex::from(10.0_f64.ln()),
+        ("log", [a, base]) => a.ln() / base.ln(),
+        ("sin", [a]) => (*a * unit).sin(),
+        ("cos", [a]) => (*a * unit).cos(),
+        ("tan", [a]) => (*a * unit).tan(),
+        ("sqrt", [a]) => a.sqrt(),
+
+        ("abs", [a]) => Complex::from(a.abs()),
+        ("conj", [a]) => a.conj(),
+        ("arg", [a]) => Complex::from(a.arg()) / unit,
+        ("re", [a]) => Complex::from(a.re),
+        ("im", [a]) => Complex::from(a.im),
 
-            _ => {}
+        _ => {
+            return None;
         }
+    })
+}
 
-        let op_val: u32 = value_operator(&item.token_type);
+fn apply_complex_operator(token_type: &LexerTokenType, left: Complex, right: Complex) -> Option<Complex> {
+    match token_type {
+        LexerTokenType::Add => Some(left + right),
+        LexerTokenType::Sub => Some(left - right),
+        LexerTokenType::Mul => Some(left * right),
+        LexerTokenType::Div => Some(left / right),
+        LexerTokenType::Pow => Some(left.pow(right)),
 
-        let precedence: OperatorOrdering = OperatorOrdering {
-            bracket_depth: item.bracket_depth,
-            operator_val: op_val,
-            position: pos
-        };
-        
-        let should_swap: bool = match &lowest_precedence {
-            Some(last_val) => {
+        // comparisons only make sense between real numbers
+        _ if left.is_real() && right.is_real() => Some(Complex::from(apply_operator(token_type, left.re, right.re))),
 
-                precedence.is_lower_precedence_than(last_val)
-            },
+        _ => None
+    }
+}
 
-            None => true
-        };
+// matrices are multiplied as matrices, and with numbers everything is element by element,
+// lists are column vectors on the right of a product and rows on the left
+fn apply_matrix_operator(token_type: &LexerTokenType, left: Value, right: Value) -> Result<Value, ValueError> {
+    let element_wise = |a: f64, b: f64| Some(apply_operator(token_type, a, b));
+    let arithmetic = matches!(token_type, LexerTokenType::Add | LexerTokenType::Sub | LexerTokenType::Mul | LexerTokenType::Div);
 
-        if should_swap {
-            next_op_pos = Some(pos);
-            lowest_precedence = Some(precedence);
-        }
-    }    
+    let result = match (token_type, left, right) {
+        (LexerTokenType::Mul, Value::Matrix(a), Value::Matrix(b)) => Value::Matrix(a.product(&b)?),
+        (LexerTokenType::Mul, Value::Matrix(a), Value::List(v)) => Value::List(a.apply(&v)?),
+        (LexerTokenType::Mul, Value::List(v), Value::Matrix(a)) => Value::List(a.transpose().apply(&v)?),
 
-    next_op_pos
-}
+        (LexerTokenType::Add | LexerTokenType::Sub, Value::Matrix(a), Value::Matrix(b)) => {
+            Value::Matrix(a.zip_with(&b, |a, b| apply_operator(token_type, a, b))?)
+        },
+
+        // only whole powers, so M^-1 is the inverse
+        (LexerTokenType::Pow, Value::Matrix(a), Value::Number(n)) if n.fract() == 0.0 && n.abs() <= i32::MAX as f64 => {
+            Value::Matrix(a.power(n as i32)?)
+        },
+
+        (_, Value::Matrix(a), Value::Number(n)) if arithmetic => Value::Matrix(a.map(|a| element_wise(a, n)).unwrap()),
+        (_, Value::Number(n), Value::Matrix(a)) if arithmetic => Value::Matrix(a.map(|a| element_wise(n, a)).unwrap()),
+
+        (_, Value::Matrix(a), Value::List(v)) => {
+            return Err(MatrixError::Mismatch { left: a.shape(), right: [v.len(), 1] }.into());
+        },
+
+        (_, Value::List(v), Value::Matrix(a)) => {
+            return Err(MatrixError::Mismatch { left: [v.len(), 1], right: a.shape() }.into());
+        },
+
+        _ => {
+            return Err(ValueError::Invalid);
+        }
+    };
+
+    Ok(result)
+}
+
+fn apply_operator(token_type: &LexerTokenType, left_val: f64, right_val: f64) -> f64 {
+    match token_type {
+        LexerTokenType::Add => left_val + right_val,
+        LexerTokenType::Sub => left_val - right_val,
+        LexerTokenType::Mul => left_val * right_val,
+        LexerTokenType::Div => left_val / right_val,
+        LexerTokenType::Pow => left_val.powf(right_val),
+
+        // comparisons are 1 when true and 0 when false
+        LexerTokenType::Less => (left_val < right_val) as u8 as f64,
+        LexerTokenType::LessEqual => (left_val <= right_val) as u8 as f64,
+        LexerTokenType::Greater => (left_val > right_val) as u8 as f64,
+        LexerTokenType::GreaterEqual => (left_val >= right_val) as u8 as f64,
+        LexerTokenType::And => (left_val != 0.0 && right_val != 0.0) as u8 as f64,
+
+        LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::Func(..) | LexerTokenType::X | LexerTokenType::IndefiniteFunction(_) |
+        LexerTokenType::Neg | LexerTokenType::Piecewise(_) | LexerTokenType::List(_) | LexerTokenType::Index(..) |
+        LexerTokenType::Call(..) | LexerTokenType::Unit(..) => unreachable!()
+    }
+}
+
+// f(a) where f isn't a graph goes back to the tokens it was written as, f * (a)
+fn expand_calls(items: &[LexerToken], graphs: &HashMap<char, ParseTree>) -> Vec<LexerToken> {
+    let mut out: Vec<LexerToken> = Vec::new();
+
+    for item in items {
+        match &item.token_type {
+            LexerTokenType::Call(name, argument) if !graphs.contains_key(name) => {
+                let name_token = match name {
+                    'x' => LexerTokenType::X,
+                    _ => LexerTokenType::Var(*name)
+                };
+
+                out.push(LexerToken { token_type: name_token, bracket_depth: item.bracket_depth });
+                out.push(LexerToken { token_type: LexerTokenType::Mul, bracket_depth: item.bracket_depth });
+
+                for token in argument {
+                    out.push(LexerToken {
+                        token_type: token.token_type.clone(),
+                        bracket_depth: token.bracket_depth + item.bracket_depth + 1
+                    });
+                }
+            },
+
+            _ => out.push(item.clone())
+        }
+    }
+
+    out
+}
+
+fn find_next_op(items: &[LexerToken]) -> Option<usize> {
+
+    let mut next_op_pos: Option<usize> = None;
+    let mut lowest_precedence: Option<OperatorOrdering> = None;
+
+    for (pos, item) in items.iter().enumerate() {
+        match item.token_type {
+            LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::X | LexerTokenType::Unit(..) => {
+                continue;
+            }
+
+            _ => {}
+        }
+
+        // a negation applies to everything after it, so it can only be split on from the front
+        if let LexerTokenType::Neg = item.token_type {
+            if pos != 0 {
+                continue;
+            }
+        }
+
+        let op_val: u32 = value_operator(&item.token_type);
+
+        let precedence: OperatorOrdering = OperatorOrdering {
+            bracket_depth: item.bracket_depth,
+            operator_val: op_val,
+            position: pos,
+            right_associative: matches!(item.token_type, LexerTokenType::Pow)
+        };
+        
+        let should_swap: bool = match &lowest_precedence {
+            Some(last_val) => {
+
+                precedence.is_lower_precedence_than(last_val)
+            },
+
+            None => true
+        };
+
+        if should_swap {
+            next_op_pos = Some(pos);
+            lowest_precedence = Some(precedence);
+        }
+    }    
+
+    next_op_pos
+}
 
 type TreeLink = Option<Box<TreeNode>>;
 
@@ -469,6 +1154,15 @@ impl std::error::Error for EvaluateError {}
 
 impl TreeNode {
     fn new_from_tokens(items: &[LexerToken], graphs: &HashMap<char, ParseTree>) -> Result<TreeNode, ParseError>  {
+        let not_a_graph = |token: &LexerToken| match &token.token_type {
+            LexerTokenType::Call(name, _) => !graphs.contains_key(name),
+            _ => false
+        };
+
+        if items.iter().any(not_a_graph) {
+            return TreeNode::new_from_tokens(&expand_calls(items, graphs), graphs);
+        }
+
         let next_op_pos = find_next_op(items);
 
         match next_op_pos {
@@ -523,12 +1217,107 @@ impl TreeNode {
                     });
                 }
                 
+                let arguments: Option<Vec<&Vec<LexerToken>>> = match &token_type {
+                    LexerTokenType::List(elements) => Some(elements.iter().collect()),
+                    LexerTokenType::Index(_, index) => Some(vec![index]),
+                    LexerTokenType::Call(_, argument) => Some(vec![argument]),
+
+                    _ => None
+                };
+
+                if let Some(arguments) = arguments {
+                    assert_eq!(items.len(), 1);
+
+                    let mut function_args: Vec<TreeNode> = arguments.into_iter()
+                        .map(|tokens| TreeNode::new_from_tokens(tokens, graphs))
+                        .collect::<Result<Vec<TreeNode>, ParseError>>()?;
+
+                    // the graph is copied in, in the same way as for int
+                    if let LexerTokenType::Call(name, _) = &token_type {
+                        let graph = graphs.get(name)
+                            .and_then(|g| g.inner_tree.clone())
+                            .ok_or(ParseError)?;
+
+                        function_args.insert(0, *graph);
+                    }
+
+                    return Ok(TreeNode {
+                        token_type,
+                        function_args,
+
+                        left: None,
+                        right: None,
+                    });
+                }
+
+                if let LexerTokenType::Piecewise(branches) = &token_type {
+                    assert_eq!(items.len(), 1);
+
+                    // arguments are stored as condition, value, condition, value...
+                    let mut function_args: Vec<TreeNode> = Vec::new();
+
+                    for (condition, value) in branches {
+                        let condition_node = match condition {
+                            Some(c) => TreeNode::new_from_tokens(c, graphs)?,
+
+                            // always taken
+                            None => TreeNode {
+                                token_type: LexerTokenType::Num(1.0),
+                                function_args: Vec::new(),
+                                left: None,
+                                right: None,
+                            }
+                        };
+
+                        function_args.push(condition_node);
+                        function_args.push(TreeNode::new_from_tokens(value, graphs)?);
+                    }
+
+                    return Ok(TreeNode {
+                        token_type,
+                        function_args,
+
+                        left: None,
+                        right: None,
+                    });
+                }
+
+                if let LexerTokenType::Neg = token_type {
+                    let right_node = TreeNode::new_from_tokens(&items[1..], graphs)?;
+
+                    return Ok(TreeNode {
+                        token_type,
+                        function_args: Vec::new(),
+                        left: None,
+                        right: Some(Box::new(right_node)),
+                    });
+                }
+
                 let left_items = &items[0..pos];
                 let left_node = TreeNode::new_from_tokens(left_items, graphs)?;
 
                 let right_items = &items[pos+1..];
                 let right_node = TreeNode::new_from_tokens(right_items, graphs)?;
 
+                // a <= b <= c is read as a <= b and b <= c
+                if is_comparison(&token_type) {
+                    if let Some(shared) = left_node.chain_operand() {
+                        let shared = shared.clone();
+
+                        return Ok(TreeNode {
+                            token_type: LexerTokenType::And,
+                            function_args: Vec::new(),
+                            left: Some(Box::new(left_node)),
+                            right: Some(Box::new(TreeNode {
+                                token_type,
+                                function_args: Vec::new(),
+                                left: Some(Box::new(shared)),
+                                right: Some(Box::new(right_node)),
+                            })),
+                        });
+                    }
+                }
+
                 Ok(TreeNode { 
                     token_type,
                     function_args: Vec::new(),
@@ -553,448 +1342,2743 @@ impl TreeNode {
         }
     }
 
-    fn evaluate(&self, x: Option<f64>, vars: &HashMap<char, f64>) -> Result<f64, EvaluateError> {
-        if let LexerTokenType::Num(num) = self.token_type {
+    fn references(&self, name: char) -> bool {
+        if let LexerTokenType::Var(var) = self.token_type {
+            return var == name;
+        }
 
-            // num shouldn't have left and right args
-            assert!(self.left.is_none());
-            assert!(self.right.is_none());
-            assert!(self.function_args.is_empty());
+        if let LexerTokenType::Index(list, _) = self.token_type {
+            if list == name {
+                return true;
+            }
+        }
 
-            return Ok(num);
+        self.left.iter()
+            .chain(self.right.iter())
+            .any(|node| node.references(name)) ||
+        self.function_args.iter()
+            .any(|node| node.references(name))
+    }
 
-        } else if let LexerTokenType::Var(var) = self.token_type {
-            // var shouldn't have left and right args
-            assert!(self.left.is_none());
-            assert!(self.right.is_none());
-            assert!(self.function_args.is_empty());
+    // every variable and list read by the node, leaving out the variable a limit approaches with
+    fn names(&self, out: &mut HashSet<char>) {
+        match &self.token_type {
+            LexerTokenType::Var(var) | LexerTokenType::Index(var, _) => {
+                out.insert(*var);
+            },
 
-            let var_value: f64 = *vars.get(&var).ok_or(EvaluateError)?;
+            LexerTokenType::Func(_, name) if name == "lim" => {
+                let mut inner: HashSet<char> = HashSet::new();
+                self.function_args[0].names(&mut inner);
 
-            return Ok(var_value);
-        }
+                if let LexerTokenType::Var(bound) = self.function_args[1].token_type {
+                    inner.remove(&bound);
+                }
 
-        if let LexerTokenType::Func(_, name) = &self.token_type {
+                out.extend(inner);
 
-            assert!(self.left.is_none());
-            assert!(self.right.is_none());
+                for node in &self.function_args[2..] {
+                    node.names(out);
+                }
 
-            if name == "ln" {
-                assert_eq!(self.function_args.len(), 1);
+                return;
+            },
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.ln());
-            }
+            _ => {}
+        }
 
-            if name == "log" {
-                assert!(self.function_args.len() == 1 || self.function_args.len() == 2);
-                
-                let base = match self.function_args.len() {
-                    1 => 10.0,
-                    2 => self.function_args[1].evaluate(x, vars)?,
+        for node in self.left.iter().chain(self.right.iter()) {
+            node.names(out);
+        }
 
-                    _ => unreachable!()
-                };
+        for node in &self.function_args {
+            node.names(out);
+        }
+    }
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.log(base));
+    // whether the node changes with x, the x inside a graph that is called belongs to that graph
+    fn uses_x(&self) -> bool {
+        match &self.token_type {
+            LexerTokenType::X => true,
+            LexerTokenType::Call(..) => self.function_args[1].uses_x(),
+
+            _ => {
+                self.left.iter()
+                    .chain(self.right.iter())
+                    .any(|node| node.uses_x()) ||
+                self.function_args.iter()
+                    .any(|node| node.uses_x())
             }
+        }
+    }
 
-            if name == "sin" {
-                assert_eq!(self.function_args.len(), 1);
+    // whether evaluating at x fails because x falls outside of every branch of a piecewise
+    // function, such as the domain of f(x) = x^2 {0 <= x <= 3}, rather than for another reason
+    fn outside_domain(&self, x: Option<f64>, vars: &HashMap<char, f64>, angle: AngleMode) -> bool {
+        match &self.token_type {
+            LexerTokenType::Piecewise(_) => {
+                for branch in self.function_args.chunks(2) {
+                    match branch[0].evaluate(x, vars, angle) {
+                        Ok(condition) if condition != 0.0 => {
+                            return branch[1].outside_domain(x, vars, angle);
+                        },
+                        Ok(_) => {
+                            continue;
+                        },
+                        Err(_) => {
+                            return branch[0].outside_domain(x, vars, angle);
+                        }
+                    }
+                }
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.sin());
-            }
-            if name == "cos" {
-                assert_eq!(self.function_args.len(), 1);
+                true
+            },
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.cos());
-            }
-            if name == "tan" {
-                assert_eq!(self.function_args.len(), 1);
+            LexerTokenType::Call(..) => {
+                match self.function_args[1].evaluate(x, vars, angle) {
+                    Ok(argument) => self.function_args[0].outside_domain(Some(argument), vars, angle),
+                    Err(_) => self.function_args[1].outside_domain(x, vars, angle)
+                }
+            },
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.tan());
-            }
-            if name == "sqrt" {
-                assert_eq!(self.function_args.len(), 1);
+            // these bring in a variable of their own
+            LexerTokenType::Func(_, name) if name == "int" || name == "lim" => false,
 
-                return Ok(self.function_args[0].evaluate(x, vars)?.sqrt());
+            _ => {
+                self.left.iter()
+                    .chain(self.right.iter())
+                    .any(|node| node.outside_domain(x, vars, angle)) ||
+                self.function_args.iter()
+                    .any(|node| node.outside_domain(x, vars, angle))
             }
+        }
+    }
 
-            if name == "int" {
-                assert_eq!(self.function_args.len(), 3);
+    // parts that use lists but not x, like L[2] or mean(L), become the number they give,
+    // so the rest can be sampled with plain numbers
+    fn resolve_values(&mut self, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) {
+        if !self.uses_values(values) {
+            return;
+        }
 
-                let f = |x: f64| self.function_args[0].evaluate(Some(x), vars).unwrap();
+        if !self.uses_x() {
+            if let Ok(Value::Number(n)) = self.evaluate_value(None, vars, values, angle) {
+                *self = TreeNode {
+                    token_type: LexerTokenType::Num(n),
+                    function_args: Vec::new(),
+                    left: None,
+                    right: None
+                };
 
-                return Ok(integrate(f, 
-                        self.function_args[1].evaluate(x, vars)?, 
-                        self.function_args[2].evaluate(x, vars)?, 
-                        10000
-                ));
+                return;
             }
-
-            unimplemented!()
         }
 
-        if let LexerTokenType::X = self.token_type {
-            return match x {
-                Some(v) => Ok(v),
-                None => Err(EvaluateError)
-            };
+        for node in self.left.iter_mut().chain(self.right.iter_mut()) {
+            node.resolve_values(vars, values, angle);
         }
 
-        // TODO: remove unwraps if necessary
-        let left_val: f64 = self.left.as_ref().unwrap().evaluate(x, vars)?;
-        let right_val: f64 = self.right.as_ref().unwrap().evaluate(x, vars)?;
+        for node in self.function_args.iter_mut() {
+            node.resolve_values(vars, values, angle);
+        }
+    }
 
-        Ok(match &self.token_type {
-            LexerTokenType::Add => left_val + right_val,
-            LexerTokenType::Sub => left_val - right_val,
-            LexerTokenType::Mul => left_val * right_val,
-            LexerTokenType::Div => left_val / right_val,
+    // whether a unit appears anywhere, written out or through a variable that has one
+    fn has_units(&self, quantities: &HashMap<char, Quantity>) -> bool {
+        match &self.token_type {
+            LexerTokenType::Unit(..) => true,
+            LexerTokenType::Var(c) => quantities.get(c).is_some_and(|q| !q.dimension.is_dimensionless()),
+
+            _ => {
+                self.left.iter()
+                    .chain(self.right.iter())
+                    .any(|node| node.has_units(quantities)) ||
+                self.function_args.iter()
+                    .any(|node| node.has_units(quantities))
+            }
+        }
+    }
 
-            LexerTokenType::Num(_) | LexerTokenType::Var(_) | LexerTokenType::Func(..) | LexerTokenType::X | LexerTokenType::IndefiniteFunction(_) => unreachable!()
-        })
+    fn uses_values(&self, values: &HashMap<char, Value>) -> bool {
+        match &self.token_type {
+            LexerTokenType::Var(c) => values.contains_key(c),
+            LexerTokenType::List(_) | LexerTokenType::Index(..) => true,
+
+            // statistics can give back lists, like quartiles
+            LexerTokenType::Func(_, name) if STATISTICS.contains(&name.as_str()) => true,
+            LexerTokenType::Func(_, name) if MATRIX_FUNCTIONS.contains(&name.as_str()) => true,
+            LexerTokenType::Func(_, name) if DECOMPOSITIONS.contains(&name.as_str()) => true,
+
+            _ => {
+                self.left.iter()
+                    .chain(self.right.iter())
+                    .any(|node| node.uses_values(values)) ||
+                self.function_args.iter()
+                    .any(|node| node.uses_values(values))
+            }
+        }
     }
 
-}
+    // like evaluate, but lists are allowed and are worked on element by element
+    fn evaluate_value(&self, x: Option<f64>, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) -> Result<Value, ValueError> {
+        if !self.uses_values(values) {
+            return Ok(Value::Number(self.evaluate(x, vars, angle)?));
+        }
 
-#[derive(Debug, Clone)]
-pub struct ParseTree {
-    inner_tree: TreeLink
-}
+        let number = |node: &TreeNode| -> Result<f64, ValueError> {
+            node.evaluate_value(x, vars, values, angle)?
+                .as_number()
+                .ok_or(ValueError::Invalid)
+        };
 
-#[derive(Debug)]
-pub struct ParseError;
+        match &self.token_type {
+            LexerTokenType::Var(c) => values.get(c).cloned().ok_or(ValueError::Invalid),
 
-impl std::fmt::Display for ParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Failed to evaluate equation")
-    }
-}
+            LexerTokenType::List(_) => {
+                let elements = self.function_args.iter()
+                    .map(|element| element.evaluate_value(x, vars, values, angle))
+                    .collect::<Result<Vec<Value>, ValueError>>()?;
 
-impl std::error::Error for ParseError {}
+                // a list of lists is a matrix, row by row
+                if let Some(Value::List(_)) = elements.first() {
+                    let rows = elements.into_iter()
+                        .map(|row| match row {
+                            Value::List(row) => Ok(row),
+                            _ => Err(MatrixError::Ragged)
+                        })
+                        .collect::<Result<Vec<Vec<f64>>, MatrixError>>()?;
 
-impl ParseTree {
-    pub fn new(lexed: &[LexerToken], graphs: &HashMap<char, ParseTree>) -> Result<ParseTree, ParseError> {
-        let inner_tree = Some(Box::new(
-            TreeNode::new_from_tokens(lexed, graphs)?
-        ));
+                    return Ok(Value::Matrix(Matrix::new(rows)?));
+                }
 
-        Ok(ParseTree { inner_tree })
-    }
+                elements.iter()
+                    .map(|element| element.as_number().ok_or(ValueError::Invalid))
+                    .collect::<Result<Vec<f64>, ValueError>>()
+                    .map(Value::List)
+            },
 
-    pub fn evaluate(&self, x: Option<f64>, vars: &HashMap<char, f64>) -> Result<f64, EvaluateError> {
-        if let Some(tree) = &self.inner_tree {
-            tree.evaluate(x, vars)
-        } else {
-            panic!()
-        }
-    }
-}
+            LexerTokenType::Index(name, _) => {
+                let index = number(&self.function_args[0])?;
 
-#[wasm_bindgen]
-pub struct Evaluator {
-    vars: HashMap<char, f64>,
-    graphs: HashMap<char, ParseTree>,
-}
+                // counting from 1
+                if index.fract() != 0.0 || index < 1.0 {
+                    return Err(ValueError::Invalid);
+                }
 
+                // a row of a matrix is a list
+                let element = match values.get(name) {
+                    Some(Value::List(list)) => list.get(index as usize - 1).map(|v| Value::Number(*v)),
+                    Some(Value::Matrix(m)) => m.rows().get(index as usize - 1).map(|row| Value::List(row.clone())),
 
+                    _ => None
+                };
 
-#[derive(Serialize, Deserialize)]
-#[serde(tag = "type")]
-enum EvaluatorResponse {
-    Value {
-        value: f64,
-        var_name: Option<String>
-    },
+                element.ok_or(ValueError::Invalid)
+            },
 
-    Graph {
-        points: Vec<[f64; 2]>,
-        //graph_name: 
-    }
-}
+            LexerTokenType::Func(_, name) if ELEMENTWISE_FUNCTIONS.contains(&name.as_str()) => {
+                let args = self.function_args.iter()
+                    .map(|arg| arg.evaluate_value(x, vars, values, angle))
+                    .collect::<Result<Vec<Value>, ValueError>>()?;
 
-fn evaluate_value_if_valid(
-    input: &str,
-    vars: &HashMap<char, f64>,
-    graphs: &HashMap<char, ParseTree>
-) -> Option<f64> {
+                if let [Value::Matrix(m)] = &args[..] {
+                    return m.map(|a| apply_function(name, &[a], angle))
+                        .map(Value::Matrix)
+                        .ok_or(ValueError::Invalid);
+                }
 
-    let tokens = match lex(input) {
-        Ok(v) => v,
+                Ok(broadcast(&args, |a| apply_function(name, a, angle))?)
+            },
 
-        Err(_) => {
-            return None;
-        }
-    };
+            LexerTokenType::Func(_, name) if MATRIX_FUNCTIONS.contains(&name.as_str()) => {
+                let args = self.function_args.iter()
+                    .map(|arg| arg.evaluate_value(x, vars, values, angle))
+                    .collect::<Result<Vec<Value>, ValueError>>()?;
 
-    let tree = match ParseTree::new(&tokens, graphs) {
-        Ok(v) => v,
+                Ok(matrix::apply(name, &args)?)
+            },
 
-        Err(_) => {
-            return None;
-        }
-    };
+            LexerTokenType::Func(_, name) if DECOMPOSITIONS.contains(&name.as_str()) => {
+                let args = self.function_args.iter()
+                    .map(|arg| arg.evaluate_value(x, vars, values, angle))
+                    .collect::<Result<Vec<Value>, ValueError>>()?;
 
-    let value = match tree.evaluate(None, vars) {
+                Ok(linalg::apply(name, &args)?)
+            },
+
+            // every argument is gathered into one list, so mean(L) and mean(1, 2, 3) both work
+            LexerTokenType::Func(_, name) if STATISTICS.contains(&name.as_str()) => {
+                let mut data: Vec<f64> = Vec::new();
+
+                for arg in &self.function_args {
+                    match arg.evaluate_value(x, vars, values, angle)? {
+                        Value::Number(n) => data.push(n),
+                        Value::List(list) => data.extend(list),
+                        Value::Points(_) | Value::Complex(_) | Value::Matrix(_) | Value::Decomposition(_) => {
+                            return Err(ValueError::Invalid);
+                        }
+                    }
+                }
+
+                statistics::apply(name, &data).map_err(|_| ValueError::Invalid)
+            },
+
+            LexerTokenType::Call(..) => {
+                let argument = self.function_args[1].evaluate_value(x, vars, values, angle)?;
+
+                Ok(broadcast(&[argument], |a| self.function_args[0].evaluate(Some(a[0]), vars, angle).ok())?)
+            },
+
+            LexerTokenType::Neg => {
+                let right = self.right.as_ref().unwrap().evaluate_value(x, vars, values, angle)?;
+
+                if let Value::Matrix(m) = right {
+                    return Ok(Value::Matrix(m.map(|a| Some(-a)).unwrap()));
+                }
+
+                Ok(broadcast(&[right], |a| Some(-a[0]))?)
+            },
+
+            token_type => match (&self.left, &self.right) {
+                (Some(left), Some(right)) => {
+                    let args = [
+                        left.evaluate_value(x, vars, values, angle)?,
+                        right.evaluate_value(x, vars, values, angle)?
+                    ];
+
+                    if let [Value::Matrix(_), _] | [_, Value::Matrix(_)] = &args {
+                        let [left, right] = args;
+
+                        return apply_matrix_operator(token_type, left, right);
+                    }
+
+                    Ok(broadcast(&args, |a| Some(apply_operator(token_type, a[0], a[1])))?)
+                },
+
+                // piecewise definitions, int and lim only work on numbers
+                _ => Err(ValueError::Invalid)
+            }
+        }
+    }
+
+    // the right hand side of the last comparison in a chain
+    fn chain_operand(&self) -> Option<&TreeNode> {
+        match self.token_type {
+            LexerTokenType::And => self.right.as_ref()?.chain_operand(),
+            _ if is_comparison(&self.token_type) => self.right.as_deref(),
+
+            _ => None
+        }
+    }
+
+    fn comparisons(&self) -> Vec<Comparison> {
+        if let LexerTokenType::And = self.token_type {
+            let mut out = self.left.as_ref().unwrap().comparisons();
+            out.extend(self.right.as_ref().unwrap().comparisons());
+
+            return out;
+        }
+
+        if !is_comparison(&self.token_type) {
+            return Vec::new();
+        }
+
+        let left = ParseTree { inner_tree: self.left.clone() };
+        let right = ParseTree { inner_tree: self.right.clone() };
+
+        let (lhs, rhs) = match self.token_type {
+            LexerTokenType::Greater | LexerTokenType::GreaterEqual => (right, left),
+            _ => (left, right)
+        };
+
+        vec![Comparison {
+            lhs,
+            rhs,
+            strict: matches!(self.token_type, LexerTokenType::Less | LexerTokenType::Greater)
+        }]
+    }
+
+    fn evaluate(&self, x: Option<f64>, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<f64, EvaluateError> {
+        if let LexerTokenType::Num(num) = self.token_type {
+
+            // num shouldn't have left and right args
+            assert!(self.left.is_none());
+            assert!(self.right.is_none());
+            assert!(self.function_args.is_empty());
+
+            return Ok(num);
+
+        } else if let LexerTokenType::Var(var) = self.token_type {
+            // var shouldn't have left and right args
+            assert!(self.left.is_none());
+            assert!(self.right.is_none());
+            assert!(self.function_args.is_empty());
+
+            let var_value: f64 = *vars.get(&var).ok_or(EvaluateError)?;
+
+            return Ok(var_value);
+        } else if let LexerTokenType::Unit(name, power) = &self.token_type {
+            // a unit by itself is its size in base units
+            return Quantity::unit(name, *power)
+                .map(|unit| unit.value)
+                .ok_or(EvaluateError);
+        }
+
+        if let LexerTokenType::Func(_, name) = &self.token_type {
+
+            assert!(self.left.is_none());
+            assert!(self.right.is_none());
+
+            if ELEMENTWISE_FUNCTIONS.contains(&name.as_str()) {
+                let args = self.function_args.iter()
+                    .map(|arg| arg.evaluate(x, vars, angle))
+                    .collect::<Result<Vec<f64>, EvaluateError>>()?;
+
+                return apply_function(name, &args, angle).ok_or(EvaluateError);
+            }
+
+            // matrix functions giving a number, like det([[x, 1], [1, x]]) in a graph,
+            // decompositions never do but are still errors rather than unknown functions
+            if MATRIX_FUNCTIONS.contains(&name.as_str()) || DECOMPOSITIONS.contains(&name.as_str()) {
+                return self.evaluate_value(x, vars, &HashMap::new(), angle)
+                    .ok()
+                    .and_then(|v| v.as_number())
+                    .ok_or(EvaluateError);
+            }
+
+            // only statistics of numbers, such as max(x, 0), can be worked out here
+            if STATISTICS.contains(&name.as_str()) {
+                let args = self.function_args.iter()
+                    .map(|arg| arg.evaluate(x, vars, angle))
+                    .collect::<Result<Vec<f64>, EvaluateError>>()?;
+
+                return statistics::apply(name, &args)
+                    .ok()
+                    .and_then(|v| v.as_number())
+                    .ok_or(EvaluateError);
+            }
+
+            if name == "int" {
+                assert_eq!(self.function_args.len(), 3);
+
+                // outside of a restricted domain the function adds nothing,
+                // any other failure makes the whole integral fail
+                let f = |x: f64| match self.function_args[0].evaluate(Some(x), vars, angle) {
+                    Ok(y) => y,
+                    Err(_) if self.function_args[0].outside_domain(Some(x), vars, angle) => 0.0,
+                    Err(_) => f64::NAN
+                };
+
+                let area = integrate(f, 
+                        self.function_args[1].evaluate(x, vars, angle)?, 
+                        self.function_args[2].evaluate(x, vars, angle)?, 
+                        10000
+                );
+
+                if area.is_nan() {
+                    return Err(EvaluateError);
+                }
+
+                return Ok(area);
+            }
+
+            if name == "lim" {
+                assert!(self.function_args.len() == 3 || self.function_args.len() == 4);
+
+                let approaching = self.function_args[2].evaluate(x, vars, angle)?;
+
+                // the second argument names the variable that approaches the point
+                let limit_var: Option<char> = match self.function_args[1].token_type {
+                    LexerTokenType::X => None,
+                    LexerTokenType::Var(c) => Some(c),
+
+                    _ => {
+                        return Err(EvaluateError);
+                    }
+                };
+
+                let f = |t: f64| {
+                    let result = match limit_var {
+                        Some(c) => {
+                            let mut inner_vars = vars.clone();
+                            inner_vars.insert(c, t);
+
+                            self.function_args[0].evaluate(x, &inner_vars, angle)
+                        },
+                        None => self.function_args[0].evaluate(Some(t), vars, angle)
+                    };
+
+                    result.unwrap_or(f64::NAN)
+                };
+
+                // optional direction, negative approaches from the left and positive from the right
+                let estimate = if self.function_args.len() == 4 {
+                    let direction = self.function_args[3].evaluate(x, vars, angle)?;
+
+                    if direction < 0.0 {
+                        one_sided_limit(f, approaching, LimitSide::Left)
+                    } else if direction > 0.0 {
+                        one_sided_limit(f, approaching, LimitSide::Right)
+                    } else {
+                        limit(f, approaching).limit
+                    }
+                } else {
+                    limit(f, approaching).limit
+                };
+
+                return match estimate {
+                    LimitEstimate::Value { value } => Ok(value),
+                    _ => Err(EvaluateError)
+                };
+            }
+
+            unimplemented!()
+        }
+
+        if let LexerTokenType::Piecewise(_) = self.token_type {
+            for branch in self.function_args.chunks(2) {
+                if branch[0].evaluate(x, vars, angle)? != 0.0 {
+                    return branch[1].evaluate(x, vars, angle);
+                }
+            }
+
+            // outside of every branch the function is undefined
+            return Err(EvaluateError);
+        }
+
+        if let LexerTokenType::Neg = self.token_type {
+            return Ok(-self.right.as_ref().unwrap().evaluate(x, vars, angle)?);
+        }
+
+        if let LexerTokenType::Call(..) = self.token_type {
+            let argument = self.function_args[1].evaluate(x, vars, angle)?;
+
+            return self.function_args[0].evaluate(Some(argument), vars, angle);
+        }
+
+        // lists need evaluate_value
+        if let LexerTokenType::List(_) | LexerTokenType::Index(..) = self.token_type {
+            return Err(EvaluateError);
+        }
+
+        if let LexerTokenType::X = self.token_type {
+            return match x {
+                Some(v) => Ok(v),
+                None => Err(EvaluateError)
+            };
+        }
+
+        // TODO: remove unwraps if necessary
+        let left_val: f64 = self.left.as_ref().unwrap().evaluate(x, vars, angle)?;
+        let right_val: f64 = self.right.as_ref().unwrap().evaluate(x, vars, angle)?;
+
+        Ok(apply_operator(&self.token_type, left_val, right_val))
+    }
+
+    // whole numbers and exactly known variables under the four basic operators, None for anything else
+    fn evaluate_rational(&self, exact: &HashMap<char, Rational>) -> Option<Rational> {
+        let rational = |node: &Option<Box<TreeNode>>| node.as_ref()?.evaluate_rational(exact);
+
+        match &self.token_type {
+            LexerTokenType::Num(num) => Rational::from_integer(*num),
+            LexerTokenType::Var(c) => exact.get(c).copied(),
+            LexerTokenType::Neg => rational(&self.right)?.checked_neg(),
+
+            LexerTokenType::Add => rational(&self.left)?.checked_add(&rational(&self.right)?),
+            LexerTokenType::Sub => rational(&self.left)?.checked_sub(&rational(&self.right)?),
+            LexerTokenType::Mul => rational(&self.left)?.checked_mul(&rational(&self.right)?),
+            LexerTokenType::Div => rational(&self.left)?.checked_div(&rational(&self.right)?),
+
+            _ => None
+        }
+    }
+
+    // like evaluate, but to the given number of significant digits
+    #[cfg(feature = "precision")]
+    fn evaluate_precise(&self, x: Option<f64>, vars: &Variables, digits: usize) -> Result<BigFloat, EvaluateError> {
+        let precise = |node: &TreeNode| node.evaluate_precise(x, vars, digits);
+        let from_f64 = |v: f64| BigFloat::from_f64(v).ok_or(EvaluateError);
+
+        let result = match &self.token_type {
+            LexerTokenType::Num(num) => return from_f64(*num),
+            LexerTokenType::X => return from_f64(x.ok_or(EvaluateError)?),
+
+            LexerTokenType::Var(c) => {
+                if let Some(v) = vars.precise.get(c) {
+                    return Ok(v.clone());
+                }
+
+                return match vars.exact.get(c) {
+                    Some(r) => BigFloat::from_integer(r.numerator()).div(&BigFloat::from_integer(r.denominator()), digits).ok_or(EvaluateError),
+                    None => from_f64(*vars.vars.get(c).ok_or(EvaluateError)?)
+                };
+            },
+
+            LexerTokenType::Neg => return Ok(precise(self.right.as_ref().unwrap())?.neg()),
+
+            LexerTokenType::Func(_, name) => {
+                let args = self.function_args.iter()
+                    .map(precise)
+                    .collect::<Result<Vec<BigFloat>, EvaluateError>>()?;
+
+                // trig works in radians, so degrees are turned into radians first
+                let radians = |a: &BigFloat| match vars.angle == AngleMode::Radians {
+                    true => Some(a.clone()),
+                    false => a.mul(&BigFloat::pi(digits), digits).div(&BigFloat::from_integer(180), digits)
+                };
+
+                match (name.as_str(), &args[..]) {
+                    ("ln", [a]) => a.ln(digits),
+                    ("log", [a]) => a.log(&BigFloat::from_integer(10), digits),
+                    ("log", [a, base]) => a.log(base, digits),
+                    ("sin", [a]) => radians(a).and_then(|a| a.sin(digits)),
+                    ("cos", [a]) => radians(a).and_then(|a| a.cos(digits)),
+                    ("tan", [a]) => radians(a).and_then(|a| a.tan(digits)),
+                    ("sqrt", [a]) => a.sqrt(digits),
+                    ("abs", [a]) => Some(a.abs()),
+
+                    _ => None
+                }
+            },
+
+            token_type => match (&self.left, &self.right) {
+                (Some(left), Some(right)) => {
+                    let (left, right) = (precise(left)?, precise(right)?);
+
+                    match token_type {
+                        LexerTokenType::Add => Some(left.add(&right, digits)),
+                        LexerTokenType::Sub => Some(left.sub(&right, digits)),
+                        LexerTokenType::Mul => Some(left.mul(&right, digits)),
+                        LexerTokenType::Div => left.div(&right, digits),
+                        LexerTokenType::Pow => left.pow(&right, digits),
+
+                        _ => None
+                    }
+                },
+
+                _ => None
+            }
+        };
+
+        result.ok_or(EvaluateError)
+    }
+
+    // like evaluate, but any number can be complex and i is the imaginary unit
+    fn evaluate_complex(&self, x: Option<f64>, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) -> Result<Complex, EvaluateError> {
+        let complex = |node: &TreeNode| node.evaluate_complex(x, vars, values, angle);
+
+        match &self.token_type {
+            LexerTokenType::Num(num) => Ok(Complex::from(*num)),
+            LexerTokenType::Var('i') => Ok(I),
+
+            LexerTokenType::Var(c) => match values.get(c) {
+                Some(Value::Complex(z)) => Ok(*z),
+                Some(_) => Err(EvaluateError),
+                None => vars.get(c).map(|v| Complex::from(*v)).ok_or(EvaluateError)
+            },
+
+            LexerTokenType::Func(_, name) if ELEMENTWISE_FUNCTIONS.contains(&name.as_str()) => {
+                let args = self.function_args.iter()
+                    .map(complex)
+                    .collect::<Result<Vec<Complex>, EvaluateError>>()?;
+
+                apply_complex_function(name, &args, angle).ok_or(EvaluateError)
+            },
+
+            LexerTokenType::Neg => Ok(-complex(self.right.as_ref().unwrap())?),
+
+            token_type => match (&self.left, &self.right) {
+                (Some(left), Some(right)) => apply_complex_operator(token_type, complex(left)?, complex(right)?)
+                    .ok_or(EvaluateError),
+
+                // piecewise definitions, calculus, graph calls, lists and statistics are real only
+                _ => self.evaluate(x, vars, angle).map(Complex::from)
+            }
+        }
+    }
+
+    // like evaluate, but keeping track of units, which have to agree across every operator and function
+    fn evaluate_quantity(&self, x: Option<f64>, vars: &HashMap<char, f64>, quantities: &HashMap<char, Quantity>, angle: AngleMode) -> Result<Quantity, UnitError> {
+        let quantity = |node: &TreeNode| node.evaluate_quantity(x, vars, quantities, angle);
+
+        if !self.has_units(quantities) {
+            return self.evaluate(x, vars, angle)
+                .map(Quantity::from)
+                .map_err(|_| UnitError::Invalid);
+        }
+
+        match &self.token_type {
+            LexerTokenType::Unit(name, power) => Quantity::unit(name, *power).ok_or(UnitError::Invalid),
+            LexerTokenType::Var(c) => quantities.get(c).copied().ok_or(UnitError::Invalid),
+            LexerTokenType::Neg => Ok(-quantity(self.right.as_ref().unwrap())?),
+
+            LexerTokenType::Func(_, name) if ELEMENTWISE_FUNCTIONS.contains(&name.as_str()) => {
+                let args = self.function_args.iter()
+                    .map(quantity)
+                    .collect::<Result<Vec<Quantity>, UnitError>>()?;
+
+                match (name.as_str(), &args[..]) {
+                    ("sqrt", [a]) => a.pow(&Quantity::from(0.5)),
+                    ("abs", [a]) => Ok(Quantity::new(a.value.abs(), a.dimension)),
+                    ("conj", [a]) | ("re", [a]) => Ok(*a),
+                    ("im", [a]) => Ok(Quantity::new(0.0, a.dimension)),
+                    ("arg", [a]) => apply_function(name, &[a.value], angle).map(Quantity::from).ok_or(UnitError::Invalid),
+
+                    // logarithms and trig only take plain numbers
+                    _ => {
+                        let args = args.iter()
+                            .map(|a| a.dimensionless())
+                            .collect::<Result<Vec<f64>, UnitError>>()?;
+
+                        apply_function(name, &args, angle).map(Quantity::from).ok_or(UnitError::Invalid)
+                    }
+                }
+            },
+
+            LexerTokenType::Piecewise(_) => {
+                for branch in self.function_args.chunks(2) {
+                    if quantity(&branch[0])?.dimensionless()? != 0.0 {
+                        return quantity(&branch[1]);
+                    }
+                }
+
+                Err(UnitError::Invalid)
+            },
+
+            token_type => match (&self.left, &self.right) {
+                (Some(left), Some(right)) => {
+                    let (left, right) = (quantity(left)?, quantity(right)?);
+
+                    match token_type {
+                        LexerTokenType::Mul => left * right,
+                        LexerTokenType::Div => left / right,
+                        LexerTokenType::Pow => left.pow(&right),
+
+                        LexerTokenType::Add | LexerTokenType::Sub => Ok(Quantity::new(
+                            apply_operator(token_type, left.value, right.value),
+                            left.same_dimension(&right)?
+                        )),
+
+                        // comparisons need the same units on both sides, and give a plain 0 or 1
+                        _ => {
+                            left.same_dimension(&right)?;
+
+                            Ok(Quantity::from(apply_operator(token_type, left.value, right.value)))
+                        }
+                    }
+                },
+
+                // calculus, graph calls, lists and statistics are plain numbers only
+                _ => Err(UnitError::Unsupported)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseTree {
+    inner_tree: TreeLink
+}
+
+#[derive(Debug)]
+pub struct ParseError;
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to evaluate equation")
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// one side compared against the other, always arranged as lhs < rhs or lhs <= rhs
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub lhs: ParseTree,
+    pub rhs: ParseTree,
+
+    // < and > leave out the boundary where both sides are equal
+    pub strict: bool
+}
+
+impl Comparison {
+    // lhs - rhs, negative when the comparison holds
+    pub fn difference(&self, x: Option<f64>, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<f64, EvaluateError> {
+        Ok(self.lhs.evaluate(x, vars, angle)? - self.rhs.evaluate(x, vars, angle)?)
+    }
+}
+
+impl ParseTree {
+    pub fn new(lexed: &[LexerToken], graphs: &HashMap<char, ParseTree>) -> Result<ParseTree, ParseError> {
+        let inner_tree = Some(Box::new(
+            TreeNode::new_from_tokens(lexed, graphs)?
+        ));
+
+        Ok(ParseTree { inner_tree })
+    }
+
+    // only defined where every condition holds, so that evaluating outside of the domain fails
+    // list indexing and statistics of lists worked out to numbers, so f(x) = x + L[2] can be drawn
+    pub fn resolve_values(mut self, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) -> ParseTree {
+        if let Some(tree) = self.inner_tree.as_mut() {
+            tree.resolve_values(vars, values, angle);
+        }
+
+        self
+    }
+
+    pub fn restrict(self, conditions: Vec<ParseTree>) -> ParseTree {
+        let domain = conditions.into_iter()
+            .filter_map(|c| c.inner_tree)
+            .map(|c| *c)
+            .reduce(|left, right| TreeNode {
+                token_type: LexerTokenType::And,
+                function_args: Vec::new(),
+                left: Some(Box::new(left)),
+                right: Some(Box::new(right)),
+            });
+
+        let (domain, inner) = match (domain, self.inner_tree) {
+            (Some(domain), Some(inner)) => (domain, inner),
+            (_, inner_tree) => {
+                return ParseTree { inner_tree };
+            }
+        };
+
+        // a piecewise function with a single branch
+        ParseTree {
+            inner_tree: Some(Box::new(TreeNode {
+                token_type: LexerTokenType::Piecewise(Vec::new()),
+                function_args: vec![domain, *inner],
+                left: None,
+                right: None,
+            }))
+        }
+    }
+
+    pub fn names(&self) -> HashSet<char> {
+        let mut out: HashSet<char> = HashSet::new();
+
+        if let Some(tree) = &self.inner_tree {
+            tree.names(&mut out);
+        }
+
+        out
+    }
+
+    // whether the variable appears anywhere in the tree
+    pub fn references(&self, name: char) -> bool {
+        match &self.inner_tree {
+            Some(tree) => tree.references(name),
+            None => false
+        }
+    }
+
+    pub fn evaluate_value(&self, x: Option<f64>, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) -> Result<Value, ValueError> {
+        match &self.inner_tree {
+            Some(tree) => tree.evaluate_value(x, vars, values, angle),
+            None => Err(ValueError::Invalid)
+        }
+    }
+
+    pub fn evaluate_rational(&self, exact: &HashMap<char, Rational>) -> Option<Rational> {
+        self.inner_tree.as_ref()?.evaluate_rational(exact)
+    }
+
+    #[cfg(feature = "precision")]
+    pub fn evaluate_precise(&self, x: Option<f64>, vars: &Variables, digits: usize) -> Result<BigFloat, EvaluateError> {
+        match &self.inner_tree {
+            Some(tree) => tree.evaluate_precise(x, vars, digits),
+            None => Err(EvaluateError)
+        }
+    }
+
+    pub fn evaluate_complex(&self, x: Option<f64>, vars: &HashMap<char, f64>, values: &HashMap<char, Value>, angle: AngleMode) -> Result<Complex, EvaluateError> {
+        match &self.inner_tree {
+            Some(tree) => tree.evaluate_complex(x, vars, values, angle),
+            None => Err(EvaluateError)
+        }
+    }
+
+    pub fn has_units(&self, quantities: &HashMap<char, Quantity>) -> bool {
+        match &self.inner_tree {
+            Some(tree) => tree.has_units(quantities),
+            None => false
+        }
+    }
+
+    pub fn evaluate_quantity(&self, x: Option<f64>, vars: &HashMap<char, f64>, quantities: &HashMap<char, Quantity>, angle: AngleMode) -> Result<Quantity, UnitError> {
+        match &self.inner_tree {
+            Some(tree) => tree.evaluate_quantity(x, vars, quantities, angle),
+            None => Err(UnitError::Invalid)
+        }
+    }
+
+    // every comparison made by the tree, a chain such as 0 <= x <= 3 gives two
+    pub fn comparisons(&self) -> Vec<Comparison> {
+        match &self.inner_tree {
+            Some(tree) => tree.comparisons(),
+            None => Vec::new()
+        }
+    }
+
+    pub fn evaluate(&self, x: Option<f64>, vars: &HashMap<char, f64>, angle: AngleMode) -> Result<f64, EvaluateError> {
+        if let Some(tree) = &self.inner_tree {
+            tree.evaluate(x, vars, angle)
+        } else {
+            panic!()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct Evaluator {
+    vars: HashMap<char, f64>,
+    graphs: HashMap<char, ParseTree>,
+    curves: HashMap<char, ParametricCurve>,
+    polar_curves: HashMap<char, PolarCurve>,
+
+    // range and amount of samples used for the parameter of parametric curves
+    t_min: f64,
+    t_max: f64,
+    t_samples: usize,
+
+    theta_min: f64,
+    theta_max: f64,
+
+    // vertical extent of the viewport, used when tracing implicit curves
+    min_y: f64,
+    max_y: f64,
+
+    sliders: HashMap<char, Slider>,
+
+    // variables holding lists and points rather than a single number
+    values: HashMap<char, Value>,
+
+    // the equation list by line id, kept so a single line can be updated on its own
+    lines: BTreeMap<u32, Definition>,
+    line_results: BTreeMap<u32, LineResult>,
+
+    min_x: f64,
+    max_x: f64,
+
+    angle_mode: AngleMode,
+    styles: BTreeMap<u32, GraphStyle>,
+
+    csv_options: CsvOptions,
+
+    // values are worked out with complex numbers, graphs stay real
+    complex_mode: bool,
+
+    // how values are written out in responses
+    number_format: NumberFormat,
+
+    // names of units like kg are read as units, and the variables that were given one
+    units_mode: bool,
+    quantities: HashMap<char, Quantity>,
+
+    // the variables that are known as exact fractions, also kept as f64 in the vars
+    exact: HashMap<char, Rational>,
+
+    // significant digits for values, and the variables worked out to them
+    #[cfg(feature = "precision")]
+    precision: Option<usize>,
+    #[cfg(feature = "precision")]
+    precise: HashMap<char, BigFloat>,
+}
+
+// samples taken across the graph before adaptive refinement
+const GRAPH_SAMPLES: usize = 200;
+const POLAR_SAMPLES: usize = 360;
+
+// implicit curves are traced on a grid of cells, subdivided near the curve
+const IMPLICIT_CELLS: usize = 48;
+const IMPLICIT_REFINEMENT: u32 = 3;
+
+// cells along each side of the viewport when shading inequalities
+const REGION_RESOLUTION: usize = 100;
+
+// how far a sampled curve can stray from the drawn line, as a fraction of the x range
+const SAMPLE_TOLERANCE: f64 = 0.001;
+
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EvaluatorResponse {
+    Value {
+        value: f64,
+
+        // set for complex results, value is then the real part
+        imaginary: Option<f64>,
+
+        // exact answers that aren't whole numbers, like 3/2 and 1 1/2
+        fraction: Option<String>,
+        mixed: Option<String>,
+
+        // the value to the evaluator's precision, with the precision feature
+        precise: Option<String>,
+
+        // the value shown in the evaluator's number format
+        formatted: String,
+
+        // what the value is measured in, with units on
+        unit: Option<String>,
+
+        var_name: Option<String>,
+
+        // set when the variable was given a range to be shown as a slider
+        slider: Option<Slider>
+    },
+
+    List {
+        values: Vec<f64>,
+        formatted: String,
+        var_name: Option<String>
+    },
+
+    Matrix {
+        rows: Vec<Vec<f64>>,
+        formatted: String,
+        var_name: Option<String>
+    },
+
+    Decomposition {
+        decomposition: Decomposition,
+        formatted: String,
+        var_name: Option<String>
+    },
+
+    // points drawn on their own, from a list of points or a pair of lists
+    Scatter {
+        points: Vec<[f64; 2]>,
+        var_name: Option<String>
+    },
+
+    // each segment is drawn as its own line, breaking where the graph is undefined
+    Graph {
+        segments: Vec<Vec<[f64; 2]>>,
+        //graph_name: 
+    },
+
+    // a model fitted to points, drawn like any other graph
+    Regression {
+        fit: Fit,
+        var_name: Option<String>,
+        segments: Vec<Vec<[f64; 2]>>
+    },
+
+    // the variables changed by fitting a model to data, in the same order as their values
+    Fit {
+        names: Vec<char>,
+        parameters: Vec<f64>,
+        r_squared: f64,
+        residuals: Vec<f64>,
+
+        // each parameter and the r squared, like a = 2, b = 1, R² = 0.99
+        formatted: String
+    },
+
+    // shaded area of one or more inequalities, raster is row by row from the bottom left
+    Region {
+        raster: Vec<bool>,
+        columns: usize,
+        rows: usize,
+
+        boundaries: Vec<RegionBoundary>
+    }
+}
+
+// the outcome of evaluating one line of the equation list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineResult {
+    pub id: u32,
+    pub response: Option<EvaluatorResponse>,
+    pub error: Option<DefinitionError>
+}
+
+fn data_response(value: Value, format: NumberFormat, var_name: Option<String>, slider: Option<Slider>) -> EvaluatorResponse {
+    match value {
+        Value::Number(value) => EvaluatorResponse::Value {
+            value,
+            imaginary: None,
+            fraction: None,
+            mixed: None,
+            precise: None,
+            formatted: format_number(value, format),
+            unit: None,
+            var_name,
+            slider
+        },
+
+        Value::Complex(z) => EvaluatorResponse::Value {
+            value: z.re,
+            imaginary: Some(z.im),
+            fraction: None,
+            mixed: None,
+            precise: None,
+            formatted: format_complex(z.re, z.im, format),
+            unit: None,
+            var_name,
+            slider
+        },
+
+        Value::List(values) => EvaluatorResponse::List {
+            formatted: format_list(&values, format),
+            values,
+            var_name
+        },
+
+        Value::Matrix(m) => EvaluatorResponse::Matrix {
+            formatted: format_rows(m.rows(), format),
+            rows: m.into_rows(),
+            var_name
+        },
+
+        Value::Decomposition(decomposition) => EvaluatorResponse::Decomposition {
+            formatted: decomposition.format(format),
+            decomposition,
+            var_name
+        },
+        Value::Points(points) => EvaluatorResponse::Scatter { points, var_name }
+    }
+}
+
+// the answer from the exact value when there is one, so 1/3 + 1/6 is exactly 0.5
+fn exact_response(exact: Rational, format: NumberFormat, var_name: Option<String>, slider: Option<Slider>) -> EvaluatorResponse {
+    EvaluatorResponse::Value {
+        value: exact.to_f64(),
+        imaginary: None,
+        fraction: (!exact.is_integer()).then(|| exact.fraction()),
+        mixed: exact.mixed(),
+        precise: None,
+        formatted: format_number(exact.to_f64(), format),
+        unit: None,
+        var_name,
+        slider
+    }
+}
+
+fn quantity_response(measurement: Measurement, format: NumberFormat, var_name: Option<String>, slider: Option<Slider>) -> EvaluatorResponse {
+    EvaluatorResponse::Value {
+        value: measurement.value,
+        imaginary: None,
+        fraction: None,
+        mixed: None,
+        precise: None,
+        formatted: format_number(measurement.value, format),
+        unit: measurement.unit,
+        var_name,
+        slider
+    }
+}
+
+fn sample_graph(tree: &ParseTree, vars: &HashMap<char, f64>, angle: AngleMode, min_x: f64, max_x: f64) -> Vec<Vec<[f64; 2]>> {
+    sample_function(
+        |x: f64| tree.evaluate(Some(x), vars, angle).ok(),
+        min_x,
+        max_x,
+        GRAPH_SAMPLES,
+        (max_x - min_x) * SAMPLE_TOLERANCE
+    )
+}
+
+// f(x) = x^2 {0 <= x <= 3} is split into the definition and the conditions of its domain,
+// a trailing piecewise like {x < 0: -x, x} is part of the definition
+fn split_domain(input: &str) -> (&str, Option<&str>) {
+    let trimmed = input.trim_end();
+
+    if !trimmed.ends_with('}') {
+        return (input, None);
+    }
+
+    // find the brace that opens the last group
+    let mut brace_depth: u32 = 0;
+    let mut opening: Option<usize> = None;
+
+    for (pos, c) in trimmed.char_indices().rev() {
+        match c {
+            '}' => brace_depth += 1,
+            '{' => {
+                brace_depth -= 1;
+
+                if brace_depth == 0 {
+                    opening = Some(pos);
+                    break;
+                }
+            },
+
+            _ => {}
+        }
+    }
+
+    let opening = match opening {
+        Some(v) => v,
+        None => {
+            return (input, None);
+        }
+    };
+
+    let definition = &trimmed[..opening];
+    let conditions = &trimmed[opening + 1..trimmed.len() - 1];
+
+    let is_piecewise = split_top_level(conditions, ':').len() > 1;
+
+    if is_piecewise || definition.trim().is_empty() {
+        return (input, None);
+    }
+
+    (definition, Some(conditions))
+}
+
+// split on the = of a definition, leaving the = of <= and >= alone
+fn split_definition(input: &str) -> Vec<&str> {
+    let mut parts: Vec<&str> = Vec::new();
+    let mut start: usize = 0;
+
+    let mut previous: Option<char> = None;
+    for (pos, c) in input.char_indices() {
+        if c == '=' && previous != Some('<') && previous != Some('>') {
+            parts.push(&input[start..pos]);
+            start = pos + 1;
+        }
+
+        previous = Some(c);
+    }
+
+    parts.push(&input[start..]);
+
+    parts
+}
+
+fn evaluate_value_if_valid(
+    input: &str,
+    vars: &HashMap<char, f64>,
+    angle: AngleMode,
+    graphs: &HashMap<char, ParseTree>
+) -> Option<f64> {
+
+    let tokens = match lex(input) {
+        Ok(v) => v,
+
+        Err(_) => {
+            return None;
+        }
+    };
+
+    let tree = match ParseTree::new(&tokens, graphs) {
+        Ok(v) => v,
+
+        Err(_) => {
+            return None;
+        }
+    };
+
+    let value = match tree.evaluate(None, vars, angle) {
         Ok(v) => v,
 
-        Err(_) => {
-            return None;
-        }
-    };
+        Err(_) => {
+            return None;
+        }
+    };
+
+    Some(value)
+
+}
+
+// (L, M) pairs up two lists, and [(1, 2), (3, 4)] lists points directly
+fn points_if_valid(
+    input: &str,
+    vars: &HashMap<char, f64>,
+    values: &HashMap<char, Value>,
+    angle: AngleMode,
+    graphs: &HashMap<char, ParseTree>
+) -> Option<Vec<[f64; 2]>> {
+    let trimmed = input.trim();
+
+    if let Some(parts) = split_tuple(trimmed) {
+        if parts.len() != 2 {
+            return None;
+        }
+
+        let xs = evaluate_data_if_valid(&parts[0], vars, values, angle, graphs)?;
+        let ys = evaluate_data_if_valid(&parts[1], vars, values, angle, graphs)?;
+
+        let points = match (xs, ys) {
+            (Value::List(xs), Value::List(ys)) if xs.len() == ys.len() => xs.into_iter().zip(ys).map(|(x, y)| [x, y]).collect(),
+            (Value::List(xs), Value::Number(y)) => xs.into_iter().map(|x| [x, y]).collect(),
+            (Value::Number(x), Value::List(ys)) => ys.into_iter().map(|y| [x, y]).collect(),
+            (Value::Number(x), Value::Number(y)) => vec![[x, y]],
+
+            _ => {
+                return None;
+            }
+        };
+
+        return Some(points);
+    }
+
+    let inner = trimmed.strip_prefix('[')?.strip_suffix(']')?;
+
+    split_top_level(inner, ',')
+        .iter()
+        .map(|element| {
+            let parts = split_tuple(element)?;
+
+            let coordinates = parts.iter()
+                .map(|p| evaluate_data_if_valid(p, vars, values, angle, graphs)?.as_number())
+                .collect::<Option<Vec<f64>>>()?;
+
+            match coordinates.as_slice() {
+                [x, y] => Some([*x, *y]),
+                _ => None
+            }
+        })
+        .collect()
+}
+
+// a number, list or set of points
+fn evaluate_data_if_valid(
+    input: &str,
+    vars: &HashMap<char, f64>,
+    values: &HashMap<char, Value>,
+    angle: AngleMode,
+    graphs: &HashMap<char, ParseTree>
+) -> Option<Value> {
+    if let Some(points) = points_if_valid(input, vars, values, angle, graphs) {
+        return Some(Value::Points(points));
+    }
+
+    let tokens = lex(input).ok()?;
+    let tree = ParseTree::new(&tokens, graphs).ok()?;
+
+    tree.evaluate_value(None, vars, values, angle).ok()
+}
+
+// every kind of variable a precise evaluation can read, the most precise first
+#[cfg(feature = "precision")]
+pub struct Variables<'a> {
+    pub precise: &'a HashMap<char, BigFloat>,
+    pub exact: &'a HashMap<char, Rational>,
+    pub vars: &'a HashMap<char, f64>,
+    pub angle: AngleMode
+}
+
+#[cfg(feature = "precision")]
+fn evaluate_precise_if_valid(input: &str, vars: &Variables, graphs: &HashMap<char, ParseTree>, digits: usize) -> Option<BigFloat> {
+    let tokens = lex(input).ok()?;
+    let tree = ParseTree::new(&tokens, graphs).ok()?;
+
+    tree.evaluate_precise(None, vars, digits).ok()
+}
+
+fn exact_if_valid(input: &str, exact: &HashMap<char, Rational>, graphs: &HashMap<char, ParseTree>) -> Option<Rational> {
+    let tokens = lex(input).ok()?;
+    let tree = ParseTree::new(&tokens, graphs).ok()?;
+
+    tree.evaluate_rational(exact)
+}
+
+// None for anything that isn't a single number, such as lists, so those can be evaluated as data instead
+fn evaluate_complex_if_valid(
+    input: &str,
+    vars: &HashMap<char, f64>,
+    values: &HashMap<char, Value>,
+    angle: AngleMode,
+    graphs: &HashMap<char, ParseTree>
+) -> Option<Value> {
+    let tokens = lex(input).ok()?;
+    let tree = ParseTree::new(&tokens, graphs).ok()?;
+
+    tree.evaluate_complex(None, vars, values, angle).ok().map(Value::from)
+}
+
+/*
+struct FunctionDef {
+    name: char,
+    differentiates: u32,
+    inside: String,
+}
+
+impl FunctionDef {
+    /*
+    fn new(s: &'a str) -> Self {
+        let name = s.chars().nth(0).unwrap();
+        let differentiates = s.chars()
+            .filter(|x| *x == '\'')
+            .count() as u32;
+        
+        let inside_start = s.find('(').unwrap() + 1;
+        let inside_end = s.find(')').unwrap();
+
+        let inside = &s[inside_start..inside_end];
+
+        FunctionDef { name, differentiates, inside }
+    }
+    */
+
+    fn new(s: &str) {
+        let mut characters= s.chars().peekable();
+
+        let name: char = characters.next().unwrap();
+
+        let mut differentiates: usize = 0;
+        let mut inner: Vec<char> = Vec::new();
+
+        while let Some('\'') = characters.peek() {
+            differentiates += 1;
+            characters.next();
+        }
+
+        characters.next();
+
+        let bracket_depth: u32 = 1;
+
+        //for c in 
+    }
+}
+*/
+
+#[wasm_bindgen]
+impl Evaluator {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Evaluator {
+        Evaluator {
+            vars: HashMap::new(),
+            graphs: HashMap::new(),
+            curves: HashMap::new(),
+            polar_curves: HashMap::new(),
+
+            t_min: 0.0,
+            t_max: 2.0 * std::f64::consts::PI,
+            t_samples: 500,
+
+            theta_min: 0.0,
+            theta_max: 2.0 * std::f64::consts::PI,
+
+            min_y: -10.0,
+            max_y: 10.0,
+
+            sliders: HashMap::new(),
+
+            values: HashMap::new(),
+
+            lines: BTreeMap::new(),
+            line_results: BTreeMap::new(),
+
+            min_x: -10.0,
+            max_x: 10.0,
+
+            angle_mode: AngleMode::Radians,
+            styles: BTreeMap::new(),
+
+            csv_options: CsvOptions::default(),
+
+            complex_mode: false,
+            number_format: NumberFormat::Auto,
+            units_mode: false,
+            quantities: HashMap::new(),
+            exact: HashMap::new(),
+
+            #[cfg(feature = "precision")]
+            precision: None,
+            #[cfg(feature = "precision")]
+            precise: HashMap::new(),
+        }
+    }
+
+    pub fn get_graph_names(&self) -> Vec<u8> {
+        self.graphs
+            .keys()
+            .map(|&x| x as u8)
+            .collect()
+    }
+
+    pub fn find_intercepts(
+        &self,
+        fn1_name: char,
+        fn2_name: char,
+
+        min_x: f64,
+        max_x: f64,
+    ) -> Option<Vec<f64>> {
+        let fn1 = self.graphs.get(&fn1_name)?;
+
+        let fn2 = self.graphs.get(&fn2_name)?;
+
+        // outside of either graph's domain there can't be an intercept
+        let f = |x: f64| match (fn1.evaluate(Some(x), &self.vars, self.angle_mode), fn2.evaluate(Some(x), &self.vars, self.angle_mode)) {
+            (Ok(y1), Ok(y2)) => y1 - y2,
+            _ => f64::NAN
+        };
+
+        let roots_xs = find_roots(f, min_x, max_x, 0.0001, 0.0001);
+
+        let average_ys: Vec<f64> = roots_xs.iter()
+            .map(|r| {
+                (fn1.evaluate(Some(*r), &self.vars, self.angle_mode).unwrap_or(f64::NAN) +
+                fn2.evaluate(Some(*r), &self.vars, self.angle_mode).unwrap_or(f64::NAN)) /
+                2.0
+            })
+            .collect();
+
+        let roots_points: Vec<f64> = roots_xs.into_iter()
+            .zip(average_ys)
+            .flat_map(|(x, y)| vec![x, y].into_iter())
+            .collect();
+            
+        Some(roots_points)
+    }
+
+    // turning points of a graph as [x1, y1, x2, y2...], in the same layout as find_intercepts
+    pub fn find_extrema(&self, fn_name: char, min_x: f64, max_x: f64) -> Option<Vec<f64>> {
+        let tree = self.graphs.get(&fn_name)?;
+
+        let f = |x: f64| tree.evaluate(Some(x), &self.vars, self.angle_mode).unwrap_or(f64::NAN);
+
+        let points: Vec<f64> = find_turning_points(f, min_x, max_x, 0.01)
+            .into_iter()
+            .flat_map(|x| vec![x, f(x)].into_iter())
+            .collect();
+
+        Some(points)
+    }
+
+    pub fn get_slider(&self, var_name: char) -> JsValue {
+        match self.sliders.get(&var_name) {
+            Some(slider) => serde_wasm_bindgen::to_value(slider).unwrap(),
+            None => JsValue::NULL
+        }
+    }
+
+    // every frame of an animation in one call, see animation_frames
+    pub fn animate(&self, var_name: char, values: Vec<f64>, min_x: f64, max_x: f64) -> JsValue {
+        match self.animation_frames(var_name, &values, min_x, max_x) {
+            Some(frames) => serde_wasm_bindgen::to_value(&frames).unwrap(),
+            None => JsValue::NULL
+        }
+    }
+
+    pub fn limit(&self, fn_name: char, a: f64) -> JsValue {
+        match self.estimate_limit(fn_name, a) {
+            Some(v) => serde_wasm_bindgen::to_value(&v).unwrap(),
+            None => JsValue::NULL
+        }
+    }
+
+    pub fn set_parametric_range(&mut self, t_min: f64, t_max: f64, samples: usize) {
+        self.t_min = t_min;
+        self.t_max = t_max;
+        self.t_samples = samples.max(2);
+    }
+
+    pub fn set_polar_range(&mut self, theta_min: f64, theta_max: f64) {
+        self.theta_min = theta_min;
+        self.theta_max = theta_max;
+    }
+
+    pub fn set_y_range(&mut self, min_y: f64, max_y: f64) {
+        self.min_y = min_y;
+        self.max_y = max_y;
+    }
+
+    pub fn polar_area(&self, fn_name: char, theta_start: f64, theta_end: f64) -> Option<f64> {
+        let curve = self.polar_curves.get(&fn_name)?;
+
+        Some(curve.area(&self.vars, self.angle_mode, theta_start, theta_end))
+    }
+
+    // set the line with the given id, returning the results of every line that changed
+    pub fn set_line(&mut self, id: u32, input: String) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.update_line(id, &input)).unwrap()
+    }
+
+    pub fn remove_line(&mut self, id: u32) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.delete_line(id)).unwrap()
+    }
+
+    // graphs are sampled across the viewport, so every line is redrawn
+    pub fn set_viewport(&mut self, min_x: f64, max_x: f64, min_y: f64, max_y: f64) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.update_viewport(min_x, max_x, min_y, max_y)).unwrap()
+    }
+
+    // columns are graph names or expressions in x, tabulated side by side
+    pub fn table(&self, columns: Vec<String>, start: f64, step: f64, count: usize) -> JsValue {
+        let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+
+        match self.table_of_values(&columns, start, step, count) {
+            Some(table) => serde_wasm_bindgen::to_value(&table).unwrap(),
+            None => JsValue::NULL
+        }
+    }
+
+    // precision is decimal places, or None for as many as the value needs
+    pub fn set_csv_options(&mut self, delimiter: char, precision: Option<usize>, header: bool) -> Result<(), JsValue> {
+        self.csv_options = CsvOptions::new(delimiter, precision, header)
+            .ok_or_else(|| JsValue::from_str("invalid csv delimiter"))?;
+
+        Ok(())
+    }
+
+    pub fn graph_csv(&self, fn_name: char, min_x: f64, max_x: f64) -> Option<String> {
+        let tree = self.graphs.get(&fn_name)?;
+
+        Some(segments_to_csv(&sample_graph(tree, &self.vars, self.angle_mode, min_x, max_x), &self.csv_options))
+    }
+
+    pub fn intercepts_csv(&self, fn1_name: char, fn2_name: char, min_x: f64, max_x: f64) -> Option<String> {
+        let points = self.find_intercepts(fn1_name, fn2_name, min_x, max_x)?;
+
+        Some(points_to_csv(&points, &self.csv_options))
+    }
+
+    pub fn extrema_csv(&self, fn_name: char, min_x: f64, max_x: f64) -> Option<String> {
+        let points = self.find_extrema(fn_name, min_x, max_x)?;
+
+        Some(points_to_csv(&points, &self.csv_options))
+    }
+
+    pub fn table_csv(&self, columns: Vec<String>, start: f64, step: f64, count: usize) -> Option<String> {
+        let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        let table = self.table_of_values(&columns, start, step, count)?;
+
+        Some(table_to_csv(&table, &self.csv_options))
+    }
+
+    // the whole plot as a standalone svg, marking where fn1 and fn2 meet if both are given
+    #[allow(clippy::too_many_arguments)]
+    pub fn svg(
+        &self,
+        width: f64,
+        height: f64,
+        axes: bool,
+        grid: bool,
+        legend: bool,
+        fn1_name: Option<char>,
+        fn2_name: Option<char>
+    ) -> String {
+        let settings = PlotSettings { width, height, axes, grid, legend, tick_spacing: None };
+
+        let intercepts_between = match (fn1_name, fn2_name) {
+            (Some(a), Some(b)) => Some((a, b)),
+            _ => None
+        };
+
+        self.render_plot(&settings, intercepts_between)
+    }
+
+    pub fn set_angle_mode(&mut self, degrees: bool) -> JsValue {
+        let mode = match degrees {
+            true => AngleMode::Degrees,
+            false => AngleMode::Radians
+        };
+
+        serde_wasm_bindgen::to_value(&self.update_angle_mode(mode)).unwrap()
+    }
+
+    pub fn set_complex_mode(&mut self, enabled: bool) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.update_complex_mode(enabled)).unwrap()
+    }
+
+    pub fn set_units_mode(&mut self, enabled: bool) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.update_units_mode(enabled)).unwrap()
+    }
+
+    pub fn set_number_format(&mut self, mode: String, digits: Option<usize>) -> Result<JsValue, JsValue> {
+        let format = NumberFormat::parse(&mode, digits)
+            .ok_or_else(|| JsValue::from_str("unknown number format"))?;
+
+        Ok(serde_wasm_bindgen::to_value(&self.update_number_format(format)).unwrap())
+    }
+
+    #[cfg(feature = "precision")]
+    pub fn set_precision(&mut self, digits: Option<usize>) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.update_precision(digits)).unwrap()
+    }
+
+    pub fn set_style(&mut self, id: u32, colour: String, width: f64, dashed: bool, visible: bool) {
+        self.styles.insert(id, GraphStyle { colour, width, dashed, visible });
+    }
+
+    pub fn get_style(&self, id: u32) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.line_style(id)).unwrap()
+    }
+
+    pub fn save_session(&self) -> String {
+        self.session().to_json()
+    }
+
+    // replaces everything with the saved session, throwing a description of what is wrong with it
+    pub fn load_session(&mut self, json: String) -> Result<JsValue, JsValue> {
+        match self.import_session(&json) {
+            Ok(results) => Ok(serde_wasm_bindgen::to_value(&results).unwrap()),
+            Err(e) => Err(JsValue::from_str(&e.to_string()))
+        }
+    }
+
+    // fits a model, such as "linear" or "polynomial" with a degree, to points like (L, M) and saves it as a graph
+    pub fn regression(&mut self, fn_name: char, model: String, degree: Option<usize>, data: String) -> Result<JsValue, JsValue> {
+        let model = Model::parse(&model, degree)
+            .ok_or_else(|| JsValue::from_str("unknown model"))?;
+
+        let fit = self.fit_regression(fn_name, model, &data)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let response = EvaluatorResponse::Regression {
+            segments: sample_graph(&self.graphs[&fn_name], &self.vars, self.angle_mode, self.min_x, self.max_x),
+            var_name: Some(fn_name.to_string()),
+            fit
+        };
+
+        Ok(serde_wasm_bindgen::to_value(&response).unwrap())
+    }
+
+    pub fn evaluate(&mut self, input: String, min_x: f64, max_x: f64) -> JsValue {
+        match self.evaluate_response(&input, min_x, max_x) {
+            Some(response) => serde_wasm_bindgen::to_value(&response).unwrap(),
+            None => JsValue::NULL
+        }
+    }
+}
+
+impl Evaluator {
+    pub fn evaluate_response(&mut self, input: &str, min_x: f64, max_x: f64) -> Option<EvaluatorResponse> {
+        if input.contains('~') {
+            let (names, fit) = self.fit_model(input).ok()?;
+
+            let mut formatted: Vec<String> = names.iter()
+                .zip(&fit.parameters)
+                .map(|(name, parameter)| format!("{} = {}", name, format_number(*parameter, self.number_format)))
+                .collect();
+
+            formatted.push(format!("R² = {}", format_number(fit.r_squared, self.number_format)));
+
+            return Some(EvaluatorResponse::Fit {
+                names,
+                parameters: fit.parameters,
+                r_squared: fit.r_squared,
+                residuals: fit.residuals,
+                formatted: formatted.join(", ")
+            });
+        }
+
+        if is_inequality(input) {
+            let viewport = [min_x, max_x, self.min_y, self.max_y];
+
+            // a comparison without x or y, like 3 > 2, is evaluated as a value instead
+            if let Some(region) = Region::new(input, &self.graphs, &self.vars) {
+                return Some(EvaluatorResponse::Region {
+                    raster: region.raster(&self.vars, self.angle_mode, viewport, REGION_RESOLUTION, REGION_RESOLUTION),
+                    columns: REGION_RESOLUTION,
+                    rows: REGION_RESOLUTION,
+
+                    boundaries: region.boundaries(&self.vars, self.angle_mode, viewport, IMPLICIT_CELLS, IMPLICIT_REFINEMENT)
+                });
+            }
+        }
+
+        let equals_count: usize = split_definition(input).len() - 1;
+
+        match equals_count {
+            0 => {
+
+                let re = Regex::new(r"^[a-zA-Z][']+\(x\)$").unwrap();
+
+                if re.is_match(input) {
+
+                    let fn_name = input.chars().nth(0).unwrap();
+                    let differentiation_count = input.chars()
+                        .filter(|c| *c == '\'')
+                        .count();
+
+                    let tree = match self.graphs.get(&fn_name) {
+                        Some(v) => v,
+                        None => {
+                            return None;
+                        }
+                    };
+
+                    // TODO : TRY USING RC REFCELL TO AVOID CLONES
+                    // this is done as differentiate requires static closure
+                    let cloned_tree = tree.clone();
+                    let cloned_vars = self.vars.clone();
+                    let angle = self.angle_mode;
+
+                    // undefined points become NaN, which the sampler breaks the line at
+                    let f = move |x: f64| cloned_tree.evaluate(Some(x), &cloned_vars, angle).unwrap_or(f64::NAN);
+                    let f_prime = differentiate(f, differentiation_count);
+
+                    let segments = sample_function(
+                        |x: f64| Some(f_prime(x)),
+                        min_x,
+                        max_x,
+                        GRAPH_SAMPLES,
+                        (max_x - min_x) * SAMPLE_TOLERANCE
+                    );
+
+                    return Some(EvaluatorResponse::Graph { segments });
+                }
+
+                // mismatched units reject the line rather than falling back to plain numbers
+                if self.units_mode {
+                    if let Some((_, measurement)) = self.evaluate_quantity(input).ok()? {
+                        return Some(quantity_response(measurement, self.number_format, None, None));
+                    }
+                }
+
+                if let Some(exact) = exact_if_valid(input, &self.exact, &self.graphs) {
+                    return Some(self.with_precise(exact_response(exact, self.number_format, None, None), input, None));
+                }
+
+                let value = match self.evaluate_data(input) {
+                    Some(v) => v,
+                    None => {
+                        return None;
+                    }
+                };
+
+                Some(self.with_precise(data_response(value, self.number_format, None, None), input, None))
+                
+            },
+
+            1 => {
+                // [ function def, function ]
+                let parts: Vec<String> = split_definition(input)
+                    .into_iter()
+                    .map(|x| x.chars().filter(|y| *y != ' ').collect())
+                    .collect();
+
+                let fn_re = Regex::new(r"^[a-zA-Z]\(x\)$")
+                    .expect("regex failed");
+
+                let parametric_re = Regex::new(r"^[a-zA-Z]\(t\)$")
+                    .expect("regex failed");
+
+                if parametric_re.is_match(&parts[0]) {
+                    let fn_name = parts[0]
+                        .chars()
+                        .nth(0)
+                        .unwrap();
+
+                    let curve = ParametricCurve::new(&parts[1], &self.graphs)?;
+                    let segments = self.sample_parametric(&curve, &self.vars, min_x, max_x);
+
+                    if segments.is_empty() {
+                        return None;
+                    }
+
+                    self.curves.insert(fn_name, curve);
+
+                    return Some(EvaluatorResponse::Graph { segments });
+                }
+
+                let polar_re = Regex::new(r"^[a-zA-Z]\(θ\)$")
+                    .expect("regex failed");
+
+                // r = f(θ) is also accepted as a polar curve named r
+                let is_polar = polar_re.is_match(&parts[0]) ||
+                    (parts[0] == "r" && parts[1].contains('θ'));
+
+                if is_polar {
+                    let fn_name = parts[0]
+                        .chars()
+                        .nth(0)
+                        .unwrap();
+
+                    let curve = PolarCurve::new(&parts[1], &self.graphs)?;
+                    let segments = self.sample_polar(&curve, &self.vars, min_x, max_x);
+
+                    if segments.is_empty() {
+                        return None;
+                    }
+
+                    self.polar_curves.insert(fn_name, curve);
+
+                    return Some(EvaluatorResponse::Graph { segments });
+                }
+
+                if fn_re.is_match(&parts[0]) {
+                    let fn_name = parts[0]
+                        .chars()
+                        .nth(0)
+                        .unwrap();
+
+                    let (definition, domain) = split_domain(&parts[1]);
+
+                    let tokens = match lex(definition) {
+                        Ok(v) => v,
+                        Err(_) => {
+                            return None;
+                        }
+                    };
+
+                    let mut tree = match ParseTree::new(&tokens, &self.graphs) {
+                        Ok(v) => v.resolve_values(&self.vars, &self.values, self.angle_mode),
+                        Err(_) => {
+                            return None;
+                        }
+                    };
+
+                    if let Some(domain) = domain {
+                        let conditions = split_top_level(domain, ',')
+                            .iter()
+                            .map(|c| ParseTree::new(&lex(c).ok()?, &self.graphs).ok())
+                            .map(|c| c.map(|c| c.resolve_values(&self.vars, &self.values, self.angle_mode)))
+                            .collect::<Option<Vec<ParseTree>>>()?;
+
+                        tree = tree.restrict(conditions);
+                    }
+
+                    let segments = sample_graph(&tree, &self.vars, self.angle_mode, min_x, max_x);
+
+                    // nowhere on screen could be evaluated, eg an undefined variable
+                    if segments.is_empty() {
+                        return None;
+                    }
+
+                    self.graphs.insert(fn_name, tree);
+
+                    Some(EvaluatorResponse::Graph { segments })
+
+                } else {
+                    // x and y are coordinates, so x = 3 or y = 2x is a relation rather than a variable
+                    let var_re = Regex::new(r"^[a-wzA-Z]$")
+                        .expect("regex failed");
+
+                    if var_re.is_match(&parts[0]) {
+                        let var_name = parts[0]
+                            .chars()
+                            .nth(0)
+                            .unwrap();
+
+                        // i always means the imaginary unit in complex mode
+                        if var_name == 'i' && self.complex_mode {
+                            return None;
+                        }
+
+                        // a = 2 {0..10 step 0.5} is shown as a slider
+                        let (definition, range) = split_domain(&parts[1]);
+
+                        let slider = match range {
+                            Some(spec) => Some(Slider::parse(spec, |bound| {
+                                evaluate_value_if_valid(bound, &self.vars, self.angle_mode, &self.graphs)
+                            })?),
+
+                            None => None
+                        };
+
+                        if self.units_mode {
+                            if let Some((quantity, measurement)) = self.evaluate_quantity(definition).ok()? {
+                                self.values.remove(&var_name);
+                                self.sliders.remove(&var_name);
+
+                                // kept in base units, so graphs using the variable still work
+                                self.set_var(var_name, Some(quantity.value));
+                                self.quantities.insert(var_name, quantity);
+
+                                if let Some(s) = slider {
+                                    self.sliders.insert(var_name, s);
+                                }
+
+                                return Some(quantity_response(measurement, self.number_format, Some(var_name.to_string()), slider));
+                            }
+                        }
+
+                        if let Some(exact) = exact_if_valid(definition, &self.exact, &self.graphs) {
+                            self.values.remove(&var_name);
+                            self.sliders.remove(&var_name);
+
+                            self.set_var(var_name, Some(exact.to_f64()));
+                            self.exact.insert(var_name, exact);
+
+                            if let Some(s) = slider {
+                                self.sliders.insert(var_name, s);
+                            }
+
+                            let response = exact_response(exact, self.number_format, Some(var_name.to_string()), slider);
+                            return Some(self.with_precise(response, definition, Some(var_name)));
+                        }
+
+                        if let Some(value) = self.evaluate_data(definition) {
+                            self.set_var(var_name, None);
+                            self.values.remove(&var_name);
+                            self.sliders.remove(&var_name);
+
+                            match &value {
+                                Value::Number(n) => {
+                                    self.set_var(var_name, Some(*n));
+                                },
+
+                                // only a single number can be a slider
+                                _ if slider.is_some() => {
+                                    return None;
+                                },
+
+                                _ => {
+                                    self.values.insert(var_name, value.clone());
+                                }
+                            }
+
+                            if let Some(s) = slider {
+                                self.sliders.insert(var_name, s);
+                            }
+
+                            let response = data_response(value, self.number_format, Some(var_name.to_string()), slider);
+                            return Some(self.with_precise(response, definition, Some(var_name)));
+                        }
+                    }
+
+                    // anything else is traced as an implicit curve in x and y
+                    let curve = ImplicitCurve::new(&parts[0], &parts[1], &self.graphs, &self.vars)?;
+                    let segments = curve.trace(
+                        &self.vars,
+                        self.angle_mode,
+                        [min_x, max_x, self.min_y, self.max_y],
+                        IMPLICIT_CELLS,
+                        IMPLICIT_REFINEMENT
+                    );
+
+                    Some(EvaluatorResponse::Graph { segments })
+                }
+            }
+
+            _ => None
+        }
+    }
+
+    // None when no units are involved, so the answer is an ordinary number,
+    // 90 km/h to m/s is given in the unit after to and anything else in base or derived SI units
+    fn evaluate_quantity(&self, input: &str) -> Result<Option<(Quantity, Measurement)>, UnitError> {
+        let (expression, target) = match input.rsplit_once(" to ") {
+            Some((expression, target)) => (expression, Some(target)),
+            None => (input, None)
+        };
+
+        // a name used for a variable or graph anywhere, like a list L, is never a unit
+        let names: HashSet<char> = self.vars.keys()
+            .chain(self.values.keys())
+            .chain(self.graphs.keys())
+            .chain(self.curves.keys())
+            .chain(self.polar_curves.keys())
+            .chain(self.lines.values().filter_map(|line| line.name.as_ref()))
+            .copied()
+            .collect();
+
+        let parse = |text: &str| {
+            let tokens = lex_units(text, &names).map_err(|_| UnitError::Invalid)?;
+
+            ParseTree::new(&tokens, &self.graphs).map_err(|_| UnitError::Invalid)
+        };
+
+        // anything that can't be read with units is left to be read without them
+        let tree = match parse(expression) {
+            Ok(tree) => tree,
+            Err(_) if target.is_none() => {
+                return Ok(None);
+            },
+
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        if target.is_none() && !tree.has_units(&self.quantities) {
+            return Ok(None);
+        }
+
+        let quantity = tree.evaluate_quantity(None, &self.vars, &self.quantities, self.angle_mode)?;
+
+        let measurement = match target {
+            Some(unit) => quantity.in_unit(&parse(unit)?.evaluate_quantity(None, &self.vars, &self.quantities, self.angle_mode)?, unit)?,
+            None => quantity.measurement()
+        };
+
+        Ok(Some((quantity, measurement)))
+    }
+
+    // units and matrices that don't fit together are reported as such, anything else by what's wrong with the text
+    fn line_error(&self, input: &str) -> DefinitionError {
+        let parts = split_definition(input);
+        let (definition, _) = split_domain(parts[parts.len() - 1]);
+
+        if self.complex_mode && parts.len() == 2 && parts[0].trim() == "i" {
+            return DefinitionError::ImaginaryUnit;
+        }
+
+        if self.units_mode {
+            if let Err(UnitError::Incompatible { left, right }) = self.evaluate_quantity(definition) {
+                return DefinitionError::IncompatibleUnits { left: left.to_string(), right: right.to_string() };
+            }
+        }
+
+        let value = lex(definition).ok()
+            .and_then(|tokens| ParseTree::new(&tokens, &self.graphs).ok())
+            .map(|tree| tree.evaluate_value(None, &self.vars, &self.values, self.angle_mode));
+
+        if let Some(Err(ValueError::Matrix(e))) = value {
+            return DefinitionError::Matrix { reason: e.to_string() };
+        }
+
+        invalid_reason(input)
+    }
+
+    // a number, a list or points, with single numbers possibly complex in complex mode
+    fn evaluate_data(&self, input: &str) -> Option<Value> {
+        let complex = match self.complex_mode {
+            true => evaluate_complex_if_valid(input, &self.vars, &self.values, self.angle_mode, &self.graphs),
+            false => None
+        };
+
+        complex.or_else(|| evaluate_data_if_valid(input, &self.vars, &self.values, self.angle_mode, &self.graphs))
+    }
+
+    pub fn update_line(&mut self, id: u32, input: &str) -> Vec<LineResult> {
+        let line = Definition::new(input);
+
+        let mut changed: HashSet<char> = HashSet::new();
+        changed.extend(line.name);
+
+        if let Some(old) = self.lines.insert(id, line) {
+            changed.extend(old.name);
+        }
+
+        self.reevaluate(Some(id), changed)
+    }
+
+    pub fn delete_line(&mut self, id: u32) -> Vec<LineResult> {
+        self.line_results.remove(&id);
+        self.styles.remove(&id);
+
+        let old = match self.lines.remove(&id) {
+            Some(v) => v,
+            None => {
+                return Vec::new();
+            }
+        };
+
+        let changed: HashSet<char> = old.name.into_iter().collect();
+
+        self.reevaluate(None, changed)
+    }
+
+    pub fn update_viewport(&mut self, min_x: f64, max_x: f64, min_y: f64, max_y: f64) -> Vec<LineResult> {
+        self.min_x = min_x;
+        self.max_x = max_x;
+        self.min_y = min_y;
+        self.max_y = max_y;
+
+        let ids: Vec<u32> = self.lines.keys().copied().collect();
+        let names: HashSet<char> = self.lines.values()
+            .filter_map(|line| line.name)
+            .collect();
+
+        for name in &names {
+            self.forget(*name);
+        }
+
+        self.evaluate_lines(&ids.into_iter().collect())
+    }
+
+    // trig functions take the new unit, so every line is redrawn
+    pub fn update_angle_mode(&mut self, mode: AngleMode) -> Vec<LineResult> {
+        self.angle_mode = mode;
+
+        self.update_viewport(self.min_x, self.max_x, self.min_y, self.max_y)
+    }
+
+    // every value is worked out again in the new mode
+    pub fn update_complex_mode(&mut self, enabled: bool) -> Vec<LineResult> {
+        self.complex_mode = enabled;
+
+        self.update_viewport(self.min_x, self.max_x, self.min_y, self.max_y)
+    }
+
+    pub fn update_units_mode(&mut self, enabled: bool) -> Vec<LineResult> {
+        self.units_mode = enabled;
+
+        self.update_viewport(self.min_x, self.max_x, self.min_y, self.max_y)
+    }
+
+    pub fn update_number_format(&mut self, format: NumberFormat) -> Vec<LineResult> {
+        self.number_format = format;
+
+        self.update_viewport(self.min_x, self.max_x, self.min_y, self.max_y)
+    }
+
+    // None goes back to plain f64 answers
+    #[cfg(feature = "precision")]
+    pub fn update_precision(&mut self, digits: Option<usize>) -> Vec<LineResult> {
+        self.precision = digits.filter(|d| *d > 0);
+
+        self.update_viewport(self.min_x, self.max_x, self.min_y, self.max_y)
+    }
+
+    // the value to the chosen number of digits, saved for the variable it defines
+    #[cfg(feature = "precision")]
+    fn with_precise(&mut self, mut response: EvaluatorResponse, input: &str, var_name: Option<char>) -> EvaluatorResponse {
+        if let Some(name) = var_name {
+            self.precise.remove(&name);
+        }
+
+        let digits = match (&response, self.precision) {
+            (EvaluatorResponse::Value { imaginary: None, .. }, Some(digits)) => digits,
+            _ => {
+                return response;
+            }
+        };
+
+        let vars = Variables { precise: &self.precise, exact: &self.exact, vars: &self.vars, angle: self.angle_mode };
+        let value = evaluate_precise_if_valid(input, &vars, &self.graphs, digits);
+
+        if let EvaluatorResponse::Value { precise, .. } = &mut response {
+            *precise = value.as_ref().map(|v| v.to_string());
+        }
+
+        if let (Some(name), Some(value)) = (var_name, value) {
+            self.precise.insert(name, value);
+        }
+
+        response
+    }
+
+    #[cfg(not(feature = "precision"))]
+    fn with_precise(&mut self, response: EvaluatorResponse, _input: &str, _var_name: Option<char>) -> EvaluatorResponse {
+        response
+    }
+
+    pub fn line_style(&self, id: u32) -> GraphStyle {
+        self.styles.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub fn session(&self) -> Session {
+        let lines = self.lines.iter()
+            .map(|(id, line)| SessionLine {
+                id: *id,
+                input: line.input.clone(),
+                style: self.styles.get(id).cloned()
+            })
+            .collect();
+
+        Session {
+            version: SESSION_VERSION,
+            lines,
+            viewport: Viewport {
+                min_x: self.min_x,
+                max_x: self.max_x,
+                min_y: self.min_y,
+                max_y: self.max_y
+            },
+            angle_mode: self.angle_mode,
+            sliders: self.sliders.iter().map(|(name, slider)| (*name, *slider)).collect()
+        }
+    }
+
+    // nothing is changed if the session can't be read
+    pub fn import_session(&mut self, json: &str) -> Result<Vec<LineResult>, SessionError> {
+        let session = Session::from_json(json)?;
+
+        // the parameter ranges and number modes aren't part of a session
+        *self = Evaluator {
+            complex_mode: self.complex_mode,
+            number_format: self.number_format,
+            units_mode: self.units_mode,
+
+            #[cfg(feature = "precision")]
+            precision: self.precision,
+
+            t_min: self.t_min,
+            t_max: self.t_max,
+            t_samples: self.t_samples,
+
+            theta_min: self.theta_min,
+            theta_max: self.theta_max,
+
+            ..Evaluator::new()
+        };
+
+        let v = session.viewport;
+        self.min_x = v.min_x;
+        self.max_x = v.max_x;
+        self.min_y = v.min_y;
+        self.max_y = v.max_y;
 
-    Some(value)
+        self.angle_mode = session.angle_mode;
 
-}
+        for line in session.lines {
+            if let Some(style) = line.style {
+                self.styles.insert(line.id, style);
+            }
 
-/*
-struct FunctionDef {
-    name: char,
-    differentiates: u32,
-    inside: String,
-}
+            self.lines.insert(line.id, Definition::new(&line.input));
+        }
 
-impl FunctionDef {
-    /*
-    fn new(s: &'a str) -> Self {
-        let name = s.chars().nth(0).unwrap();
-        let differentiates = s.chars()
-            .filter(|x| *x == '\'')
-            .count() as u32;
-        
-        let inside_start = s.find('(').unwrap() + 1;
-        let inside_end = s.find(')').unwrap();
+        let ids: HashSet<u32> = self.lines.keys().copied().collect();
+        let results = self.evaluate_lines(&ids);
 
-        let inside = &s[inside_start..inside_end];
+        // ranges saved for variables that still exist
+        for (name, slider) in session.sliders {
+            if self.vars.contains_key(&name) {
+                self.sliders.insert(name, slider);
+            }
+        }
 
-        FunctionDef { name, differentiates, inside }
+        Ok(results)
     }
-    */
 
-    fn new(s: &str) {
-        let mut characters= s.chars().peekable();
+    // the style of the line that defines the name
+    fn style_of(&self, name: char) -> GraphStyle {
+        self.lines.iter()
+            .find(|(_, line)| line.name == Some(name))
+            .map(|(id, _)| self.line_style(*id))
+            .unwrap_or_default()
+    }
 
-        let name: char = characters.next().unwrap();
+    pub fn render_plot(&self, settings: &PlotSettings, intercepts_between: Option<(char, char)>) -> String {
+        let viewport = Viewport {
+            min_x: self.min_x,
+            max_x: self.max_x,
+            min_y: self.min_y,
+            max_y: self.max_y
+        };
 
-        let mut differentiates: usize = 0;
-        let mut inner: Vec<char> = Vec::new();
+        let mut series: Vec<PlotSeries> = Vec::new();
 
-        while let Some('\'') = characters.peek() {
-            differentiates += 1;
-            characters.next();
+        let mut graph_names: Vec<&char> = self.graphs.keys().collect();
+        graph_names.sort_unstable();
+
+        for name in graph_names {
+            series.push(PlotSeries::curve(
+                format!("{}(x)", name),
+                sample_graph(&self.graphs[name], &self.vars, self.angle_mode, self.min_x, self.max_x),
+                self.style_of(*name)
+            ));
         }
 
-        characters.next();
+        let mut curve_names: Vec<&char> = self.curves.keys().collect();
+        curve_names.sort_unstable();
 
-        let bracket_depth: u32 = 1;
+        for name in curve_names {
+            series.push(PlotSeries::curve(
+                format!("{}(t)", name),
+                self.sample_parametric(&self.curves[name], &self.vars, self.min_x, self.max_x),
+                self.style_of(*name)
+            ));
+        }
 
-        //for c in 
-    }
-}
-*/
+        let mut polar_names: Vec<&char> = self.polar_curves.keys().collect();
+        polar_names.sort_unstable();
 
-#[wasm_bindgen]
-impl Evaluator {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> Evaluator {
-        Evaluator {
-            vars: HashMap::new(),
-            graphs: HashMap::new(),
+        for name in polar_names {
+            series.push(PlotSeries::curve(
+                format!("{}(θ)", name),
+                self.sample_polar(&self.polar_curves[name], &self.vars, self.min_x, self.max_x),
+                self.style_of(*name)
+            ));
+        }
+
+        // implicit curves, inequalities and scatter plots aren't kept by name, so they come from
+        // what each line last drew, which is always for the current viewport
+        for (id, line) in &self.lines {
+            let named_graph = line.name.is_some_and(|name| {
+                self.graphs.contains_key(&name) || self.curves.contains_key(&name) || self.polar_curves.contains_key(&name)
+            });
+
+            let response = match self.line_results.get(id).and_then(|result| result.response.as_ref()) {
+                Some(response) => response,
+                None => {
+                    continue;
+                }
+            };
+
+            let name = line.input.trim().to_string();
+            let style = self.line_style(*id);
+
+            match response {
+                EvaluatorResponse::Graph { segments } if !named_graph => {
+                    series.push(PlotSeries::curve(name, segments.clone(), style));
+                },
+
+                EvaluatorResponse::Region { raster, columns, rows, boundaries } => {
+                    let (dashed, solid): (Vec<&RegionBoundary>, Vec<&RegionBoundary>) = boundaries.iter()
+                        .partition(|boundary| boundary.strict);
+
+                    series.push(PlotSeries {
+                        segments: solid.into_iter().flat_map(|boundary| boundary.segments.clone()).collect(),
+                        dashed_segments: dashed.into_iter().flat_map(|boundary| boundary.segments.clone()).collect(),
+                        shading: Some(Shading { cells: raster.clone(), columns: *columns, rows: *rows }),
+                        ..PlotSeries::curve(name, Vec::new(), style)
+                    });
+                },
+
+                EvaluatorResponse::Scatter { points, .. } => {
+                    series.push(PlotSeries { points: points.clone(), ..PlotSeries::curve(name, Vec::new(), style) });
+                },
+
+                _ => {}
+            }
         }
+
+        let intercepts: Vec<[f64; 2]> = intercepts_between
+            .and_then(|(a, b)| self.find_intercepts(a, b, self.min_x, self.max_x))
+            .unwrap_or_default()
+            .chunks(2)
+            .map(|p| [p[0], p[1]])
+            .collect();
+
+        render_svg(&series, &intercepts, viewport, settings)
     }
 
-    pub fn get_graph_names(&self) -> Vec<u8> {
-        self.graphs
-            .keys()
-            .map(|&x| x as u8)
-            .collect()
+    pub fn line_result(&self, id: u32) -> Option<&LineResult> {
+        self.line_results.get(&id)
     }
 
-    pub fn find_intercepts(
-        &self,
-        fn1_name: char,
-        fn2_name: char,
+    // re-evaluate the line along with every line that defines or depends on one of the changed names
+    fn reevaluate(&mut self, id: Option<u32>, mut changed: HashSet<char>) -> Vec<LineResult> {
+        let mut affected: HashSet<u32> = id.into_iter().collect();
 
-        min_x: f64,
-        max_x: f64,
-    ) -> Option<Vec<f64>> {
-        let fn1 = match self.graphs.get(&fn1_name) {
-            Some(v) => v,
-            None => {
-                return None
+        loop {
+            let newly_affected: Vec<u32> = self.lines.iter()
+                .filter(|(line_id, _)| !affected.contains(line_id))
+                .filter(|(_, line)| {
+                    line.dependencies.iter().any(|name| changed.contains(name)) ||
+                    line.name.is_some_and(|name| changed.contains(&name))
+                })
+                .map(|(line_id, _)| *line_id)
+                .collect();
+
+            if newly_affected.is_empty() {
+                break;
             }
-        };
 
-        let fn2 = match self.graphs.get(&fn2_name) {
-            Some(v) => v,
-            None => {
-                return None
+            for line_id in newly_affected {
+                changed.extend(self.lines[&line_id].name);
+                affected.insert(line_id);
             }
-        };
+        }
 
-        let f = |x: f64| 
-            fn1.evaluate(Some(x), &self.vars).unwrap() - fn2.evaluate(Some(x), &self.vars).unwrap();
+        for name in &changed {
+            self.forget(*name);
+        }
 
-        let roots_xs = find_roots(f, min_x, max_x, 0.0001, 0.0001);
+        self.evaluate_lines(&affected)
+    }
 
-        let average_ys: Vec<f64> = roots_xs.iter()
-            .map(|r| {
-                (fn1.evaluate(Some(*r), &self.vars).unwrap() +
-                fn2.evaluate(Some(*r), &self.vars).unwrap()) /
-                2.0
-            })
-            .collect();
+    fn evaluate_lines(&mut self, affected: &HashSet<u32>) -> Vec<LineResult> {
+        let (order, errors) = evaluation_order(&self.lines);
 
-        let roots_points: Vec<f64> = roots_xs.into_iter()
-            .zip(average_ys)
-            .flat_map(|(x, y)| vec![x, y].into_iter())
+        for id in order {
+            if !affected.contains(&id) {
+                continue;
+            }
+
+            let input = self.lines[&id].input.clone();
+            let response = self.evaluate_response(&input, self.min_x, self.max_x);
+
+            let error = match response {
+                Some(_) => None,
+                None => Some(self.line_error(&input))
+            };
+
+            self.line_results.insert(id, LineResult { id, response, error });
+        }
+
+        let redrawn = self.redraw_fitted(affected);
+
+        for (id, error) in errors {
+            if affected.contains(&id) {
+                self.line_results.insert(id, LineResult { id, response: None, error: Some(error) });
+            }
+        }
+
+        let mut results: Vec<LineResult> = affected.iter()
+            .chain(redrawn.iter().filter(|id| !affected.contains(id)))
+            .filter_map(|id| self.line_results.get(id))
+            .cloned()
             .collect();
-            
-        Some(roots_points)
+
+        results.sort_by_key(|result| result.id);
+        results
     }
 
-    pub fn evaluate(&mut self, input: String, min_x: f64, max_x: f64) -> JsValue {
-        let equals_count: usize = input.chars()
-            .filter(|x| *x == '=')
-            .count();
+    // fits change variables without defining them, so lines using the fitted variables,
+    // and anything depending on those lines, are evaluated again with the fitted values
+    fn redraw_fitted(&mut self, affected: &HashSet<u32>) -> Vec<u32> {
+        let mut fitted: HashSet<char> = affected.iter()
+            .filter_map(|id| match &self.line_results.get(id)?.response {
+                Some(EvaluatorResponse::Fit { names, .. }) => Some(names.clone()),
+                _ => None
+            })
+            .flatten()
+            .collect();
 
-        match equals_count {
-            0 => {
+        if fitted.is_empty() {
+            return Vec::new();
+        }
 
-                let re = Regex::new(r"^[a-zA-Z][']+\(x\)$").unwrap();
+        let (order, _) = evaluation_order(&self.lines);
+        let mut redrawn: Vec<u32> = Vec::new();
 
-                if re.is_match(&input) {
+        for id in order {
+            let line = &self.lines[&id];
 
-                    let fn_name = input.chars().nth(0).unwrap();
-                    let differentiation_count = input.chars()
-                        .filter(|c| *c == '\'')
-                        .count();
+            let uses_fitted = line.dependencies.iter().any(|name| fitted.contains(name));
+            let defines_fitted = line.name.is_some_and(|name| fitted.contains(&name));
 
-                    let tree = match self.graphs.get(&fn_name) {
-                        Some(v) => v,
-                        None => {
-                            return JsValue::NULL;
-                        }
-                    };
+            if !uses_fitted || defines_fitted || line.input.contains('~') {
+                continue;
+            }
 
-                    let mut points: Vec<[f64; 2]> = Vec::new();
+            let input = line.input.clone();
+            fitted.extend(line.name);
 
-                    // TODO : TRY USING RC REFCELL TO AVOID CLONES
-                    // this is done as differentiate requires static closure
-                    let cloned_tree = tree.clone();
-                    let cloned_vars = self.vars.clone();
+            let response = self.evaluate_response(&input, self.min_x, self.max_x);
 
-                    // TODO : FIX THIS SO IT DOESN'T CRASH IF f(x) FAILS TO EVALUATE
-                    let f = move |x: f64| cloned_tree.evaluate(Some(x), &cloned_vars).unwrap();
-                    let f_prime = differentiate(f, differentiation_count);
+            let error = match response {
+                Some(_) => None,
+                None => Some(self.line_error(&input))
+            };
 
-                    let mut x: f64 = min_x;
-                    while x <= max_x {
-                        let y = f_prime(x);
-                        points.push([x, y]);
+            self.line_results.insert(id, LineResult { id, response, error });
+            redrawn.push(id);
+        }
 
-                        x += 0.1;
-                    }
+        redrawn
+    }
 
-                    return serde_wasm_bindgen::to_value(&
-                        EvaluatorResponse::Graph { points }
-                    ).unwrap();
-                }
+    // every change to a variable goes through here, so the exact, precise and unit forms
+    // kept beside it never outlive the value they were worked out for
+    fn set_var(&mut self, name: char, value: Option<f64>) {
+        match value {
+            Some(value) => self.vars.insert(name, value),
+            None => self.vars.remove(&name)
+        };
 
-                let value = match evaluate_value_if_valid(&input, &self.vars, &self.graphs) {
-                    Some(v) => v,
-                    None => {
-                        return JsValue::NULL;
-                    }
-                };
+        self.exact.remove(&name);
+        self.quantities.remove(&name);
 
-                serde_wasm_bindgen::to_value(
-                    &EvaluatorResponse::Value {
-                        value,
-                        var_name: None
-                    }
-                )
-                    .expect("failed to serialize")
-                
-            },
+        #[cfg(feature = "precision")]
+        self.precise.remove(&name);
+    }
 
-            1 => {
-                // [ function def, function ]
-                let parts: Vec<String> = input.split('=')
-                    .map(|x| x.chars().filter(|y| *y != ' ').collect())
-                    .collect();
+    // remove whatever is stored under the name, before it is defined again
+    fn forget(&mut self, name: char) {
+        self.set_var(name, None);
+        self.graphs.remove(&name);
+        self.curves.remove(&name);
+        self.polar_curves.remove(&name);
+        self.sliders.remove(&name);
+        self.values.remove(&name);
+    }
 
-                let fn_re = Regex::new(r"^[a-zA-Z]\(x\)$")
-                    .expect("regex failed");
+    fn sample_parametric(&self, curve: &ParametricCurve, vars: &HashMap<char, f64>, min_x: f64, max_x: f64) -> Vec<Vec<[f64; 2]>> {
+        curve.sample(vars, self.angle_mode, self.t_min, self.t_max, self.t_samples, (max_x - min_x) * SAMPLE_TOLERANCE)
+    }
 
-                if fn_re.is_match(&parts[0]) {
-                    let fn_name = parts[0]
-                        .chars()
-                        .nth(0)
-                        .unwrap();
+    fn sample_polar(&self, curve: &PolarCurve, vars: &HashMap<char, f64>, min_x: f64, max_x: f64) -> Vec<Vec<[f64; 2]>> {
+        curve.sample(vars, self.angle_mode, self.theta_min, self.theta_max, POLAR_SAMPLES, (max_x - min_x) * SAMPLE_TOLERANCE)
+    }
 
-                    let tokens = match lex(&parts[1]) {
-                        Ok(v) => v,
-                        Err(_) => {
-                            return JsValue::NULL;
-                        }
-                    };
+    // redraw every graph that uses the variable for each of the values, directly or through
+    // variables defined from it, without touching anything else, if no values are given
+    // the variable's slider range is used
+    pub fn animation_frames(&self, var_name: char, values: &[f64], min_x: f64, max_x: f64) -> Option<Vec<AnimationFrame>> {
+        if !self.vars.contains_key(&var_name) {
+            return None;
+        }
 
-                    let tree = match ParseTree::new(&tokens, &self.graphs) {
-                        Ok(v) => v,
-                        Err(_) => {
-                            return JsValue::NULL;
-                        }
-                    };
+        let values: Vec<f64> = if values.is_empty() {
+            self.sliders.get(&var_name)?.frames()
+        } else {
+            values.to_vec()
+        };
 
-                    let mut points: Vec<[f64; 2]> = Vec::new();
+        // variables like b = 2a are worked out again for every frame, in an order where
+        // each comes after the ones it uses
+        let mut affected: HashSet<char> = HashSet::from([var_name]);
+        let mut dependents: Vec<(char, &str)> = Vec::new();
 
-                    let mut x: f64 = min_x;
-                    while x <= max_x {
-                        let y = match tree.evaluate(Some(x), &self.vars) {
-                            Ok(v) => v,
-                            Err(_) => {
-                                return JsValue::NULL;
-                            }
-                        };
+        for id in evaluation_order(&self.lines).0 {
+            let line = &self.lines[&id];
 
-                        points.push([x, y]);
+            let name = match line.name {
+                Some(name) if name != var_name && self.vars.contains_key(&name) => name,
+                _ => {
+                    continue;
+                }
+            };
 
-                        x += 0.1;
-                    }
+            if line.dependencies.iter().any(|used| affected.contains(used)) {
+                let (definition, _) = split_domain(split_definition(&line.input)[1]);
 
-                    self.graphs.insert(fn_name, tree);
+                affected.insert(name);
+                dependents.push((name, definition));
+            }
+        }
 
-                    serde_wasm_bindgen::to_value(
-                        &EvaluatorResponse::Graph { points }
-                    ).unwrap()
+        let mut graph_names: Vec<char> = self.graphs.iter()
+            .filter(|(_, tree)| affected.iter().any(|name| tree.references(*name)))
+            .map(|(name, _)| *name)
+            .collect();
+        graph_names.sort_unstable();
 
-                } else {
-                    let var_name = parts[0]
-                        .chars()
-                        .nth(0)
-                        .unwrap();
+        let mut curve_names: Vec<char> = self.curves.iter()
+            .filter(|(_, curve)| affected.iter().any(|name| curve.references(*name)))
+            .map(|(name, _)| *name)
+            .collect();
+        curve_names.sort_unstable();
 
-                    let value = match evaluate_value_if_valid(&parts[1], &self.vars, &self.graphs) {
-                        Some(v) => v,
-                        None => {
-                            return JsValue::NULL;
-                        }
+        let mut polar_names: Vec<char> = self.polar_curves.iter()
+            .filter(|(_, curve)| affected.iter().any(|name| curve.references(*name)))
+            .map(|(name, _)| *name)
+            .collect();
+        polar_names.sort_unstable();
+
+        let frames = values.into_iter()
+            .map(|value| {
+                let mut vars = self.vars.clone();
+                vars.insert(var_name, value);
+
+                for (name, definition) in &dependents {
+                    match evaluate_value_if_valid(definition, &vars, self.angle_mode, &self.graphs) {
+                        Some(v) => vars.insert(*name, v),
+                        None => vars.remove(name)
                     };
+                }
 
-                    self.vars.insert(var_name, value);
+                let mut graphs: Vec<FrameGraph> = Vec::new();
 
-                    serde_wasm_bindgen::to_value(
-                        &EvaluatorResponse::Value {
-                            value,
-                            var_name: Some(var_name.to_string())
-                        }
-                    ).unwrap()
+                for name in &graph_names {
+                    graphs.push(FrameGraph {
+                        name: name.to_string(),
+                        segments: sample_graph(&self.graphs[name], &vars, self.angle_mode, min_x, max_x)
+                    });
                 }
+
+                for name in &curve_names {
+                    graphs.push(FrameGraph {
+                        name: name.to_string(),
+                        segments: self.sample_parametric(&self.curves[name], &vars, min_x, max_x)
+                    });
+                }
+
+                for name in &polar_names {
+                    graphs.push(FrameGraph {
+                        name: name.to_string(),
+                        segments: self.sample_polar(&self.polar_curves[name], &vars, min_x, max_x)
+                    });
+                }
+
+                AnimationFrame { value, graphs }
+            })
+            .collect();
+
+        Some(frames)
+    }
+
+    // None if a column isn't a graph or an expression, undefined values only blank their own cell
+    pub fn table_of_values(&self, columns: &[&str], start: f64, step: f64, count: usize) -> Option<Table> {
+        let graph_re = Regex::new(r"^([a-zA-Z])(\(x\))?$")
+            .expect("regex failed");
+
+        let columns = columns.iter()
+            .map(|column| {
+                let column = column.trim();
+
+                // f or f(x) is the graph, anything else is an expression
+                let graph = graph_re.captures(column)
+                    .and_then(|captures| captures[1].chars().next())
+                    .and_then(|name| self.graphs.get(&name));
+
+                let tree = match graph {
+                    Some(tree) => tree.clone(),
+                    None => ParseTree::new(&lex(column).ok()?, &self.graphs).ok()?
+                        .resolve_values(&self.vars, &self.values, self.angle_mode)
+                };
+
+                Some((column.to_string(), tree))
+            })
+            .collect::<Option<Vec<(String, ParseTree)>>>()?;
+
+        Some(tabulate(&columns, &self.vars, self.angle_mode, start, step, count))
+    }
+
+    // the fitted model replaces any graph with the same name
+    pub fn fit_regression(&mut self, fn_name: char, model: Model, data: &str) -> Result<Fit, FitError> {
+        let points = points_if_valid(data, &self.vars, &self.values, self.angle_mode, &self.graphs)
+            .ok_or(FitError::InvalidData)?;
+
+        let fit = fit(model, &points)?;
+
+        let tree = lex(&fit.expression).ok()
+            .and_then(|lexed| ParseTree::new(&lexed, &self.graphs).ok())
+            .ok_or(FitError::InvalidData)?;
+
+        self.graphs.insert(fn_name, tree);
+
+        Ok(fit)
+    }
+
+    // fits data ~ model, such as M ~ a sin(bL + c), by changing every variable in the model
+    // that isn't data or a graph, starting from their current values and keeping the fitted ones
+    pub fn fit_model(&mut self, input: &str) -> Result<(Vec<char>, ParameterFit), FitError> {
+        let (data, model) = match input.split('~').collect::<Vec<&str>>()[..] {
+            [data, model] => (data, model),
+            _ => {
+                return Err(FitError::InvalidData);
+            }
+        };
+
+        let observed = match evaluate_data_if_valid(data, &self.vars, &self.values, self.angle_mode, &self.graphs) {
+            Some(Value::List(observed)) => observed,
+            _ => {
+                return Err(FitError::InvalidData);
+            }
+        };
+
+        let tree = lex(model).ok()
+            .and_then(|lexed| ParseTree::new(&lexed, &self.graphs).ok())
+            .ok_or(FitError::InvalidData)?;
+
+        let mut names: Vec<char> = referenced_names(model).into_iter()
+            .filter(|name| !self.values.contains_key(name) && !self.graphs.contains_key(name))
+            .collect();
+        names.sort_unstable();
+
+        if names.is_empty() {
+            return Err(FitError::InvalidData);
+        }
+
+        // parameters without a value yet start at 1
+        let initial: Vec<f64> = names.iter()
+            .map(|name| self.vars.get(name).copied().unwrap_or(1.0))
+            .collect();
+
+        let predict = |parameters: &[f64]| {
+            let mut vars = self.vars.clone();
+            vars.extend(names.iter().copied().zip(parameters.iter().copied()));
+
+            match tree.evaluate_value(None, &vars, &self.values, self.angle_mode).ok()? {
+                Value::List(predicted) => Some(predicted),
+                Value::Number(n) => Some(vec![n; observed.len()]),
+                Value::Points(_) | Value::Complex(_) | Value::Matrix(_) | Value::Decomposition(_) => None
             }
+        };
 
-            _ => JsValue::NULL
+        let fit = fit_parameters(predict, &observed, &initial)?;
+
+        for (name, value) in names.iter().zip(&fit.parameters) {
+            self.set_var(*name, Some(*value));
         }
+
+        Ok((names, fit))
+    }
+
+    pub fn estimate_limit(&self, fn_name: char, a: f64) -> Option<Limit> {
+        let tree = self.graphs.get(&fn_name)?;
+
+        let f = |x: f64| tree.evaluate(Some(x), &self.vars, self.angle_mode).unwrap_or(f64::NAN);
+
+        Some(limit(f, a))
     }
 }
 
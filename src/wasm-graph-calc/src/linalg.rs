@@ -0,0 +1,692 @@
+use serde::{Serialize, Deserialize};
+
+use crate::complex::Complex;
+use crate::format::{NumberFormat, format_number, format_complex, format_list, format_rows};
+use crate::matrix::{Matrix, MatrixError};
+use crate::value::Value;
+
+pub const DECOMPOSITIONS: [&str; 4] = ["lu", "qr", "eig", "svd"];
+
+// qr steps allowed for each eigenvalue, and jacobi sweeps for the svd, before giving up
+const MAX_ITERATIONS: usize = 100;
+const MAX_SWEEPS: usize = 60;
+
+// a matrix is symmetric when mirrored entries differ by this much of the largest at most
+const SYMMETRY_TOLERANCE: f64 = 1e-12;
+
+// eigenvalues this close, next to the largest entry, are one repeated eigenvalue, rounding splits
+// a repeated eigenvalue by around the square root of the machine epsilon
+const REPEATED_TOLERANCE: f64 = 1e-6;
+
+// singular values this small next to the largest entry belong to the null space
+const NULL_TOLERANCE: f64 = 1e-8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Decomposition {
+    // p a = l u, with l unit lower triangular and p a permutation
+    Lu { p: Matrix, l: Matrix, u: Matrix },
+
+    // a = q r, with q orthogonal and r upper triangular
+    Qr { q: Matrix, r: Matrix },
+
+    // each vector is a column, only given when every eigenvalue is real and there are
+    // as many independent eigenvectors as rows, so never for a defective matrix like [[1, 1], [0, 1]]
+    Eigen { values: Vec<Complex>, vectors: Option<Matrix> },
+
+    // a = u diag(s) v^t, with the singular values from largest to smallest
+    Svd { u: Matrix, s: Vec<f64>, v: Matrix }
+}
+
+impl Decomposition {
+    // each factor by name, like l = [[1, 0], [3, 1]], leaving out eigenvectors that weren't found
+    pub fn format(&self, format: NumberFormat) -> String {
+        let matrix = |m: &Matrix| format_rows(m.rows(), format);
+
+        let factors: Vec<(&str, String)> = match self {
+            Decomposition::Lu { p, l, u } => vec![("p", matrix(p)), ("l", matrix(l)), ("u", matrix(u))],
+            Decomposition::Qr { q, r } => vec![("q", matrix(q)), ("r", matrix(r))],
+
+            Decomposition::Eigen { values, vectors } => {
+                let values: Vec<String> = values.iter()
+                    .map(|z| match z.im == 0.0 {
+                        true => format_number(z.re, format),
+                        false => format_complex(z.re, z.im, format)
+                    })
+                    .collect();
+
+                let mut factors = vec![("values", format!("[{}]", values.join(", ")))];
+
+                if let Some(vectors) = vectors {
+                    factors.push(("vectors", matrix(vectors)));
+                }
+
+                factors
+            },
+
+            Decomposition::Svd { u, s, v } => vec![("u", matrix(u)), ("s", format_list(s, format)), ("v", matrix(v))]
+        };
+
+        factors.iter()
+            .map(|(name, factor)| format!("{} = {}", name, factor))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    Matrix::identity(n).into_rows()
+}
+
+fn transpose(a: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..a[0].len()).map(|j| a.iter().map(|row| row[j]).collect()).collect()
+}
+
+fn product(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let columns = transpose(b);
+
+    a.iter()
+        .map(|row| columns.iter().map(|column| row.iter().zip(column).map(|(a, b)| a * b).sum()).collect())
+        .collect()
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|a| a * a).sum::<f64>().sqrt()
+}
+
+fn square_size(a: &Matrix) -> Result<usize, MatrixError> {
+    match a.shape() {
+        [rows, columns] if rows == columns => Ok(rows),
+        [rows, columns] => Err(MatrixError::NotSquare { rows, columns })
+    }
+}
+
+// the reflection (I - beta v v^t) that takes x onto its first axis, None when it is already there
+fn reflector(x: &[f64]) -> Option<(Vec<f64>, f64)> {
+    if x[1..].iter().all(|a| *a == 0.0) {
+        return None;
+    }
+
+    let length = norm(x);
+    let alpha = if x[0] > 0.0 { -length } else { length };
+
+    let mut v = x.to_vec();
+    v[0] -= alpha;
+
+    let beta = 2.0 / v.iter().map(|a| a * a).sum::<f64>();
+
+    Some((v, beta))
+}
+
+// reflects the rows from start on, as h a
+fn reflect_rows(a: &mut [Vec<f64>], start: usize, v: &[f64], beta: f64) {
+    for j in 0..a[0].len() {
+        let s = beta * a[start..].iter().zip(v).map(|(row, vi)| vi * row[j]).sum::<f64>();
+
+        for (row, vi) in a[start..].iter_mut().zip(v) {
+            row[j] -= s * vi;
+        }
+    }
+}
+
+// reflects the columns from start on, as a h
+fn reflect_columns(a: &mut [Vec<f64>], start: usize, v: &[f64], beta: f64) {
+    for row in a.iter_mut() {
+        let s = beta * row[start..].iter().zip(v).map(|(a, b)| a * b).sum::<f64>();
+
+        for (value, vi) in row[start..].iter_mut().zip(v) {
+            *value -= s * vi;
+        }
+    }
+}
+
+// q is m by m and r is m by n
+fn householder_qr(a: &[Vec<f64>]) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let (m, n) = (a.len(), a[0].len());
+
+    let mut q = identity(m);
+    let mut r = a.to_vec();
+
+    for k in 0..n.min(m.saturating_sub(1)) {
+        let x: Vec<f64> = r[k..].iter().map(|row| row[k]).collect();
+
+        let (v, beta) = match reflector(&x) {
+            Some(reflection) => reflection,
+            None => {
+                continue;
+            }
+        };
+
+        reflect_rows(&mut r, k, &v, beta);
+        reflect_columns(&mut q, k, &v, beta);
+
+        // zero up to rounding
+        for row in r[k + 1..].iter_mut() {
+            row[k] = 0.0;
+        }
+    }
+
+    (q, r)
+}
+
+// the same eigenvalues, with zeros below the first subdiagonal
+fn hessenberg(a: &mut [Vec<f64>]) {
+    for k in 0..a.len().saturating_sub(2) {
+        let x: Vec<f64> = a[k + 1..].iter().map(|row| row[k]).collect();
+
+        if let Some((v, beta)) = reflector(&x) {
+            reflect_rows(a, k + 1, &v, beta);
+            reflect_columns(a, k + 1, &v, beta);
+        }
+    }
+}
+
+pub fn lu(a: &Matrix) -> Result<Decomposition, MatrixError> {
+    let n = square_size(a)?;
+
+    let mut u = a.rows().to_vec();
+    let mut l = identity(n);
+    let mut p = identity(n);
+
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|i, j| u[*i][k].abs().total_cmp(&u[*j][k].abs()))
+            .unwrap();
+
+        // a column that is already zero needs no elimination, the matrix is singular
+        if u[pivot][k] == 0.0 {
+            continue;
+        }
+
+        u.swap(k, pivot);
+        p.swap(k, pivot);
+
+        // the multipliers found so far move with their rows
+        if pivot != k {
+            let (above, below) = l.split_at_mut(pivot);
+            above[k][..k].swap_with_slice(&mut below[0][..k]);
+        }
+
+        let pivot_row = u[k].clone();
+
+        for (i, row) in u.iter_mut().enumerate().skip(k + 1) {
+            let factor = row[k] / pivot_row[k];
+            l[i][k] = factor;
+
+            for (value, above) in row[k..].iter_mut().zip(&pivot_row[k..]) {
+                *value -= factor * above;
+            }
+        }
+    }
+
+    Ok(Decomposition::Lu { p: Matrix::new(p)?, l: Matrix::new(l)?, u: Matrix::new(u)? })
+}
+
+pub fn qr(a: &Matrix) -> Result<Decomposition, MatrixError> {
+    let (q, r) = householder_qr(a.rows());
+
+    Ok(Decomposition::Qr { q: Matrix::new(q)?, r: Matrix::new(r)? })
+}
+
+pub fn is_symmetric(a: &Matrix) -> bool {
+    let rows = a.rows();
+    let largest = rows.iter().flatten().fold(0.0_f64, |a, b| a.max(b.abs()));
+
+    square_size(a).is_ok() && rows.iter()
+        .enumerate()
+        .all(|(i, row)| row.iter().enumerate().all(|(j, v)| (v - rows[j][i]).abs() <= largest * SYMMETRY_TOLERANCE))
+}
+
+// the eigenvalue of the trailing 2 by 2 block [[a, b], [b, c]] closest to c, which converges fastest
+fn wilkinson_shift(a: f64, b: f64, c: f64) -> f64 {
+    let d = (a - c) / 2.0;
+
+    if b == 0.0 {
+        return c;
+    }
+
+    let sign = if d < 0.0 { -1.0 } else { 1.0 };
+
+    c - sign * b * b / (d.abs() + d.hypot(b))
+}
+
+// the shifted qr algorithm, the eigenvectors are the product of every q and come out orthonormal,
+// eigenvalues are from smallest to largest
+pub fn symmetric_eigen(a: &Matrix) -> Result<(Vec<f64>, Matrix), MatrixError> {
+    let n = square_size(a)?;
+
+    let mut a = a.rows().to_vec();
+    let mut vectors = identity(n);
+
+    let mut size = n;
+    let mut iterations = 0;
+
+    while size > 1 {
+        let last = size - 1;
+
+        let off_diagonal = a[last][..last].iter().fold(0.0_f64, |m, v| m.max(v.abs()));
+        let diagonal = a[last][last].abs() + a[last - 1][last - 1].abs();
+
+        // the last row has converged, so the rest is worked on alone
+        if off_diagonal <= f64::EPSILON * diagonal || off_diagonal == 0.0 {
+            size -= 1;
+            iterations = 0;
+            continue;
+        }
+
+        if iterations == MAX_ITERATIONS {
+            return Err(MatrixError::NoConvergence);
+        }
+
+        iterations += 1;
+
+        let shift = wilkinson_shift(a[last - 1][last - 1], a[last][last - 1], a[last][last]);
+
+        let block: Vec<Vec<f64>> = a[..size].iter()
+            .enumerate()
+            .map(|(i, row)| row[..size].iter().enumerate().map(|(j, v)| if i == j { v - shift } else { *v }).collect())
+            .collect();
+
+        let (q, r) = householder_qr(&block);
+
+        for (i, (row, next)) in a.iter_mut().zip(product(&r, &q)).enumerate() {
+            for (j, (value, next)) in row.iter_mut().zip(next).enumerate() {
+                *value = if i == j { next + shift } else { next };
+            }
+        }
+
+        for row in vectors.iter_mut() {
+            let rotated = product(&[row[..size].to_vec()], &q);
+            row[..size].copy_from_slice(&rotated[0]);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|i, j| a[*i][*i].total_cmp(&a[*j][*j]));
+
+    let values = order.iter().map(|i| a[*i][*i]).collect();
+    let vectors = vectors.iter().map(|row| order.iter().map(|i| row[*i]).collect()).collect();
+
+    Ok((values, Matrix::new(vectors)?))
+}
+
+// the francis double shift qr algorithm on the hessenberg form, which keeps to real arithmetic
+// by finding complex conjugate pairs two at a time, from numerical recipes' hqr
+pub fn eigenvalues(a: &Matrix) -> Result<Vec<Complex>, MatrixError> {
+    let n = square_size(a)?;
+
+    let mut a = a.rows().to_vec();
+    hessenberg(&mut a);
+
+    let mut values: Vec<Complex> = vec![Complex::from(0.0); n];
+
+    let norm: f64 = a.iter()
+        .enumerate()
+        .map(|(i, row)| row[i.saturating_sub(1)..].iter().map(|v| v.abs()).sum::<f64>())
+        .sum();
+
+    // the shifts taken so far
+    let mut t: f64 = 0.0;
+
+    // everything past end has been found
+    let mut end = n;
+    let mut iterations = 0;
+
+    while end > 0 {
+        let nn = end - 1;
+
+        // look for a small subdiagonal element to split at
+        let mut l = nn;
+
+        while l > 0 {
+            let s = a[l - 1][l - 1].abs() + a[l][l].abs();
+            let s = if s == 0.0 { norm } else { s };
+
+            if a[l][l - 1].abs() + s == s {
+                a[l][l - 1] = 0.0;
+                break;
+            }
+
+            l -= 1;
+        }
+
+        let mut x = a[nn][nn];
+
+        if l == nn {
+            values[nn] = Complex::from(x + t);
+
+            end -= 1;
+            iterations = 0;
+            continue;
+        }
+
+        let mut y = a[nn - 1][nn - 1];
+        let mut w = a[nn][nn - 1] * a[nn - 1][nn];
+
+        // a 2 by 2 block, with a pair of real or complex eigenvalues
+        if l == nn - 1 {
+            let p = 0.5 * (y - x);
+            let q = p * p + w;
+            let z = q.abs().sqrt();
+
+            x += t;
+
+            if q >= 0.0 {
+                let z = p + z.copysign(p);
+
+                values[nn - 1] = Complex::from(x + z);
+                values[nn] = Complex::from(if z != 0.0 { x - w / z } else { x + z });
+            } else {
+                values[nn - 1] = Complex::new(x + p, -z);
+                values[nn] = Complex::new(x + p, z);
+            }
+
+            end -= 2;
+            iterations = 0;
+            continue;
+        }
+
+        if iterations == MAX_ITERATIONS {
+            return Err(MatrixError::NoConvergence);
+        }
+
+        // an exceptional shift to get out of cycles
+        if iterations == 10 || iterations == 20 {
+            t += x;
+
+            for (i, row) in a.iter_mut().enumerate().take(nn + 1) {
+                row[i] -= x;
+            }
+
+            let s = a[nn][nn - 1].abs() + a[nn - 1][nn - 2].abs();
+
+            x = 0.75 * s;
+            y = x;
+            w = -0.4375 * s * s;
+        }
+
+        iterations += 1;
+
+        // form the shift and look for two consecutive small subdiagonal elements
+        let mut m = nn - 2;
+        let (mut p, mut q, mut r);
+
+        loop {
+            let z = a[m][m];
+            let r0 = x - z;
+            let s0 = y - z;
+
+            p = (r0 * s0 - w) / a[m + 1][m] + a[m][m + 1];
+            q = a[m + 1][m + 1] - z - r0 - s0;
+            r = a[m + 2][m + 1];
+
+            let s = p.abs() + q.abs() + r.abs();
+
+            p /= s;
+            q /= s;
+            r /= s;
+
+            if m == l {
+                break;
+            }
+
+            let u = a[m][m - 1].abs() * (q.abs() + r.abs());
+            let v = p.abs() * (a[m - 1][m - 1].abs() + z.abs() + a[m + 1][m + 1].abs());
+
+            if u + v == v {
+                break;
+            }
+
+            m -= 1;
+        }
+
+        for i in m + 2..=nn {
+            a[i][i - 2] = 0.0;
+
+            if i != m + 2 {
+                a[i][i - 3] = 0.0;
+            }
+        }
+
+        // the double qr step on rows l to nn and columns m to nn
+        for k in m..nn {
+            if k != m {
+                p = a[k][k - 1];
+                q = a[k + 1][k - 1];
+                r = if k != nn - 1 { a[k + 2][k - 1] } else { 0.0 };
+
+                x = p.abs() + q.abs() + r.abs();
+
+                if x != 0.0 {
+                    p /= x;
+                    q /= x;
+                    r /= x;
+                }
+            }
+
+            let s = (p * p + q * q + r * r).sqrt().copysign(p);
+
+            if s == 0.0 {
+                continue;
+            }
+
+            if k == m {
+                if l != m {
+                    a[k][k - 1] = -a[k][k - 1];
+                }
+            } else {
+                a[k][k - 1] = -s * x;
+            }
+
+            p += s;
+            x = p / s;
+            y = q / s;
+
+            let z = r / s;
+
+            q /= p;
+            r /= p;
+
+            let (above, below) = a.split_at_mut(k + 1);
+            let (first, (second, rest)) = (&mut above[k], below.split_first_mut().unwrap());
+            let mut third = if k != nn - 1 { rest.first_mut() } else { None };
+
+            for j in k..=nn {
+                let mut pp = first[j] + q * second[j];
+
+                if let Some(third) = third.as_mut() {
+                    pp += r * third[j];
+                    third[j] -= pp * z;
+                }
+
+                second[j] -= pp * y;
+                first[j] -= pp * x;
+            }
+
+            for row in a[l..=nn.min(k + 3)].iter_mut() {
+                let mut pp = x * row[k] + y * row[k + 1];
+
+                if k != nn - 1 {
+                    pp += z * row[k + 2];
+                    row[k + 2] -= pp * r;
+                }
+
+                row[k + 1] -= pp * q;
+                row[k] -= pp;
+            }
+        }
+    }
+
+    values.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+
+    Ok(values)
+}
+
+// a basis for the null space of a - lambda, the columns of v with a zero singular value,
+// each with its largest part made positive
+fn eigenspace(a: &Matrix, lambda: f64) -> Result<Vec<Vec<f64>>, MatrixError> {
+    let largest = a.rows().iter().flatten().fold(1.0_f64, |m, v| m.max(v.abs()));
+
+    let shifted = a.rows().iter()
+        .enumerate()
+        .map(|(i, row)| row.iter().enumerate().map(|(j, v)| if i == j { v - lambda } else { *v }).collect())
+        .collect();
+
+    let (s, v) = match svd(&Matrix::new(shifted)?)? {
+        Decomposition::Svd { s, v, .. } => (s, v),
+        _ => unreachable!()
+    };
+
+    let basis = transpose(v.rows()).into_iter()
+        .zip(s)
+        .filter(|(_, singular)| *singular <= largest * NULL_TOLERANCE)
+        .map(|(column, _)| {
+            let sign = column.iter().fold(0.0_f64, |m, a| if a.abs() > m.abs() { *a } else { m }).signum();
+            column.iter().map(|a| a * sign).collect()
+        })
+        .collect();
+
+    Ok(basis)
+}
+
+// one eigenspace for each distinct eigenvalue, None when one is smaller than the number of times
+// its eigenvalue is repeated, or the vectors together don't span every direction
+fn eigenvectors(a: &Matrix, values: &[Complex]) -> Result<Option<Matrix>, MatrixError> {
+    let largest = a.rows().iter().flatten().fold(1.0_f64, |m, v| m.max(v.abs()));
+
+    let mut columns: Vec<Vec<f64>> = Vec::new();
+    let mut start = 0;
+
+    // the values are sorted, so repeated ones are next to each other
+    while start < values.len() {
+        let end = (start..values.len())
+            .find(|i| values[*i].re - values[start].re > largest * REPEATED_TOLERANCE)
+            .unwrap_or(values.len());
+
+        let repeated = end - start;
+        let lambda = values[start..end].iter().map(|v| v.re).sum::<f64>() / repeated as f64;
+
+        let basis = eigenspace(a, lambda)?;
+
+        if basis.len() < repeated {
+            return Ok(None);
+        }
+
+        columns.extend(basis.into_iter().take(repeated));
+        start = end;
+    }
+
+    let vectors = Matrix::new(transpose(&columns))?;
+
+    Ok((vectors.rank() == values.len()).then_some(vectors))
+}
+
+pub fn eigen(a: &Matrix) -> Result<Decomposition, MatrixError> {
+    if is_symmetric(a) {
+        let (values, vectors) = symmetric_eigen(a)?;
+
+        return Ok(Decomposition::Eigen {
+            values: values.into_iter().map(Complex::from).collect(),
+            vectors: Some(vectors)
+        });
+    }
+
+    let values = eigenvalues(a)?;
+
+    let vectors = match values.iter().all(|v| v.is_real()) {
+        true => eigenvectors(a, &values)?,
+        false => None
+    };
+
+    Ok(Decomposition::Eigen { values, vectors })
+}
+
+// one sided jacobi, rotating pairs of columns until they are orthogonal, the column lengths are then
+// the singular values, a wide matrix is worked on through its transpose
+pub fn svd(a: &Matrix) -> Result<Decomposition, MatrixError> {
+    let [rows, columns] = a.shape();
+
+    if rows < columns {
+        return match svd(&a.transpose())? {
+            Decomposition::Svd { u, s, v } => Ok(Decomposition::Svd { u: v, s, v: u }),
+            _ => unreachable!()
+        };
+    }
+
+    // worked on column by column, so u holds the columns of a
+    let mut u = transpose(a.rows());
+    let mut v = identity(columns);
+
+    let mut converged = false;
+
+    for _ in 0..MAX_SWEEPS {
+        converged = true;
+
+        for i in 0..columns {
+            for j in i + 1..columns {
+                let alpha: f64 = u[i].iter().map(|a| a * a).sum();
+                let beta: f64 = u[j].iter().map(|a| a * a).sum();
+                let gamma: f64 = u[i].iter().zip(&u[j]).map(|(a, b)| a * b).sum();
+
+                if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                    continue;
+                }
+
+                converged = false;
+
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+
+                for vectors in [&mut u, &mut v] {
+                    let (first, second) = vectors.split_at_mut(j);
+
+                    for (a, b) in first[i].iter_mut().zip(second[0].iter_mut()) {
+                        (*a, *b) = (c * *a - s * *b, s * *a + c * *b);
+                    }
+                }
+            }
+        }
+
+        if converged {
+            break;
+        }
+    }
+
+    if !converged {
+        return Err(MatrixError::NoConvergence);
+    }
+
+    let mut order: Vec<usize> = (0..columns).collect();
+    let lengths: Vec<f64> = u.iter().map(|column| norm(column)).collect();
+    order.sort_by(|i, j| lengths[*j].total_cmp(&lengths[*i]));
+
+    // a zero singular value leaves its column of u as zeros
+    let u_columns: Vec<Vec<f64>> = order.iter()
+        .map(|i| u[*i].iter().map(|a| if lengths[*i] > 0.0 { a / lengths[*i] } else { 0.0 }).collect())
+        .collect();
+
+    let v_columns: Vec<Vec<f64>> = order.iter().map(|i| v[*i].clone()).collect();
+
+    Ok(Decomposition::Svd {
+        u: Matrix::new(transpose(&u_columns))?,
+        s: order.iter().map(|i| lengths[*i]).collect(),
+        v: Matrix::new(transpose(&v_columns))?
+    })
+}
+
+pub fn apply(name: &str, args: &[Value]) -> Result<Value, MatrixError> {
+    let decomposition = match (name, args) {
+        ("lu", [Value::Matrix(m)]) => lu(m)?,
+        ("qr", [Value::Matrix(m)]) => qr(m)?,
+        ("eig", [Value::Matrix(m)]) => eigen(m)?,
+        ("svd", [Value::Matrix(m)]) => svd(m)?,
+
+        _ => {
+            return Err(MatrixError::Arguments);
+        }
+    };
+
+    Ok(Value::Decomposition(decomposition))
+}
@@ -0,0 +1,313 @@
+use serde::{Serialize, Deserialize};
+
+use crate::regression::solve_linear;
+use crate::value::Value;
+
+pub const MATRIX_FUNCTIONS: [&str; 7] = ["transpose", "det", "inv", "rank", "dot", "cross", "solve"];
+
+// entries this small next to the largest are taken as zero, so [[1, 2], [2, 4]] is singular despite rounding
+const SINGULAR_TOLERANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MatrixError {
+    // rows of different lengths, like [[1, 2], [3]]
+    Ragged,
+
+    // shapes that don't fit together, as rows by columns with vectors as columns
+    Mismatch { left: [usize; 2], right: [usize; 2] },
+
+    NotSquare { rows: usize, columns: usize },
+    Singular,
+
+    // the cross product is only of vectors of length 3
+    WrongLength { expected: usize, found: usize },
+
+    // a function given numbers or lists where it needs something else
+    Arguments,
+
+    // an iterative method, like the qr algorithm for eigenvalues, that didn't settle
+    NoConvergence
+}
+
+impl std::fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixError::Ragged => write!(f, "rows have different lengths"),
+            MatrixError::Mismatch { left, right } => write!(f, "can't combine {}x{} with {}x{}", left[0], left[1], right[0], right[1]),
+            MatrixError::NotSquare { rows, columns } => write!(f, "a {}x{} matrix isn't square", rows, columns),
+            MatrixError::Singular => write!(f, "matrix is singular"),
+            MatrixError::WrongLength { expected, found } => write!(f, "expected a vector of length {}, found {}", expected, found),
+            MatrixError::Arguments => write!(f, "expected a matrix or vector"),
+            MatrixError::NoConvergence => write!(f, "failed to converge")
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+// a matrix stored row by row, never empty and always rectangular
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Matrix {
+    rows: Vec<Vec<f64>>
+}
+
+impl Matrix {
+    pub fn new(rows: Vec<Vec<f64>>) -> Result<Matrix, MatrixError> {
+        let columns = rows.first().map_or(0, |row| row.len());
+
+        if columns == 0 || rows.iter().any(|row| row.len() != columns) {
+            return Err(MatrixError::Ragged);
+        }
+
+        Ok(Matrix { rows })
+    }
+
+    pub fn identity(n: usize) -> Matrix {
+        let rows = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect();
+
+        Matrix { rows }
+    }
+
+    pub fn rows(&self) -> &[Vec<f64>] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<f64>> {
+        self.rows
+    }
+
+    // rows by columns
+    pub fn shape(&self) -> [usize; 2] {
+        [self.rows.len(), self.rows[0].len()]
+    }
+
+    fn square_size(&self) -> Result<usize, MatrixError> {
+        match self.shape() {
+            [rows, columns] if rows == columns => Ok(rows),
+            [rows, columns] => Err(MatrixError::NotSquare { rows, columns })
+        }
+    }
+
+    // None if f fails for any element
+    pub fn map<F>(&self, f: F) -> Option<Matrix>
+        where F: Fn(f64) -> Option<f64>
+    {
+        let rows = self.rows.iter()
+            .map(|row| row.iter().map(|v| f(*v)).collect::<Option<Vec<f64>>>())
+            .collect::<Option<Vec<Vec<f64>>>>()?;
+
+        Some(Matrix { rows })
+    }
+
+    // element by element, for matrices of the same shape
+    pub fn zip_with<F>(&self, other: &Matrix, f: F) -> Result<Matrix, MatrixError>
+        where F: Fn(f64, f64) -> f64
+    {
+        if self.shape() != other.shape() {
+            return Err(MatrixError::Mismatch { left: self.shape(), right: other.shape() });
+        }
+
+        let rows = self.rows.iter()
+            .zip(&other.rows)
+            .map(|(a, b)| a.iter().zip(b).map(|(a, b)| f(*a, *b)).collect())
+            .collect();
+
+        Ok(Matrix { rows })
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let [rows, columns] = self.shape();
+
+        Matrix {
+            rows: (0..columns).map(|j| (0..rows).map(|i| self.rows[i][j]).collect()).collect()
+        }
+    }
+
+    pub fn product(&self, other: &Matrix) -> Result<Matrix, MatrixError> {
+        if self.shape()[1] != other.shape()[0] {
+            return Err(MatrixError::Mismatch { left: self.shape(), right: other.shape() });
+        }
+
+        let columns = other.transpose();
+
+        let rows = self.rows.iter()
+            .map(|row| columns.rows.iter().map(|column| dot_unchecked(row, column)).collect())
+            .collect();
+
+        Ok(Matrix { rows })
+    }
+
+    // the matrix times a column vector
+    pub fn apply(&self, vector: &[f64]) -> Result<Vec<f64>, MatrixError> {
+        if self.shape()[1] != vector.len() {
+            return Err(MatrixError::Mismatch { left: self.shape(), right: [vector.len(), 1] });
+        }
+
+        Ok(self.rows.iter().map(|row| dot_unchecked(row, vector)).collect())
+    }
+
+    // gaussian elimination with partial pivoting, giving the row echelon form,
+    // the columns with pivots and whether an odd number of rows were swapped
+    fn row_echelon(&self) -> (Vec<Vec<f64>>, Vec<usize>, bool) {
+        let mut rows = self.rows.clone();
+        let [height, width] = self.shape();
+
+        let largest = rows.iter().flatten().fold(0.0_f64, |a, b| a.max(b.abs()));
+        let tolerance = largest * SINGULAR_TOLERANCE;
+
+        let mut pivots: Vec<usize> = Vec::new();
+        let mut odd_swaps: bool = false;
+
+        for column in 0..width {
+            let top = pivots.len();
+
+            if top == height {
+                break;
+            }
+
+            let pivot = (top..height)
+                .max_by(|i, j| rows[*i][column].abs().total_cmp(&rows[*j][column].abs()))
+                .unwrap();
+
+            if rows[pivot][column].abs() <= tolerance {
+                continue;
+            }
+
+            if pivot != top {
+                rows.swap(top, pivot);
+                odd_swaps = !odd_swaps;
+            }
+
+            let pivot_row = rows[top].clone();
+
+            for row in rows.iter_mut().skip(top + 1) {
+                let factor = row[column] / pivot_row[column];
+
+                for (value, above) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                    *value -= factor * above;
+                }
+            }
+
+            pivots.push(column);
+        }
+
+        (rows, pivots, odd_swaps)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.row_echelon().1.len()
+    }
+
+    pub fn determinant(&self) -> Result<f64, MatrixError> {
+        let n = self.square_size()?;
+        let (rows, pivots, odd_swaps) = self.row_echelon();
+
+        if pivots.len() < n {
+            return Ok(0.0);
+        }
+
+        let product: f64 = (0..n).map(|i| rows[i][i]).product();
+
+        Ok(if odd_swaps { -product } else { product })
+    }
+
+    // x where self x = b, for a square matrix that isn't singular
+    pub fn solve(&self, b: &[f64]) -> Result<Vec<f64>, MatrixError> {
+        let n = self.square_size()?;
+
+        if b.len() != n {
+            return Err(MatrixError::Mismatch { left: self.shape(), right: [b.len(), 1] });
+        }
+
+        if self.rank() < n {
+            return Err(MatrixError::Singular);
+        }
+
+        solve_linear(self.rows.clone(), b.to_vec()).ok_or(MatrixError::Singular)
+    }
+
+    // solved one column of the identity at a time
+    pub fn inverse(&self) -> Result<Matrix, MatrixError> {
+        let n = self.square_size()?;
+
+        let columns = Matrix::identity(n).rows.iter()
+            .map(|e| self.solve(e))
+            .collect::<Result<Vec<Vec<f64>>, MatrixError>>()?;
+
+        Ok(Matrix { rows: columns }.transpose())
+    }
+
+    // whole powers only, negative ones through the inverse, by repeated squaring
+    // so large powers take a few dozen products
+    pub fn power(&self, exponent: i32) -> Result<Matrix, MatrixError> {
+        let n = self.square_size()?;
+
+        let mut base = match exponent < 0 {
+            true => self.inverse()?,
+            false => self.clone()
+        };
+
+        let mut result = Matrix::identity(n);
+        let mut remaining = exponent.unsigned_abs();
+
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.product(&base)?;
+            }
+
+            remaining /= 2;
+
+            if remaining > 0 {
+                base = base.product(&base)?;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+fn dot_unchecked(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub fn dot(a: &[f64], b: &[f64]) -> Result<f64, MatrixError> {
+    if a.len() != b.len() {
+        return Err(MatrixError::Mismatch { left: [a.len(), 1], right: [b.len(), 1] });
+    }
+
+    Ok(dot_unchecked(a, b))
+}
+
+pub fn cross(a: &[f64], b: &[f64]) -> Result<Vec<f64>, MatrixError> {
+    match (a, b) {
+        ([a1, a2, a3], [b1, b2, b3]) => Ok(vec![
+            a2 * b3 - a3 * b2,
+            a3 * b1 - a1 * b3,
+            a1 * b2 - a2 * b1
+        ]),
+
+        ([_, _, _], _) => Err(MatrixError::WrongLength { expected: 3, found: b.len() }),
+        _ => Err(MatrixError::WrongLength { expected: 3, found: a.len() })
+    }
+}
+
+// lists are vectors, and a vector on its own is transposed into a row
+pub fn apply(name: &str, args: &[Value]) -> Result<Value, MatrixError> {
+    Ok(match (name, args) {
+        ("transpose", [Value::Matrix(m)]) => Value::Matrix(m.transpose()),
+        ("transpose", [Value::List(v)]) => Value::Matrix(Matrix::new(vec![v.clone()])?),
+        ("det", [Value::Matrix(m)]) => Value::Number(m.determinant()?),
+        ("inv", [Value::Matrix(m)]) => Value::Matrix(m.inverse()?),
+        ("rank", [Value::Matrix(m)]) => Value::Number(m.rank() as f64),
+        ("dot", [Value::List(a), Value::List(b)]) => Value::Number(dot(a, b)?),
+        ("cross", [Value::List(a), Value::List(b)]) => Value::List(cross(a, b)?),
+        ("solve", [Value::Matrix(m), Value::List(b)]) => Value::List(m.solve(b)?),
+
+        _ => {
+            return Err(MatrixError::Arguments);
+        }
+    })
+}
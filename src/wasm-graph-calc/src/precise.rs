@@ -0,0 +1,487 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use num_bigint::BigInt;
+use num_traits::{Zero, One, Signed};
+
+// extra digits carried through the series so the final rounding is right
+const GUARD_DIGITS: usize = 10;
+
+// e^x gets too big to hold past this, f64 gives up far sooner
+const MAX_EXP_ARGUMENT: f64 = 1e5;
+
+// whole powers above this go through exp and ln instead of repeated multiplication
+const MAX_WHOLE_POWER: u64 = 1 << 20;
+
+fn ten_pow(n: usize) -> BigInt {
+    BigInt::from(10u32).pow(n as u32)
+}
+
+fn digit_count(m: &BigInt) -> usize {
+    m.magnitude().to_str_radix(10).len()
+}
+
+// divides and rounds to the nearest, for fixed point values that already carry guard digits
+fn div_nearest(a: &BigInt, b: &BigInt) -> BigInt {
+    let (q, r) = (a / b, a % b);
+
+    match (r.abs() * 2u32).cmp(&b.abs()) {
+        Ordering::Less => q,
+        _ if a.is_negative() != b.is_negative() => q - 1u32,
+        _ => q + 1u32
+    }
+}
+
+// a decimal floating point number, mantissa * 10^exponent, with any number of digits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigFloat {
+    mantissa: BigInt,
+    exponent: i64
+}
+
+impl BigFloat {
+    // trailing zeros are moved into the exponent so equal values compare equal
+    fn new(mut mantissa: BigInt, mut exponent: i64) -> BigFloat {
+        if mantissa.is_zero() {
+            return BigFloat { mantissa, exponent: 0 };
+        }
+
+        let ten = BigInt::from(10u32);
+
+        while (&mantissa % &ten).is_zero() {
+            mantissa /= &ten;
+            exponent += 1;
+        }
+
+        BigFloat { mantissa, exponent }
+    }
+
+    // rounded to the given number of significant digits, halves go to even unless
+    // sticky says something non zero was already dropped from below the mantissa
+    fn rounded(mantissa: BigInt, exponent: i64, digits: usize, sticky: bool) -> BigFloat {
+        let n = digit_count(&mantissa);
+
+        if n <= digits {
+            return BigFloat::new(mantissa, exponent);
+        }
+
+        let divisor = ten_pow(n - digits);
+        let magnitude = mantissa.abs();
+
+        let quotient = &magnitude / &divisor;
+        let remainder = &magnitude % &divisor;
+
+        let round_up = match (remainder * 2u32).cmp(&divisor) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => sticky || !(&quotient % 2u32).is_zero()
+        };
+
+        let quotient = if round_up { quotient + 1u32 } else { quotient };
+        let quotient = if mantissa.is_negative() { -quotient } else { quotient };
+
+        BigFloat::new(quotient, exponent + (n - digits) as i64)
+    }
+
+    pub fn zero() -> BigFloat {
+        BigFloat::new(BigInt::zero(), 0)
+    }
+
+    pub fn from_integer(n: i128) -> BigFloat {
+        BigFloat::new(BigInt::from(n), 0)
+    }
+
+    // a decimal like -1.25e-3, exactly
+    pub fn parse(input: &str) -> Option<BigFloat> {
+        let (number, exponent) = match input.split_once(['e', 'E']) {
+            Some((number, exponent)) => (number, exponent.parse::<i64>().ok()?),
+            None => (input, 0)
+        };
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let digits = format!("{}{}", whole, fraction);
+
+        let mantissa: BigInt = digits.parse().ok()?;
+
+        Some(BigFloat::new(mantissa, exponent - fraction.len() as i64))
+    }
+
+    // the shortest decimal that gives back the same f64, so 0.1 is exactly 0.1
+    pub fn from_f64(value: f64) -> Option<BigFloat> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        BigFloat::parse(&format!("{:e}", value))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        format!("{}e{}", self.mantissa, self.exponent).parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    fn is_integer(&self) -> bool {
+        self.exponent >= 0
+    }
+
+    pub fn neg(&self) -> BigFloat {
+        BigFloat::new(-&self.mantissa, self.exponent)
+    }
+
+    pub fn abs(&self) -> BigFloat {
+        BigFloat::new(self.mantissa.abs(), self.exponent)
+    }
+
+    // the value times 10^scale, rounded to an integer
+    fn to_fixed(&self, scale: usize) -> BigInt {
+        let shift = self.exponent + scale as i64;
+
+        match shift >= 0 {
+            true => &self.mantissa * ten_pow(shift as usize),
+            false => div_nearest(&self.mantissa, &ten_pow(-shift as usize))
+        }
+    }
+
+    fn from_fixed(fixed: BigInt, scale: usize, digits: usize) -> BigFloat {
+        BigFloat::rounded(fixed, -(scale as i64), digits, false)
+    }
+
+    // the basic operations are worked out exactly and then rounded once
+
+    pub fn add(&self, other: &BigFloat, digits: usize) -> BigFloat {
+        let exponent = self.exponent.min(other.exponent);
+
+        let a = &self.mantissa * ten_pow((self.exponent - exponent) as usize);
+        let b = &other.mantissa * ten_pow((other.exponent - exponent) as usize);
+
+        BigFloat::rounded(a + b, exponent, digits, false)
+    }
+
+    pub fn sub(&self, other: &BigFloat, digits: usize) -> BigFloat {
+        self.add(&other.neg(), digits)
+    }
+
+    pub fn mul(&self, other: &BigFloat, digits: usize) -> BigFloat {
+        BigFloat::rounded(&self.mantissa * &other.mantissa, self.exponent + other.exponent, digits, false)
+    }
+
+    pub fn div(&self, other: &BigFloat, digits: usize) -> Option<BigFloat> {
+        if other.is_zero() {
+            return None;
+        }
+
+        // enough digits in the quotient that the remainder only decides ties
+        let shift = (digits + 2 + digit_count(&other.mantissa)).saturating_sub(digit_count(&self.mantissa));
+        let numerator = &self.mantissa * ten_pow(shift);
+
+        let quotient = &numerator / &other.mantissa;
+        let remainder = &numerator % &other.mantissa;
+
+        Some(BigFloat::rounded(quotient, self.exponent - other.exponent - shift as i64, digits, !remainder.is_zero()))
+    }
+
+    pub fn sqrt(&self, digits: usize) -> Option<BigFloat> {
+        if self.is_negative() {
+            return None;
+        }
+
+        if self.is_zero() {
+            return Some(BigFloat::zero());
+        }
+
+        // an even exponent, with twice the digits wanted under the root
+        let mut shift = (2 * (digits + 2)).saturating_sub(digit_count(&self.mantissa));
+        if (self.exponent - shift as i64) % 2 != 0 {
+            shift += 1;
+        }
+
+        let scaled = &self.mantissa * ten_pow(shift);
+        let root = scaled.sqrt();
+        let exact = &root * &root == scaled;
+
+        Some(BigFloat::rounded(root, (self.exponent - shift as i64) / 2, digits, !exact))
+    }
+
+    // digits lost to cancellation when the result is much smaller than one
+    fn small_result_digits(estimate: f64, digits: usize) -> usize {
+        match estimate.abs() {
+            e if e == 0.0 || !e.is_finite() || e >= 1.0 => 0,
+            e => (-e.log10().floor() as usize).min(2 * digits)
+        }
+    }
+
+    pub fn exp(&self, digits: usize) -> Option<BigFloat> {
+        if self.to_f64().abs() > MAX_EXP_ARGUMENT {
+            return None;
+        }
+
+        // e^-x = 1 / e^x keeps the relative error small
+        if self.is_negative() {
+            return BigFloat::from_integer(1).div(&self.neg().exp(digits + GUARD_DIGITS)?, digits);
+        }
+
+        let scale = digits + GUARD_DIGITS;
+
+        Some(BigFloat::from_fixed(exp_fixed(&self.to_fixed(scale), scale), scale, digits))
+    }
+
+    pub fn ln(&self, digits: usize) -> Option<BigFloat> {
+        if self.is_negative() || self.is_zero() {
+            return None;
+        }
+
+        // x = a * 10^n with a in [1, 10), ln x = ln a + n ln 10
+        let n = self.exponent + digit_count(&self.mantissa) as i64 - 1;
+        let a = BigFloat::new(self.mantissa.clone(), self.exponent - n);
+
+        let estimate = a.to_f64().ln() + n as f64 * 10f64.ln();
+        let scale = digits + GUARD_DIGITS + n.unsigned_abs().to_string().len() + BigFloat::small_result_digits(estimate, digits);
+
+        let mut result = ln_fixed(&a.to_fixed(scale), scale);
+        if n != 0 {
+            result += ln_fixed(&(BigInt::from(10u32) * ten_pow(scale)), scale) * n;
+        }
+
+        Some(BigFloat::from_fixed(result, scale, digits))
+    }
+
+    // sin and cos of the argument brought into (-pi, pi]
+    fn sin_cos(&self, digits: usize, estimate: f64) -> Option<(BigInt, BigInt, usize)> {
+        let whole_digits = (self.exponent + digit_count(&self.mantissa) as i64).max(0) as usize;
+
+        if whole_digits > 4 * digits + 100 {
+            return None;
+        }
+
+        let scale = digits + GUARD_DIGITS + whole_digits + BigFloat::small_result_digits(estimate, digits);
+        let one = ten_pow(scale);
+
+        let pi = pi_fixed(scale);
+        let two_pi = &pi * 2u32;
+
+        let mut r = self.to_fixed(scale) % &two_pi;
+        if r > pi {
+            r -= &two_pi;
+        } else if r <= -&pi {
+            r += &two_pi;
+        }
+
+        let r_squared = &r * &r / &one;
+
+        let mut sin = r.clone();
+        let mut cos = one.clone();
+
+        let mut sin_term = r;
+        let mut cos_term = one;
+        let mut n: u64 = 1;
+
+        while !sin_term.is_zero() || !cos_term.is_zero() {
+            cos_term = -(&cos_term * &r_squared) / &ten_pow(scale) / ((2 * n - 1) * (2 * n));
+            sin_term = -(&sin_term * &r_squared) / &ten_pow(scale) / ((2 * n) * (2 * n + 1));
+
+            sin += &sin_term;
+            cos += &cos_term;
+            n += 1;
+        }
+
+        Some((sin, cos, scale))
+    }
+
+    pub fn sin(&self, digits: usize) -> Option<BigFloat> {
+        let (sin, _, scale) = self.sin_cos(digits, self.to_f64().sin())?;
+
+        Some(BigFloat::from_fixed(sin, scale, digits))
+    }
+
+    pub fn cos(&self, digits: usize) -> Option<BigFloat> {
+        let (_, cos, scale) = self.sin_cos(digits, self.to_f64().cos())?;
+
+        Some(BigFloat::from_fixed(cos, scale, digits))
+    }
+
+    pub fn tan(&self, digits: usize) -> Option<BigFloat> {
+        let estimate = self.to_f64().sin().abs().min(self.to_f64().cos().abs());
+        let (sin, cos, scale) = self.sin_cos(digits, estimate)?;
+
+        let sin = BigFloat::from_fixed(sin, scale, digits + GUARD_DIGITS);
+        let cos = BigFloat::from_fixed(cos, scale, digits + GUARD_DIGITS);
+
+        sin.div(&cos, digits)
+    }
+
+    pub fn pow(&self, power: &BigFloat, digits: usize) -> Option<BigFloat> {
+        let whole_power = power.is_integer()
+            .then(|| power.mantissa.abs() * ten_pow(power.exponent as usize))
+            .and_then(|p| u64::try_from(p).ok())
+            .filter(|p| *p <= MAX_WHOLE_POWER);
+
+        if let Some(mut n) = whole_power {
+            let working = digits + GUARD_DIGITS + n.to_string().len();
+
+            let mut result = BigFloat::from_integer(1);
+            let mut base = self.clone();
+
+            while n > 0 {
+                if n & 1 == 1 {
+                    result = result.mul(&base, working);
+                }
+
+                base = base.mul(&base, working);
+                n >>= 1;
+            }
+
+            return match power.is_negative() {
+                true => BigFloat::from_integer(1).div(&result, digits),
+                false => Some(BigFloat::rounded(result.mantissa, result.exponent, digits, false))
+            };
+        }
+
+        if self.is_zero() {
+            return (!power.is_negative() && !power.is_zero()).then(BigFloat::zero);
+        }
+
+        // x^y = e^(y ln x), with enough digits in the exponent for its whole part
+        let estimate = power.to_f64() * self.to_f64().ln();
+        let working = digits + GUARD_DIGITS + (estimate.abs().max(1.0).log10() as usize);
+
+        power.mul(&self.ln(working)?, working).exp(digits)
+    }
+
+    pub fn log(&self, base: &BigFloat, digits: usize) -> Option<BigFloat> {
+        let working = digits + GUARD_DIGITS;
+
+        self.ln(working)?.div(&base.ln(working)?, digits)
+    }
+
+    pub fn pi(digits: usize) -> BigFloat {
+        let scale = digits + GUARD_DIGITS;
+
+        BigFloat::from_fixed(pi_fixed(scale), scale, digits)
+    }
+}
+
+// plain notation for reasonable sizes, otherwise scientific like 1.5e-30
+impl std::fmt::Display for BigFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let digits = self.mantissa.magnitude().to_str_radix(10);
+
+        let n = digits.len() as i64;
+        let adjusted = self.exponent + n - 1;
+
+        if !(-7..21).contains(&adjusted) {
+            let fraction = match n > 1 {
+                true => format!(".{}", &digits[1..]),
+                false => String::new()
+            };
+
+            return write!(f, "{}{}{}e{}", sign, &digits[..1], fraction, adjusted);
+        }
+
+        if self.exponent >= 0 {
+            return write!(f, "{}{}{}", sign, digits, "0".repeat(self.exponent as usize));
+        }
+
+        let point = n + self.exponent;
+
+        match point > 0 {
+            true => write!(f, "{}{}.{}", sign, &digits[..point as usize], &digits[point as usize..]),
+            false => write!(f, "{}0.{}{}", sign, "0".repeat(-point as usize), digits)
+        }
+    }
+}
+
+// e^x for a fixed point x >= 0, by halving x until the series is quick and squaring back
+fn exp_fixed(x: &BigInt, scale: usize) -> BigInt {
+    let one = ten_pow(scale);
+
+    let mut halvings: u32 = 0;
+    while (x >> halvings) * 100u32 > one {
+        halvings += 1;
+    }
+
+    // every squaring doubles the error, so more digits are carried
+    let extra = halvings as usize / 3 + 2;
+    let inner_one = ten_pow(scale + extra);
+    let r = (x * ten_pow(extra)) >> halvings;
+
+    let mut sum = inner_one.clone();
+    let mut term = inner_one.clone();
+    let mut n: u32 = 1;
+
+    while !term.is_zero() {
+        term = &term * &r / &inner_one / n;
+        sum += &term;
+        n += 1;
+    }
+
+    for _ in 0..halvings {
+        sum = &sum * &sum / &inner_one;
+    }
+
+    div_nearest(&sum, &ten_pow(extra))
+}
+
+// ln a for a fixed point a in [1, 10], by newton's method on e^y = a from the f64 guess
+fn ln_fixed(a: &BigInt, scale: usize) -> BigInt {
+    let one = ten_pow(scale);
+
+    let guess = (a * 1_000_000u32 / &one).to_string().parse::<f64>().unwrap_or(1e6) / 1e6;
+    let mut y = BigFloat::from_f64(guess.ln())
+        .map(|g| g.to_fixed(scale))
+        .unwrap_or_else(BigInt::zero);
+
+    // each step triples the correct digits
+    for _ in 0..64 {
+        let e = exp_fixed(&y, scale);
+        let step = div_nearest(&((a - &e) * 2u32 * &one), &(a + &e));
+
+        y += &step;
+
+        if step.abs() <= BigInt::one() {
+            break;
+        }
+    }
+
+    y
+}
+
+// machin's formula, pi = 16 atan(1/5) - 4 atan(1/239)
+fn pi_fixed(scale: usize) -> BigInt {
+    let extra = 5;
+    let one = ten_pow(scale + extra);
+
+    let atan_inverse = |k: u32| -> BigInt {
+        let k_squared = BigInt::from(k) * k;
+
+        let mut power = &one / k;
+        let mut sum = power.clone();
+        let mut n: u32 = 1;
+
+        while !power.is_zero() {
+            power /= &k_squared;
+
+            let term = &power / (2 * n + 1);
+            if n % 2 == 1 {
+                sum -= term;
+            } else {
+                sum += term;
+            }
+
+            n += 1;
+        }
+
+        sum
+    };
+
+    let pi = atan_inverse(5) * 16u32 - atan_inverse(239) * 4u32;
+
+    div_nearest(&pi, &ten_pow(extra))
+}
@@ -0,0 +1,108 @@
+use serde::{Serialize, Deserialize};
+
+// an exact fraction in lowest terms with a positive denominator,
+// every operation gives None when it would overflow so the caller can fall back to f64
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128
+}
+
+// None when i128::MIN is involved, since its size doesn't fit in an i128
+fn gcd(mut a: i128, mut b: i128) -> Option<i128> {
+    while b != 0 {
+        (a, b) = (b, a.checked_rem(b)?);
+    }
+
+    a.checked_abs()
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numerator, denominator)?;
+        let sign = denominator.signum();
+
+        Some(Rational {
+            numerator: numerator.checked_div(divisor)?.checked_mul(sign)?,
+            denominator: denominator.checked_div(divisor)?.checked_mul(sign)?
+        })
+    }
+
+    // only whole numbers are exact, 0.1 has already been rounded by the time it is an f64
+    pub fn from_integer(value: f64) -> Option<Rational> {
+        if value.fract() != 0.0 || value.abs() >= 2f64.powi(100) {
+            return None;
+        }
+
+        Rational::new(value as i128, 1)
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    pub fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.numerator.checked_mul(other.denominator)?.checked_add(other.numerator.checked_mul(self.denominator)?)?,
+            self.denominator.checked_mul(other.denominator)?
+        )
+    }
+
+    pub fn checked_sub(&self, other: &Rational) -> Option<Rational> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.numerator.checked_mul(other.numerator)?,
+            self.denominator.checked_mul(other.denominator)?
+        )
+    }
+
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.numerator.checked_mul(other.denominator)?,
+            self.denominator.checked_mul(other.numerator)?
+        )
+    }
+
+    pub fn checked_neg(&self) -> Option<Rational> {
+        Rational::new(self.numerator.checked_neg()?, self.denominator)
+    }
+
+    // 3/2
+    pub fn fraction(&self) -> String {
+        match self.is_integer() {
+            true => self.numerator.to_string(),
+            false => format!("{}/{}", self.numerator, self.denominator)
+        }
+    }
+
+    // 1 1/2, or None when there is no whole part
+    pub fn mixed(&self) -> Option<String> {
+        let whole = self.numerator / self.denominator;
+        let remainder = (self.numerator % self.denominator).abs();
+
+        if whole == 0 || remainder == 0 {
+            return None;
+        }
+
+        Some(format!("{} {}/{}", whole, remainder, self.denominator))
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::hash_map::HashMap;
+use serde::{Serialize, Deserialize};
+
+use crate::{lex, AngleMode, ParseTree, Comparison, LexerTokenType};
+use crate::curves::split_top_level;
+use crate::contour::trace_contour;
+
+// whether the input compares things outside of any braces, like y > x^2
+pub fn is_inequality(input: &str) -> bool {
+    let mut brace_depth: u32 = 0;
+
+    for c in input.chars() {
+        match c {
+            '{' => brace_depth += 1,
+            '}' => brace_depth = brace_depth.saturating_sub(1),
+            '<' | '>' if brace_depth == 0 => {
+                return true;
+            },
+
+            _ => {}
+        }
+    }
+
+    false
+}
+
+fn with_y(vars: &HashMap<char, f64>, y: f64) -> HashMap<char, f64> {
+    let mut inner_vars = vars.clone();
+    inner_vars.insert('y', y);
+
+    inner_vars
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionBoundary {
+    pub segments: Vec<Vec<[f64; 2]>>,
+
+    // strict boundaries are not part of the region, and are usually drawn dashed
+    pub strict: bool
+}
+
+// the area where every one of a list of inequalities holds
+#[derive(Debug, Clone)]
+pub struct Region {
+    inequalities: Vec<ParseTree>
+}
+
+impl Region {
+    // a comma separated list of inequalities, such as x >= 0, y >= 0, x + y <= 10,
+    // None when they use a name other than y that isn't a variable, rather than shading nothing
+    pub fn new(input: &str, graphs: &HashMap<char, ParseTree>, vars: &HashMap<char, f64>) -> Option<Region> {
+        let mut inequalities: Vec<ParseTree> = Vec::new();
+        let mut mentions_xy = false;
+
+        for part in split_top_level(input, ',') {
+            let tokens = lex(&part).ok()?;
+
+            mentions_xy |= tokens.iter()
+                .any(|t| matches!(t.token_type, LexerTokenType::X | LexerTokenType::Var('y')));
+
+            let tree = ParseTree::new(&tokens, graphs).ok()?;
+
+            if tree.comparisons().is_empty() {
+                return None;
+            }
+
+            if !tree.names().iter().all(|name| *name == 'y' || vars.contains_key(name)) {
+                return None;
+            }
+
+            inequalities.push(tree);
+        }
+
+        if !mentions_xy {
+            return None;
+        }
+
+        Some(Region { inequalities })
+    }
+
+    pub fn contains(&self, x: f64, y: f64, vars: &HashMap<char, f64>, angle: AngleMode) -> bool {
+        let inner_vars = with_y(vars, y);
+
+        self.inequalities.iter()
+            .all(|tree| matches!(tree.evaluate(Some(x), &inner_vars, angle), Ok(v) if v != 0.0))
+    }
+
+    // whether the centre of each cell is inside, row by row from the bottom left
+    pub fn raster(&self, vars: &HashMap<char, f64>, angle: AngleMode, viewport: [f64; 4], columns: usize, rows: usize) -> Vec<bool> {
+        let [min_x, max_x, min_y, max_y] = viewport;
+
+        let cell_width = (max_x - min_x) / columns as f64;
+        let cell_height = (max_y - min_y) / rows as f64;
+
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let x = min_x + (column as f64 + 0.5) * cell_width;
+                let y = min_y + (row as f64 + 0.5) * cell_height;
+
+                self.contains(x, y, vars, angle)
+            })
+            .collect()
+    }
+
+    // the edges of the region, which are the parts of each comparison's curve
+    // where every other comparison still holds
+    pub fn boundaries(
+        &self,
+        vars: &HashMap<char, f64>,
+        angle: AngleMode,
+        viewport: [f64; 4],
+        cells: usize,
+        refinement: u32
+    ) -> Vec<RegionBoundary> {
+        let [min_x, max_x, min_y, max_y] = viewport;
+
+        let comparisons: Vec<Comparison> = self.inequalities.iter()
+            .flat_map(|tree| tree.comparisons())
+            .collect();
+
+        // allow for points that sit on a corner shared with another boundary
+        let tolerance = 1e-9 * (max_x - min_x).abs().max((max_y - min_y).abs());
+
+        let others_hold = |index: usize, point: [f64; 2]| {
+            let inner_vars = with_y(vars, point[1]);
+
+            comparisons.iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .all(|(_, c)| matches!(c.difference(Some(point[0]), &inner_vars, angle), Ok(d) if d <= tolerance))
+        };
+
+        comparisons.iter()
+            .enumerate()
+            .map(|(index, comparison)| {
+                let curve = trace_contour(
+                    |x: f64, y: f64| comparison.difference(Some(x), &with_y(vars, y), angle).unwrap_or(f64::NAN),
+                    min_x,
+                    max_x,
+                    min_y,
+                    max_y,
+                    cells,
+                    refinement
+                );
+
+                let mut segments: Vec<Vec<[f64; 2]>> = Vec::new();
+
+                for polyline in curve {
+                    let mut current: Vec<[f64; 2]> = Vec::new();
+
+                    for point in polyline {
+                        if others_hold(index, point) {
+                            current.push(point);
+                        } else if current.len() > 1 {
+                            segments.push(std::mem::take(&mut current));
+                        } else {
+                            current.clear();
+                        }
+                    }
+
+                    if current.len() > 1 {
+                        segments.push(current);
+                    }
+                }
+
+                RegionBoundary {
+                    segments,
+                    strict: comparison.strict
+                }
+            })
+            .filter(|boundary| !boundary.segments.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,391 @@
+use serde::{Serialize, Deserialize};
+
+const MAX_ITERATIONS: usize = 500;
+
+// written out in full so the fitted graph doesn't depend on a variable called e
+const E: &str = "2.718281828459045";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Model {
+    // c0 + c1 x + c2 x^2 + ..., so linear is degree 1
+    Polynomial { degree: usize },
+
+    // a e^(bx)
+    Exponential,
+
+    // a + b ln(x)
+    Logarithmic,
+
+    // a x^b
+    Power,
+
+    // c / (1 + a e^(-bx))
+    Logistic
+}
+
+impl Model {
+    pub fn parse(name: &str, degree: Option<usize>) -> Option<Model> {
+        Some(match (name.trim().to_lowercase().as_str(), degree) {
+            ("linear", None) => Model::Polynomial { degree: 1 },
+            ("quadratic", None) => Model::Polynomial { degree: 2 },
+            ("cubic", None) => Model::Polynomial { degree: 3 },
+            ("polynomial", Some(degree)) if degree > 0 => Model::Polynomial { degree },
+            ("exponential", None) => Model::Exponential,
+            ("logarithmic", None) => Model::Logarithmic,
+            ("power", None) => Model::Power,
+            ("logistic", None) => Model::Logistic,
+
+            _ => {
+                return None;
+            }
+        })
+    }
+
+    fn coefficient_count(&self) -> usize {
+        match self {
+            Model::Polynomial { degree } => degree + 1,
+            Model::Logistic => 3,
+            _ => 2
+        }
+    }
+
+    fn evaluate(&self, x: f64, c: &[f64]) -> f64 {
+        match self {
+            Model::Polynomial { .. } => c.iter().rev().fold(0.0, |acc, c| acc * x + c),
+            Model::Exponential => c[0] * (c[1] * x).exp(),
+            Model::Logarithmic => c[0] + c[1] * x.ln(),
+            Model::Power => c[0] * x.powf(c[1]),
+            Model::Logistic => c[2] / (1.0 + c[0] * (-c[1] * x).exp())
+        }
+    }
+
+    // the model as an expression in x that the lexer can read
+    fn expression(&self, c: &[f64]) -> String {
+        match self {
+            Model::Polynomial { .. } => c.iter()
+                .enumerate()
+                .map(|(power, c)| format!("({})*x^{}", c, power))
+                .collect::<Vec<String>>()
+                .join("+"),
+
+            Model::Exponential => format!("({})*{}^(({})*x)", c[0], E, c[1]),
+            Model::Logarithmic => format!("({})+({})*ln(x)", c[0], c[1]),
+            Model::Power => format!("({})*x^({})", c[0], c[1]),
+            Model::Logistic => format!("({})/(1+({})*{}^(-({})*x))", c[2], c[0], E, c[1])
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FitError {
+    // fewer points than the model has coefficients
+    TooFewPoints,
+
+    // the data can't be fitted by the model, such as logarithmic with x <= 0
+    InvalidData,
+
+    // the equations for the coefficients have no single solution
+    Singular
+}
+
+impl std::fmt::Display for FitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FitError::TooFewPoints => write!(f, "there are too few points for the model"),
+            FitError::InvalidData => write!(f, "the data can't be fitted by the model"),
+            FitError::Singular => write!(f, "the model has no unique fit to the data")
+        }
+    }
+}
+
+impl std::error::Error for FitError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fit {
+    pub model: Model,
+    pub coefficients: Vec<f64>,
+    pub r_squared: f64,
+
+    // observed minus fitted y for each point
+    pub residuals: Vec<f64>,
+
+    // what the fitted graph was made from
+    pub expression: String
+}
+
+// gaussian elimination with partial pivoting, a is square and row by row
+pub fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+
+        if a[pivot][col].abs() < 1e-300 {
+            return None;
+        }
+
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+
+            for (value, above) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * above;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut solution = vec![0.0; n];
+
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| a[row][k] * solution[k]).sum();
+        solution[row] = (b[row] - known) / a[row][row];
+    }
+
+    solution.iter().all(|v| v.is_finite()).then_some(solution)
+}
+
+// the coefficients of the basis functions that best fit ys, through the normal equations
+fn least_squares(rows: &[Vec<f64>], ys: &[f64]) -> Option<Vec<f64>> {
+    let n = rows.first()?.len();
+
+    let mut ata = vec![vec![0.0; n]; n];
+    let mut aty = vec![0.0; n];
+
+    for (row, y) in rows.iter().zip(ys) {
+        for i in 0..n {
+            aty[i] += row[i] * y;
+
+            for j in 0..n {
+                ata[i][j] += row[i] * row[j];
+            }
+        }
+    }
+
+    solve_linear(ata, aty)
+}
+
+fn squared_error(predicted: &[f64], observed: &[f64]) -> f64 {
+    predicted.iter()
+        .zip(observed)
+        .map(|(p, o)| (o - p).powi(2))
+        .sum()
+}
+
+// nonlinear least squares starting from the initial parameters, f gives the model's
+// prediction for every observation and the jacobian is found by central differences
+pub fn levenberg_marquardt<F>(f: F, observed: &[f64], initial: &[f64]) -> Result<Vec<f64>, FitError>
+    where F: Fn(&[f64]) -> Option<Vec<f64>>
+{
+    if observed.len() < initial.len() {
+        return Err(FitError::TooFewPoints);
+    }
+
+    let n = initial.len();
+
+    // a model that can't be evaluated at some parameters is treated as infinitely bad there
+    let predict = |params: &[f64]| f(params).filter(|p| p.len() == observed.len());
+    let cost_of = |params: &[f64]| predict(params).map_or(f64::INFINITY, |p| squared_error(&p, observed));
+
+    let mut params = initial.to_vec();
+    let mut cost = cost_of(&params);
+    let mut damping = 1e-3;
+
+    if !cost.is_finite() {
+        return Err(FitError::InvalidData);
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let predicted = predict(&params).ok_or(FitError::InvalidData)?;
+
+        // one column of the jacobian for each parameter
+        let columns = (0..n)
+            .map(|j| {
+                let h = 1e-6 * params[j].abs().max(1.0);
+
+                let mut above = params.clone();
+                let mut below = params.clone();
+                above[j] += h;
+                below[j] -= h;
+
+                let (above, below) = (predict(&above)?, predict(&below)?);
+
+                Some(above.iter().zip(&below).map(|(a, b)| (a - b) / (2.0 * h)).collect::<Vec<f64>>())
+            })
+            .collect::<Option<Vec<Vec<f64>>>>()
+            .ok_or(FitError::InvalidData)?;
+
+        let residuals: Vec<f64> = observed.iter().zip(&predicted).map(|(o, p)| o - p).collect();
+
+        let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+
+        let jtj: Vec<Vec<f64>> = columns.iter()
+            .map(|a| columns.iter().map(|b| dot(a, b)).collect())
+            .collect();
+        let jtr: Vec<f64> = columns.iter().map(|column| dot(column, &residuals)).collect();
+
+        // raise the damping until a step makes the fit better
+        let mut improved = false;
+
+        while damping < 1e12 {
+            let mut damped = jtj.clone();
+            for (i, row) in damped.iter_mut().enumerate() {
+                row[i] += damping * jtj[i][i].max(1e-12);
+            }
+
+            if let Some(step) = solve_linear(damped, jtr.clone()) {
+                let next: Vec<f64> = params.iter().zip(&step).map(|(p, s)| p + s).collect();
+                let next_cost = cost_of(&next);
+
+                if next_cost.is_finite() && next_cost < cost {
+                    let converged = cost - next_cost <= 1e-15 * cost.max(1e-300);
+
+                    params = next;
+                    cost = next_cost;
+                    damping = (damping / 10.0).max(1e-12);
+                    improved = !converged;
+                    break;
+                }
+            }
+
+            damping *= 10.0;
+        }
+
+        if !improved {
+            break;
+        }
+    }
+
+    Ok(params)
+}
+
+fn r_squared(observed: &[f64], residuals: &[f64]) -> f64 {
+    let mean = observed.iter().sum::<f64>() / observed.len() as f64;
+
+    let total: f64 = observed.iter().map(|y| (y - mean).powi(2)).sum();
+    let unexplained: f64 = residuals.iter().map(|r| r.powi(2)).sum();
+
+    match total == 0.0 {
+        true if unexplained == 0.0 => 1.0,
+        true => 0.0,
+        false => 1.0 - unexplained / total
+    }
+}
+
+// the straight line through (x, ln y) style data, used as a starting point for the nonlinear models
+fn linearised(points: &[[f64; 2]], transform: impl Fn(f64, f64) -> Option<[f64; 2]>) -> Result<Vec<f64>, FitError> {
+    let transformed = points.iter()
+        .map(|[x, y]| transform(*x, *y))
+        .collect::<Option<Vec<[f64; 2]>>>()
+        .ok_or(FitError::InvalidData)?;
+
+    let rows: Vec<Vec<f64>> = transformed.iter().map(|[x, _]| vec![1.0, *x]).collect();
+    let ys: Vec<f64> = transformed.iter().map(|[_, y]| *y).collect();
+
+    least_squares(&rows, &ys).ok_or(FitError::Singular)
+}
+
+fn initial_logistic(points: &[[f64; 2]]) -> Result<Vec<f64>, FitError> {
+    let top = points.iter().map(|[_, y]| *y).fold(f64::NEG_INFINITY, f64::max);
+
+    if top <= 0.0 {
+        return Err(FitError::InvalidData);
+    }
+
+    // c just above the largest value, then ln(c / y - 1) = ln(a) - b x
+    let c = top * 1.05;
+    let line = linearised(points, |x, y| (y > 0.0).then(|| [x, (c / y - 1.0).ln()]))?;
+
+    Ok(vec![line[0].exp(), -line[1], c])
+}
+
+pub fn fit(model: Model, points: &[[f64; 2]]) -> Result<Fit, FitError> {
+    if points.len() < model.coefficient_count() {
+        return Err(FitError::TooFewPoints);
+    }
+
+    if points.iter().flatten().any(|v| !v.is_finite()) {
+        return Err(FitError::InvalidData);
+    }
+
+    let ys: Vec<f64> = points.iter().map(|[_, y]| *y).collect();
+
+    // every prediction of the model with the coefficients
+    let predict = |c: &[f64]| Some(points.iter().map(|[x, _]| model.evaluate(*x, c)).collect());
+
+    let coefficients = match model {
+        Model::Polynomial { degree } => {
+            let rows: Vec<Vec<f64>> = points.iter()
+                .map(|[x, _]| (0..=degree as i32).map(|power| x.powi(power)).collect())
+                .collect();
+
+            least_squares(&rows, &ys).ok_or(FitError::Singular)?
+        },
+
+        Model::Logarithmic => linearised(points, |x, y| (x > 0.0).then(|| [x.ln(), y]))?,
+
+        Model::Exponential => {
+            let line = linearised(points, |x, y| (y > 0.0).then(|| [x, y.ln()]))?;
+            levenberg_marquardt(predict, &ys, &[line[0].exp(), line[1]])?
+        },
+
+        Model::Power => {
+            let line = linearised(points, |x, y| (x > 0.0 && y > 0.0).then(|| [x.ln(), y.ln()]))?;
+            levenberg_marquardt(predict, &ys, &[line[0].exp(), line[1]])?
+        },
+
+        Model::Logistic => levenberg_marquardt(predict, &ys, &initial_logistic(points)?)?
+    };
+
+    let residuals: Vec<f64> = points.iter()
+        .map(|[x, y]| y - model.evaluate(*x, &coefficients))
+        .collect();
+
+    if residuals.iter().any(|r| !r.is_finite()) {
+        return Err(FitError::InvalidData);
+    }
+
+    Ok(Fit {
+        model,
+        r_squared: r_squared(&ys, &residuals),
+        expression: model.expression(&coefficients),
+        coefficients,
+        residuals
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterFit {
+    pub parameters: Vec<f64>,
+    pub r_squared: f64,
+    pub residuals: Vec<f64>
+}
+
+// fits any model given as its prediction for every observation, starting from the initial parameters
+pub fn fit_parameters<F>(f: F, observed: &[f64], initial: &[f64]) -> Result<ParameterFit, FitError>
+    where F: Fn(&[f64]) -> Option<Vec<f64>>
+{
+    if observed.iter().any(|v| !v.is_finite()) {
+        return Err(FitError::InvalidData);
+    }
+
+    let parameters = levenberg_marquardt(&f, observed, initial)?;
+
+    let residuals: Vec<f64> = f(&parameters)
+        .ok_or(FitError::InvalidData)?
+        .iter()
+        .zip(observed)
+        .map(|(p, o)| o - p)
+        .collect();
+
+    Ok(ParameterFit {
+        r_squared: r_squared(observed, &residuals),
+        parameters,
+        residuals
+    })
+}
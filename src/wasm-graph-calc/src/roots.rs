@@ -12,3 +12,64 @@ where
     }
     ret
 }
+
+// narrow in on the highest (or lowest) point between a and b
+fn golden_section<F>(f: F, mut a: f64, mut b: f64, maximum: bool) -> f64
+where
+    F: Fn(f64) -> f64
+{
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let better = |u: f64, v: f64| if maximum { u > v } else { u < v };
+
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+
+    for _ in 0..60 {
+        if better(f(c), f(d)) {
+            b = d;
+        } else {
+            a = c;
+        }
+
+        c = b - ratio * (b - a);
+        d = a + ratio * (b - a);
+    }
+
+    (a + b) / 2.0
+}
+
+// local maxima and minima, found where the function changes direction
+pub fn find_turning_points<F>(f: F, start: f64, stop: f64, step: f64) -> Vec<f64>
+where
+    F: Fn(f64) -> f64
+{
+    let mut ret = vec![];
+
+    let mut x0 = start;
+    let mut y0 = f(x0);
+
+    let mut x1 = start + step;
+    let mut y1 = f(x1);
+
+    while x1 + step <= stop {
+        let x2 = x1 + step;
+        let y2 = f(x2);
+
+        if y0.is_finite() && y1.is_finite() && y2.is_finite() {
+            let maximum = y1 > y0 && y1 >= y2;
+            let minimum = y1 < y0 && y1 <= y2;
+
+            if maximum || minimum {
+                ret.push(golden_section(&f, x0, x2, maximum));
+            }
+        }
+
+        x0 = x1;
+        y0 = y1;
+
+        x1 = x2;
+        y1 = y2;
+    }
+
+    ret
+}
@@ -0,0 +1,137 @@
+// how many times an interval can be halved while looking for detail or a break
+const MAX_DEPTH: u32 = 10;
+
+// collects points into polylines, starting a new one whenever the curve breaks
+struct SegmentBuilder {
+    segments: Vec<Vec<[f64; 2]>>,
+    current: Vec<[f64; 2]>
+}
+
+impl SegmentBuilder {
+    fn push(&mut self, point: [f64; 2]) {
+        if self.current.last() != Some(&point) {
+            self.current.push(point);
+        }
+    }
+
+    fn split(&mut self) {
+        if self.current.len() > 1 {
+            self.segments.push(std::mem::take(&mut self.current));
+        } else {
+            self.current.clear();
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<[f64; 2]>> {
+        self.split();
+        self.segments
+    }
+}
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+fn defined(point: Option<[f64; 2]>) -> Option<[f64; 2]> {
+    point.filter(|p| p[0].is_finite() && p[1].is_finite())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn refine<F>(
+    f: &F,
+    t0: f64,
+    p0: Option<[f64; 2]>,
+    t1: f64,
+    p1: Option<[f64; 2]>,
+    depth: u32,
+    tolerance: f64,
+    out: &mut SegmentBuilder
+)
+    where F: Fn(f64) -> Option<[f64; 2]>
+{
+    // nothing to draw between two undefined points
+    if p0.is_none() && p1.is_none() {
+        return;
+    }
+
+    let tm = (t0 + t1) / 2.0;
+    let pm = defined(f(tm));
+
+    if let (Some(a), Some(m), Some(b)) = (p0, pm, p1) {
+        let chord_midpoint = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+        let flat = distance(m, chord_midpoint) <= tolerance;
+
+        if flat {
+            out.push(m);
+            out.push(b);
+            return;
+        }
+
+        if depth >= MAX_DEPTH {
+            // still bending this sharply over a tiny interval means it jumps, eg an asymptote
+            if distance(a, b) > tolerance * 10.0 {
+                out.split();
+            } else {
+                out.push(m);
+            }
+
+            out.push(b);
+            return;
+        }
+    } else if depth >= MAX_DEPTH {
+        // edge of where the curve is defined
+        match p1 {
+            Some(b) => {
+                out.split();
+                out.push(b);
+            },
+
+            None => out.split()
+        }
+
+        return;
+    }
+
+    refine(f, t0, p0, tm, pm, depth + 1, tolerance, out);
+    refine(f, tm, pm, t1, p1, depth + 1, tolerance, out);
+}
+
+// sample a curve over a parameter range, adding detail where it bends and
+// splitting it into separate polylines where it is undefined or jumps
+pub fn sample_curve<F>(f: F, t_min: f64, t_max: f64, samples: usize, tolerance: f64) -> Vec<Vec<[f64; 2]>>
+    where F: Fn(f64) -> Option<[f64; 2]>
+{
+    let mut out = SegmentBuilder {
+        segments: Vec::new(),
+        current: Vec::new()
+    };
+
+    let samples = samples.max(2);
+    let step = (t_max - t_min) / (samples - 1) as f64;
+
+    let mut t0 = t_min;
+    let mut p0 = defined(f(t0));
+
+    if let Some(p) = p0 {
+        out.push(p);
+    }
+
+    for i in 1..samples {
+        let t1 = t_min + i as f64 * step;
+        let p1 = defined(f(t1));
+
+        refine(&f, t0, p0, t1, p1, 0, tolerance, &mut out);
+
+        t0 = t1;
+        p0 = p1;
+    }
+
+    out.finish()
+}
+
+// sample y = f(x) across the domain
+pub fn sample_function<F>(f: F, min_x: f64, max_x: f64, samples: usize, tolerance: f64) -> Vec<Vec<[f64; 2]>>
+    where F: Fn(f64) -> Option<f64>
+{
+    sample_curve(|x: f64| f(x).map(|y| [x, y]), min_x, max_x, samples, tolerance)
+}
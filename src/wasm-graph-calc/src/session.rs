@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Deserialize};
+
+use crate::AngleMode;
+use crate::slider::Slider;
+
+pub const SESSION_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64
+}
+
+// how the frontend draws the graph on a line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphStyle {
+    pub colour: String,
+    pub width: f64,
+    pub dashed: bool,
+    pub visible: bool
+}
+
+impl Default for GraphStyle {
+    fn default() -> Self {
+        GraphStyle {
+            colour: "black".to_string(),
+            width: 1.5,
+            dashed: false,
+            visible: true
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionLine {
+    pub id: u32,
+    pub input: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<GraphStyle>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u64,
+    pub lines: Vec<SessionLine>,
+    pub viewport: Viewport,
+    pub angle_mode: AngleMode,
+
+    #[serde(default)]
+    pub sliders: BTreeMap<char, Slider>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    // not json, or not the shape of a session, with the position serde_json gives
+    Json(String),
+    MissingVersion,
+    UnsupportedVersion(u64),
+    InvalidViewport,
+    InvalidSlider(char),
+    InvalidStyle(u32),
+    DuplicateLine(u32)
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Json(message) => write!(f, "malformed session file: {}", message),
+            SessionError::MissingVersion => write!(f, "session file has no version"),
+            SessionError::UnsupportedVersion(version) => write!(f, "session version {} is newer than {}, the latest supported", version, SESSION_VERSION),
+            SessionError::InvalidViewport => write!(f, "the viewport must have a minimum below its maximum on each axis"),
+            SessionError::InvalidSlider(name) => write!(f, "the slider for {} needs a minimum below its maximum and a positive step that isn't too small", name),
+            SessionError::InvalidStyle(id) => write!(f, "the style of line {} has a negative or invalid width", id),
+            SessionError::DuplicateLine(id) => write!(f, "line {} appears more than once", id)
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl Session {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("session failed to serialize")
+    }
+
+    // files from a newer version are turned away, older versions are migrated here when there are any
+    pub fn from_json(json: &str) -> Result<Session, SessionError> {
+        let document: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| SessionError::Json(e.to_string()))?;
+
+        let version = document.get("version")
+            .ok_or(SessionError::MissingVersion)?
+            .as_u64()
+            .ok_or_else(|| SessionError::Json("version must be a positive whole number".to_string()))?;
+
+        // parsed again from the text, so errors keep their line and column
+        let session: Session = match version {
+            SESSION_VERSION => serde_json::from_str(json)
+                .map_err(|e| SessionError::Json(e.to_string()))?,
+
+            _ => {
+                return Err(SessionError::UnsupportedVersion(version));
+            }
+        };
+
+        session.validate()?;
+
+        Ok(session)
+    }
+
+    fn validate(&self) -> Result<(), SessionError> {
+        let v = &self.viewport;
+        if v.min_x >= v.max_x || v.min_y >= v.max_y || [v.min_x, v.max_x, v.min_y, v.max_y].iter().any(|b| !b.is_finite()) {
+            return Err(SessionError::InvalidViewport);
+        }
+
+        for (name, slider) in &self.sliders {
+            if !slider.is_valid() {
+                return Err(SessionError::InvalidSlider(*name));
+            }
+        }
+
+        let mut ids: Vec<u32> = Vec::new();
+
+        for line in &self.lines {
+            if ids.contains(&line.id) {
+                return Err(SessionError::DuplicateLine(line.id));
+            }
+
+            ids.push(line.id);
+
+            if let Some(style) = &line.style {
+                if style.width < 0.0 || !style.width.is_finite() {
+                    return Err(SessionError::InvalidStyle(line.id));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
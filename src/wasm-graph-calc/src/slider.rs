@@ -0,0 +1,79 @@
+use serde::{Serialize, Deserialize};
+
+// frames used to animate a slider that has no step
+const DEFAULT_FRAMES: usize = 100;
+
+// the most values a slider's step can break it into, so animating it stays within memory
+const MAX_FRAMES: f64 = 100000.0;
+
+// the range a variable can be dragged through, from a definition like a = 2 {0..10 step 0.5}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Slider {
+    pub min: f64,
+    pub max: f64,
+    pub step: Option<f64>
+}
+
+impl Slider {
+    // takes the inside of the braces with spaces removed, eg "0..10step0.5",
+    // evaluating each bound with the given function
+    pub fn parse<F>(spec: &str, evaluate: F) -> Option<Slider>
+        where F: Fn(&str) -> Option<f64>
+    {
+        let (range, step) = match spec.split_once("step") {
+            Some((range, step)) => (range, Some(evaluate(step)?)),
+            None => (spec, None)
+        };
+
+        let (min, max) = range.split_once("..")?;
+
+        let slider = Slider {
+            min: evaluate(min)?,
+            max: evaluate(max)?,
+            step
+        };
+
+        if !slider.is_valid() {
+            return None;
+        }
+
+        Some(slider)
+    }
+
+    // a finite range with min below max, and a positive step that doesn't give too many frames
+    pub fn is_valid(&self) -> bool {
+        let valid_step = match self.step {
+            Some(s) => s > 0.0 && (self.max - self.min) / s <= MAX_FRAMES,
+            None => true
+        };
+
+        self.min.is_finite() && self.max.is_finite() && self.min < self.max && valid_step
+    }
+
+    // every value the slider passes through from min to max
+    pub fn frames(&self) -> Vec<f64> {
+        let count = match self.step {
+            Some(step) => ((self.max - self.min) / step + 1e-9).floor() as usize,
+            None => DEFAULT_FRAMES
+        };
+
+        let step = self.step.unwrap_or((self.max - self.min) / DEFAULT_FRAMES as f64);
+
+        (0..=count)
+            .map(|i| self.min + i as f64 * step)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameGraph {
+    pub name: String,
+    pub segments: Vec<Vec<[f64; 2]>>
+}
+
+// the graphs depending on a slider, redrawn for one of its values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationFrame {
+    pub value: f64,
+    pub graphs: Vec<FrameGraph>
+}
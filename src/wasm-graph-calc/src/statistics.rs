@@ -0,0 +1,195 @@
+use serde::{Serialize, Deserialize};
+
+use crate::value::Value;
+
+// names of the built in functions, each taking any mix of lists and numbers
+pub const STATISTICS: [&str; 15] = [
+    "mean", "median", "mode", "stdevp", "stdev", "varp", "var", "quartiles", "summary", "sum", "product", "prod", "min", "max", "count"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatsError {
+    Empty,
+
+    // such as the sample variance of a single value
+    TooFewValues,
+
+    // every value appears the same number of times
+    NoMode
+}
+
+impl std::fmt::Display for StatsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatsError::Empty => write!(f, "the list is empty"),
+            StatsError::TooFewValues => write!(f, "there are too few values"),
+            StatsError::NoMode => write!(f, "no value appears more often than the others")
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+// whether a measure describes the whole population or estimates it from a sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Estimate {
+    Population,
+    Sample
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FiveNumberSummary {
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64
+}
+
+fn non_empty(values: &[f64]) -> Result<&[f64], StatsError> {
+    match values.is_empty() {
+        true => Err(StatsError::Empty),
+        false => Ok(values)
+    }
+}
+
+fn sorted(values: &[f64]) -> Result<Vec<f64>, StatsError> {
+    let mut sorted = non_empty(values)?.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    Ok(sorted)
+}
+
+fn median_of_sorted(sorted: &[f64]) -> Result<f64, StatsError> {
+    let n = non_empty(sorted)?.len();
+
+    Ok(match n % 2 {
+        0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        _ => sorted[n / 2]
+    })
+}
+
+pub fn sum(values: &[f64]) -> Result<f64, StatsError> {
+    Ok(non_empty(values)?.iter().sum())
+}
+
+pub fn product(values: &[f64]) -> Result<f64, StatsError> {
+    Ok(non_empty(values)?.iter().product())
+}
+
+pub fn min(values: &[f64]) -> Result<f64, StatsError> {
+    Ok(non_empty(values)?.iter().copied().fold(f64::INFINITY, f64::min))
+}
+
+pub fn max(values: &[f64]) -> Result<f64, StatsError> {
+    Ok(non_empty(values)?.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+}
+
+pub fn mean(values: &[f64]) -> Result<f64, StatsError> {
+    Ok(sum(values)? / values.len() as f64)
+}
+
+pub fn median(values: &[f64]) -> Result<f64, StatsError> {
+    median_of_sorted(&sorted(values)?)
+}
+
+// the most common value, the smallest of them if several are equally common
+pub fn mode(values: &[f64]) -> Result<f64, StatsError> {
+    let sorted = sorted(values)?;
+
+    let mut best: Option<(f64, usize)> = None;
+    let mut runs: usize = 0;
+
+    for run in sorted.chunk_by(|a, b| a == b) {
+        runs += 1;
+
+        if best.is_none_or(|(_, count)| run.len() > count) {
+            best = Some((run[0], run.len()));
+        }
+    }
+
+    match best {
+        // a single repeated value has a mode, several values that never repeat don't
+        Some((value, count)) if count > 1 || runs == 1 => Ok(value),
+        _ => Err(StatsError::NoMode)
+    }
+}
+
+pub fn variance(values: &[f64], estimate: Estimate) -> Result<f64, StatsError> {
+    let mean = mean(values)?;
+    let n = values.len() as f64;
+
+    let squares: f64 = values.iter()
+        .map(|v| (v - mean).powi(2))
+        .sum();
+
+    match estimate {
+        Estimate::Population => Ok(squares / n),
+
+        Estimate::Sample if values.len() < 2 => Err(StatsError::TooFewValues),
+        Estimate::Sample => Ok(squares / (n - 1.0))
+    }
+}
+
+pub fn standard_deviation(values: &[f64], estimate: Estimate) -> Result<f64, StatsError> {
+    variance(values, estimate).map(f64::sqrt)
+}
+
+// the medians of the lower and upper halves, leaving out the middle value when there's an odd count
+pub fn quartiles(values: &[f64]) -> Result<(f64, f64, f64), StatsError> {
+    let sorted = sorted(values)?;
+    let n = sorted.len();
+
+    if n < 2 {
+        return Err(StatsError::TooFewValues);
+    }
+
+    let lower = &sorted[..n / 2];
+    let upper = &sorted[n.div_ceil(2)..];
+
+    Ok((median_of_sorted(lower)?, median_of_sorted(&sorted)?, median_of_sorted(upper)?))
+}
+
+pub fn five_number_summary(values: &[f64]) -> Result<FiveNumberSummary, StatsError> {
+    let (q1, median, q3) = quartiles(values)?;
+
+    Ok(FiveNumberSummary {
+        min: min(values)?,
+        q1,
+        median,
+        q3,
+        max: max(values)?
+    })
+}
+
+// the built in function with the given name
+pub fn apply(name: &str, values: &[f64]) -> Result<Value, StatsError> {
+    let number = match name {
+        "mean" => mean(values)?,
+        "median" => median(values)?,
+        "mode" => mode(values)?,
+        "stdev" => standard_deviation(values, Estimate::Sample)?,
+        "stdevp" => standard_deviation(values, Estimate::Population)?,
+        "var" => variance(values, Estimate::Sample)?,
+        "varp" => variance(values, Estimate::Population)?,
+        "sum" => sum(values)?,
+        "prod" | "product" => product(values)?,
+        "min" => min(values)?,
+        "max" => max(values)?,
+        "count" => values.len() as f64,
+
+        "quartiles" => {
+            let (q1, median, q3) = quartiles(values)?;
+            return Ok(Value::List(vec![q1, median, q3]));
+        },
+
+        "summary" => {
+            let s = five_number_summary(values)?;
+            return Ok(Value::List(vec![s.min, s.q1, s.median, s.q3, s.max]));
+        },
+
+        _ => unreachable!()
+    };
+
+    Ok(Value::Number(number))
+}
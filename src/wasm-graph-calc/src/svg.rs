@@ -0,0 +1,353 @@
+use serde::{Serialize, Deserialize};
+
+use crate::session::{GraphStyle, Viewport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlotSettings {
+    pub width: f64,
+    pub height: f64,
+
+    pub axes: bool,
+    pub grid: bool,
+    pub legend: bool,
+
+    // distance between ticks and grid lines, picked from the viewport when None
+    pub tick_spacing: Option<f64>
+}
+
+impl Default for PlotSettings {
+    fn default() -> Self {
+        PlotSettings {
+            width: 600.0,
+            height: 600.0,
+
+            axes: true,
+            grid: true,
+            legend: true,
+
+            tick_spacing: None
+        }
+    }
+}
+
+// cells of the viewport to fill in, row by row from the bottom, for the region of an inequality
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shading {
+    pub cells: Vec<bool>,
+    pub columns: usize,
+    pub rows: usize
+}
+
+// a named graph with the polylines to draw for it, and for inequalities and
+// scatter plots the region to shade and the points to mark
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotSeries {
+    pub name: String,
+    pub segments: Vec<Vec<[f64; 2]>>,
+
+    // always dashed, the strict edges of an inequality which aren't part of its region
+    pub dashed_segments: Vec<Vec<[f64; 2]>>,
+
+    pub points: Vec<[f64; 2]>,
+    pub shading: Option<Shading>,
+    pub style: GraphStyle
+}
+
+impl PlotSeries {
+    // a graph or curve that is only lines
+    pub fn curve(name: String, segments: Vec<Vec<[f64; 2]>>, style: GraphStyle) -> PlotSeries {
+        PlotSeries { name, segments, dashed_segments: Vec::new(), points: Vec::new(), shading: None, style }
+    }
+}
+
+const FONT_SIZE: f64 = 12.0;
+const INTERCEPT_RADIUS: f64 = 4.0;
+const POINT_RADIUS: f64 = 3.0;
+const SHADING_OPACITY: f64 = 0.25;
+
+// more ticks than this along an axis can't be told apart, and a tiny spacing would make millions
+const MAX_TICKS: f64 = 1000.0;
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// roughly ten ticks across the range, at 1, 2 or 5 times a power of ten
+fn tick_step(range: f64) -> f64 {
+    let rough = range / 10.0;
+    let magnitude = 10f64.powf(rough.log10().floor());
+
+    [1.0, 2.0, 5.0, 10.0].iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn tick_label(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let label = format!("{:.*}", decimals, value);
+
+    // -0 from rounding a tiny negative
+    if label.trim_start_matches('-').chars().all(|c| c == '0' || c == '.') {
+        return label.trim_start_matches('-').to_string();
+    }
+
+    label
+}
+
+// the spacing asked for when it is positive and gives a sensible number of ticks, otherwise one picked from the range
+fn spacing(setting: Option<f64>, range: f64) -> f64 {
+    match setting {
+        Some(step) if step.is_finite() && step > 0.0 && range / step <= MAX_TICKS => step,
+        _ => tick_step(range)
+    }
+}
+
+// multiples of step inside min..max, none when there would be too many to draw
+fn ticks(min: f64, max: f64, step: f64) -> Vec<f64> {
+    if !step.is_finite() || step <= 0.0 || (max - min) / step > MAX_TICKS {
+        return Vec::new();
+    }
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+struct Projection {
+    viewport: Viewport,
+    width: f64,
+    height: f64
+}
+
+impl Projection {
+    fn x(&self, x: f64) -> f64 {
+        (x - self.viewport.min_x) / (self.viewport.max_x - self.viewport.min_x) * self.width
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        self.height - (y - self.viewport.min_y) / (self.viewport.max_y - self.viewport.min_y) * self.height
+    }
+}
+
+// a standalone svg document of the graphs, with intercepts marked
+pub fn render_svg(series: &[PlotSeries], intercepts: &[[f64; 2]], viewport: Viewport, settings: &PlotSettings) -> String {
+    let width = settings.width;
+    let height = settings.height;
+
+    let projection = Projection { viewport, width, height };
+
+    let mut out = String::new();
+
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    ));
+    out.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+
+    // nothing is drawn outside of the viewport
+    out.push_str(&format!(
+        "<defs><clipPath id=\"plot\"><rect width=\"{}\" height=\"{}\"/></clipPath></defs>\n",
+        width,
+        height
+    ));
+
+    let x_step = spacing(settings.tick_spacing, viewport.max_x - viewport.min_x);
+    let y_step = spacing(settings.tick_spacing, viewport.max_y - viewport.min_y);
+
+    let x_ticks = ticks(viewport.min_x, viewport.max_x, x_step);
+    let y_ticks = ticks(viewport.min_y, viewport.max_y, y_step);
+
+    if settings.grid {
+        out.push_str("<g stroke=\"#e0e0e0\" stroke-width=\"1\">\n");
+
+        for x in &x_ticks {
+            let px = projection.x(*x);
+            out.push_str(&format!("<line x1=\"{:.2}\" y1=\"0\" x2=\"{:.2}\" y2=\"{}\"/>\n", px, px, height));
+        }
+
+        for y in &y_ticks {
+            let py = projection.y(*y);
+            out.push_str(&format!("<line x1=\"0\" y1=\"{:.2}\" x2=\"{}\" y2=\"{:.2}\"/>\n", py, width, py));
+        }
+
+        out.push_str("</g>\n");
+    }
+
+    if settings.axes {
+        // axes stay on the edge when zero is off screen, so the labels are always visible
+        let axis_x = projection.y(0.0).clamp(0.0, height);
+        let axis_y = projection.x(0.0).clamp(0.0, width);
+
+        out.push_str("<g stroke=\"black\" stroke-width=\"1\">\n");
+        out.push_str(&format!("<line x1=\"0\" y1=\"{:.2}\" x2=\"{}\" y2=\"{:.2}\"/>\n", axis_x, width, axis_x));
+        out.push_str(&format!("<line x1=\"{:.2}\" y1=\"0\" x2=\"{:.2}\" y2=\"{}\"/>\n", axis_y, axis_y, height));
+        out.push_str("</g>\n");
+
+        out.push_str(&format!("<g font-family=\"sans-serif\" font-size=\"{}\" fill=\"black\">\n", FONT_SIZE));
+
+        for x in x_ticks.iter().filter(|x| x.abs() > x_step / 2.0) {
+            out.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{}</text>\n",
+                projection.x(*x),
+                (axis_x + FONT_SIZE + 2.0).min(height - 2.0),
+                tick_label(*x, x_step)
+            ));
+        }
+
+        for y in y_ticks.iter().filter(|y| y.abs() > y_step / 2.0) {
+            out.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\">{}</text>\n",
+                (axis_y - 4.0).max(FONT_SIZE * 2.0),
+                projection.y(*y) + FONT_SIZE / 3.0,
+                tick_label(*y, y_step)
+            ));
+        }
+
+        out.push_str("</g>\n");
+    }
+
+    // regions go underneath every line
+    for s in series.iter().filter(|s| s.style.visible) {
+        if let Some(shading) = &s.shading {
+            out.push_str(&format!(
+                "<g clip-path=\"url(#plot)\" fill=\"{}\" fill-opacity=\"{}\" stroke=\"none\">\n",
+                escape_xml(&s.style.colour),
+                SHADING_OPACITY
+            ));
+
+            let cell_width = width / shading.columns as f64;
+            let cell_height = height / shading.rows as f64;
+
+            for (row, cells) in shading.cells.chunks(shading.columns).enumerate() {
+                let top = height - (row + 1) as f64 * cell_height;
+                let mut column = 0;
+
+                // a run of shaded cells along a row is one rectangle
+                while column < cells.len() {
+                    let run = cells[column..].iter().take_while(|shaded| **shaded).count();
+
+                    if run > 0 {
+                        out.push_str(&format!(
+                            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>\n",
+                            column as f64 * cell_width,
+                            top,
+                            run as f64 * cell_width,
+                            cell_height
+                        ));
+                    }
+
+                    column += run.max(1);
+                }
+            }
+
+            out.push_str("</g>\n");
+        }
+    }
+
+    out.push_str("<g clip-path=\"url(#plot)\" fill=\"none\">\n");
+
+    for s in series.iter().filter(|s| s.style.visible) {
+        let lines = s.segments.iter()
+            .map(|segment| (segment, s.style.dashed))
+            .chain(s.dashed_segments.iter().map(|segment| (segment, true)));
+
+        for (segment, dashed) in lines.filter(|(segment, _)| segment.len() > 1) {
+            let points: Vec<String> = segment.iter()
+                .map(|p| format!("{:.2},{:.2}", projection.x(p[0]), projection.y(p[1])))
+                .collect();
+
+            out.push_str(&format!(
+                "<polyline points=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{}/>\n",
+                points.join(" "),
+                escape_xml(&s.style.colour),
+                s.style.width,
+                if dashed { " stroke-dasharray=\"6 4\"" } else { "" }
+            ));
+        }
+    }
+
+    out.push_str("</g>\n");
+
+    for s in series.iter().filter(|s| s.style.visible && !s.points.is_empty()) {
+        out.push_str(&format!("<g clip-path=\"url(#plot)\" fill=\"{}\">\n", escape_xml(&s.style.colour)));
+
+        for p in &s.points {
+            out.push_str(&format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\"/>\n",
+                projection.x(p[0]),
+                projection.y(p[1]),
+                POINT_RADIUS
+            ));
+        }
+
+        out.push_str("</g>\n");
+    }
+
+    if !intercepts.is_empty() {
+        out.push_str("<g fill=\"red\">\n");
+
+        for p in intercepts {
+            out.push_str(&format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\"/>\n",
+                projection.x(p[0]),
+                projection.y(p[1]),
+                INTERCEPT_RADIUS
+            ));
+        }
+
+        out.push_str("</g>\n");
+    }
+
+    let visible: Vec<&PlotSeries> = series.iter().filter(|s| s.style.visible).collect();
+
+    if settings.legend && !visible.is_empty() {
+        let line_height = FONT_SIZE * 1.5;
+        let legend_width = 40.0 + FONT_SIZE * 0.6 * visible.iter()
+            .map(|s| s.name.chars().count())
+            .max()
+            .unwrap_or(0) as f64;
+
+        let left = width - legend_width - 10.0;
+
+        out.push_str(&format!(
+            "<g font-family=\"sans-serif\" font-size=\"{}\">\n<rect x=\"{:.2}\" y=\"10\" width=\"{:.2}\" height=\"{:.2}\" fill=\"white\" stroke=\"black\"/>\n",
+            FONT_SIZE,
+            left,
+            legend_width,
+            line_height * visible.len() as f64 + 6.0
+        ));
+
+        for (i, s) in visible.iter().enumerate() {
+            let y = 13.0 + line_height * (i as f64 + 0.5);
+
+            out.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                left + 6.0,
+                y,
+                left + 26.0,
+                y,
+                escape_xml(&s.style.colour),
+                s.style.width
+            ));
+
+            out.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\">{}</text>\n",
+                left + 32.0,
+                y + FONT_SIZE / 3.0,
+                escape_xml(&s.name)
+            ));
+        }
+
+        out.push_str("</g>\n");
+    }
+
+    out.push_str("</svg>\n");
+
+    out
+}
@@ -0,0 +1,45 @@
+use std::collections::hash_map::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{AngleMode, ParseTree};
+
+// one x value, and the value of each column there, None where it is undefined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableRow {
+    pub x: f64,
+    pub values: Vec<Option<f64>>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<TableRow>
+}
+
+// evaluate every column at start, start + step, ... for count rows
+pub fn tabulate(
+    columns: &[(String, ParseTree)],
+    vars: &HashMap<char, f64>,
+    angle: AngleMode,
+    start: f64,
+    step: f64,
+    count: usize
+) -> Table {
+    let rows = (0..count)
+        .map(|i| {
+            let x = start + i as f64 * step;
+
+            let values = columns.iter()
+                .map(|(_, tree)| tree.evaluate(Some(x), vars, angle).ok().filter(|y| y.is_finite()))
+                .collect();
+
+            TableRow { x, values }
+        })
+        .collect();
+
+    Table {
+        columns: columns.iter().map(|(name, _)| name.clone()).collect(),
+        rows
+    }
+}
@@ -0,0 +1,292 @@
+use std::ops::{Mul, Div, Neg};
+
+// powers of the seven SI base units, in this order
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+// the order base units are written in, so a force is kg m/s^2
+const DISPLAY_ORDER: [usize; 7] = [1, 0, 2, 3, 4, 5, 6];
+
+// powers of a unit within this of a whole number are taken as whole, so sqrt(m^2) is m
+const POWER_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimension([i32; 7]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 7]);
+
+    pub const fn new(powers: [i32; 7]) -> Dimension {
+        Dimension(powers)
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Dimension::NONE
+    }
+
+    // fails when a power wouldn't be whole, like the square root of a metre,
+    // or is too large to keep, like (m^2000000000)^2
+    pub fn pow(&self, power: f64) -> Result<Dimension, UnitError> {
+        let mut powers = [0; 7];
+
+        for (new, old) in powers.iter_mut().zip(self.0) {
+            let raised = old as f64 * power;
+
+            if (raised - raised.round()).abs() > POWER_TOLERANCE {
+                return Err(UnitError::FractionalPower);
+            }
+
+            if raised.round().abs() > i32::MAX as f64 {
+                return Err(UnitError::Invalid);
+            }
+
+            *new = raised.round() as i32;
+        }
+
+        Ok(Dimension(powers))
+    }
+
+    // the dimension of a product, or of a quotient when the other is raised to -1
+    fn combine(&self, other: &Dimension, power: i32) -> Result<Dimension, UnitError> {
+        let mut powers = self.0;
+
+        for (power_of_self, power_of_other) in powers.iter_mut().zip(other.0) {
+            *power_of_self = power.checked_mul(power_of_other)
+                .and_then(|change| power_of_self.checked_add(change))
+                .ok_or(UnitError::Invalid)?;
+        }
+
+        Ok(Dimension(powers))
+    }
+}
+
+// named derived units are used when they match exactly, otherwise the base units,
+// and a dimensionless number is 1
+impl std::fmt::Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "1");
+        }
+
+        if let Some((symbol, ..)) = DERIVED_UNITS.iter().find(|(_, _, dimension)| dimension == self) {
+            return write!(f, "{}", symbol);
+        }
+
+        let part = |index: usize, power: i32| match power {
+            1 => BASE_UNITS[index].to_string(),
+            _ => format!("{}^{}", BASE_UNITS[index], power)
+        };
+
+        let numerator: Vec<String> = DISPLAY_ORDER.iter()
+            .filter(|i| self.0[**i] > 0)
+            .map(|i| part(*i, self.0[*i]))
+            .collect();
+
+        let denominator: Vec<String> = DISPLAY_ORDER.iter()
+            .filter(|i| self.0[**i] < 0)
+            .map(|i| part(*i, -self.0[*i]))
+            .collect();
+
+        let numerator = match numerator.is_empty() {
+            true => "1".to_string(),
+            false => numerator.join(" ")
+        };
+
+        match denominator.len() {
+            0 => write!(f, "{}", numerator),
+            1 => write!(f, "{}/{}", numerator, denominator[0]),
+            _ => write!(f, "{}/({})", numerator, denominator.join(" "))
+        }
+    }
+}
+
+// derived units that results are written in, by symbol, size in base units and dimension
+const DERIVED_UNITS: [(&str, f64, Dimension); 11] = [
+    ("N", 1.0, Dimension([1, 1, -2, 0, 0, 0, 0])),
+    ("Pa", 1.0, Dimension([-1, 1, -2, 0, 0, 0, 0])),
+    ("J", 1.0, Dimension([2, 1, -2, 0, 0, 0, 0])),
+    ("W", 1.0, Dimension([2, 1, -3, 0, 0, 0, 0])),
+    ("C", 1.0, Dimension([0, 0, 1, 1, 0, 0, 0])),
+    ("V", 1.0, Dimension([2, 1, -3, -1, 0, 0, 0])),
+    ("Ω", 1.0, Dimension([2, 1, -3, -2, 0, 0, 0])),
+    ("F", 1.0, Dimension([-2, -1, 4, 2, 0, 0, 0])),
+    ("Wb", 1.0, Dimension([2, 1, -2, -1, 0, 0, 0])),
+    ("T", 1.0, Dimension([0, 1, -2, -1, 0, 0, 0])),
+    ("H", 1.0, Dimension([2, 1, -2, -2, 0, 0, 0]))
+];
+
+// units that are read but never written, the gram so that kg is a prefixed unit
+const OTHER_UNITS: [(&str, f64, Dimension); 13] = [
+    ("m", 1.0, Dimension([1, 0, 0, 0, 0, 0, 0])),
+    ("g", 1e-3, Dimension([0, 1, 0, 0, 0, 0, 0])),
+    ("s", 1.0, Dimension([0, 0, 1, 0, 0, 0, 0])),
+    ("A", 1.0, Dimension([0, 0, 0, 1, 0, 0, 0])),
+    ("K", 1.0, Dimension([0, 0, 0, 0, 1, 0, 0])),
+    ("mol", 1.0, Dimension([0, 0, 0, 0, 0, 1, 0])),
+    ("cd", 1.0, Dimension([0, 0, 0, 0, 0, 0, 1])),
+    ("Hz", 1.0, Dimension([0, 0, -1, 0, 0, 0, 0])),
+    ("S", 1.0, Dimension([-2, -1, 3, 2, 0, 0, 0])),
+    ("rad", 1.0, Dimension::NONE),
+    ("L", 1e-3, Dimension([3, 0, 0, 0, 0, 0, 0])),
+
+    // not SI, but needed for things like km/h
+    ("min", 60.0, Dimension([0, 0, 1, 0, 0, 0, 0])),
+    ("h", 3600.0, Dimension([0, 0, 1, 0, 0, 0, 0]))
+];
+
+// units that can't be prefixed, so mmin isn't a unit
+const UNPREFIXED: [&str; 2] = ["min", "h"];
+
+const PREFIXES: [(&str, f64); 20] = [
+    ("Y", 1e24), ("Z", 1e21), ("E", 1e18), ("P", 1e15), ("T", 1e12), ("G", 1e9), ("M", 1e6),
+    ("k", 1e3), ("h", 1e2), ("da", 1e1), ("d", 1e-1), ("c", 1e-2), ("m", 1e-3),
+    ("μ", 1e-6), ("u", 1e-6), ("n", 1e-9), ("p", 1e-12), ("f", 1e-15), ("a", 1e-18), ("z", 1e-21)
+];
+
+fn units() -> impl Iterator<Item = &'static (&'static str, f64, Dimension)> {
+    DERIVED_UNITS.iter().chain(OTHER_UNITS.iter())
+}
+
+// the size in base units and dimension of a unit, a symbol on its own is read before a prefixed one
+// so m is a metre rather than a milli something
+pub fn find_unit(name: &str) -> Option<(f64, Dimension)> {
+    if let Some((_, scale, dimension)) = units().find(|(symbol, ..)| *symbol == name) {
+        return Some((*scale, *dimension));
+    }
+
+    PREFIXES.iter()
+        .filter_map(|(prefix, factor)| Some((name.strip_prefix(prefix)?, factor)))
+        .find_map(|(rest, factor)| {
+            let (symbol, scale, dimension) = units().find(|(symbol, ..)| *symbol == rest)?;
+
+            match UNPREFIXED.contains(symbol) {
+                true => None,
+                false => Some((factor * scale, *dimension))
+            }
+        })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitError {
+    // adding or comparing different kinds of quantity, like kg and m
+    Incompatible { left: Dimension, right: Dimension },
+
+    // functions like sin and powers need plain numbers
+    NotDimensionless { found: Dimension },
+
+    // a power that would leave a unit raised to a fraction
+    FractionalPower,
+
+    // lists, calculus and statistics only work on plain numbers
+    Unsupported,
+
+    Invalid
+}
+
+impl std::fmt::Display for UnitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitError::Incompatible { left, right } => write!(f, "can't combine {} with {}", left, right),
+            UnitError::NotDimensionless { found } => write!(f, "expected a number without units, found {}", found),
+            UnitError::FractionalPower => write!(f, "units can't be raised to a fractional power"),
+            UnitError::Unsupported => write!(f, "units can't be used here"),
+            UnitError::Invalid => write!(f, "invalid expression")
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+// a value in base units, with its dimension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension
+}
+
+impl Quantity {
+    pub fn new(value: f64, dimension: Dimension) -> Quantity {
+        Quantity { value, dimension }
+    }
+
+    // a unit such as km^2, as a quantity of one of it
+    pub fn unit(name: &str, power: i32) -> Option<Quantity> {
+        let (scale, dimension) = find_unit(name)?;
+
+        Some(Quantity::new(scale.powi(power), dimension.pow(power as f64).ok()?))
+    }
+
+    pub fn dimensionless(&self) -> Result<f64, UnitError> {
+        match self.dimension.is_dimensionless() {
+            true => Ok(self.value),
+            false => Err(UnitError::NotDimensionless { found: self.dimension })
+        }
+    }
+
+    // addition, subtraction and comparison need both sides to be the same kind of quantity
+    pub fn same_dimension(&self, other: &Quantity) -> Result<Dimension, UnitError> {
+        match self.dimension == other.dimension {
+            true => Ok(self.dimension),
+            false => Err(UnitError::Incompatible { left: self.dimension, right: other.dimension })
+        }
+    }
+
+    pub fn pow(&self, power: &Quantity) -> Result<Quantity, UnitError> {
+        let power = power.dimensionless()?;
+        let dimension = self.dimension.pow(power)?;
+
+        Ok(Quantity::new(self.value.powf(power), dimension))
+    }
+
+    // the value written in the unit, which has to be the same kind of quantity
+    pub fn in_unit(&self, unit: &Quantity, name: &str) -> Result<Measurement, UnitError> {
+        self.same_dimension(unit)?;
+
+        Ok(Measurement { value: self.value / unit.value, unit: Some(name.trim().to_string()) })
+    }
+
+    // the value in base units, or in a named derived unit
+    pub fn measurement(&self) -> Measurement {
+        Measurement {
+            value: self.value,
+            unit: (!self.dimension.is_dimensionless()).then(|| self.dimension.to_string())
+        }
+    }
+}
+
+// fails when a unit's power grows too large to keep
+impl Mul for Quantity {
+    type Output = Result<Quantity, UnitError>;
+
+    fn mul(self, other: Quantity) -> Result<Quantity, UnitError> {
+        Ok(Quantity::new(self.value * other.value, self.dimension.combine(&other.dimension, 1)?))
+    }
+}
+
+impl Div for Quantity {
+    type Output = Result<Quantity, UnitError>;
+
+    fn div(self, other: Quantity) -> Result<Quantity, UnitError> {
+        Ok(Quantity::new(self.value / other.value, self.dimension.combine(&other.dimension, -1)?))
+    }
+}
+
+impl Neg for Quantity {
+    type Output = Quantity;
+
+    fn neg(self) -> Quantity {
+        Quantity::new(-self.value, self.dimension)
+    }
+}
+
+impl From<f64> for Quantity {
+    fn from(value: f64) -> Quantity {
+        Quantity::new(value, Dimension::NONE)
+    }
+}
+
+// a quantity as it is shown, like 90 in km/h, a plain number has no unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub value: f64,
+    pub unit: Option<String>
+}
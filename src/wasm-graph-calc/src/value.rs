@@ -0,0 +1,126 @@
+use serde::{Serialize, Deserialize};
+
+use crate::EvaluateError;
+use crate::complex::Complex;
+use crate::linalg::Decomposition;
+use crate::matrix::{Matrix, MatrixError};
+
+// anything a variable can hold other than a single number, which lives in the vars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum Value {
+    Number(f64),
+    List(Vec<f64>),
+    Points(Vec<[f64; 2]>),
+
+    // only in complex mode, and only when the imaginary part isn't zero
+    Complex(Complex),
+
+    // a list of lists, [[1, 2], [3, 4]]
+    Matrix(Matrix),
+
+    // the factors from lu, qr, eig or svd, which are shown but not calculated with
+    Decomposition(Decomposition)
+}
+
+// why a value couldn't be worked out, keeping matrix errors so they can be shown
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueError {
+    Matrix(MatrixError),
+    Invalid
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueError::Matrix(e) => write!(f, "{}", e),
+            ValueError::Invalid => write!(f, "failed to evaluate expression")
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl From<EvaluateError> for ValueError {
+    fn from(_: EvaluateError) -> ValueError {
+        ValueError::Invalid
+    }
+}
+
+impl From<MatrixError> for ValueError {
+    fn from(e: MatrixError) -> ValueError {
+        ValueError::Matrix(e)
+    }
+}
+
+impl From<Complex> for Value {
+    fn from(z: Complex) -> Value {
+        match z.is_real() {
+            true => Value::Number(z.re),
+            false => Value::Complex(z)
+        }
+    }
+}
+
+impl Value {
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[f64]> {
+        match self {
+            Value::List(l) => Some(l),
+            _ => None
+        }
+    }
+}
+
+// apply f to the arguments, element by element if any of them is a list,
+// numbers are paired with every element and lists have to be the same length
+pub fn broadcast<F>(args: &[Value], f: F) -> Result<Value, EvaluateError>
+    where F: Fn(&[f64]) -> Option<f64>
+{
+    let mut length: Option<usize> = None;
+
+    for arg in args {
+        match arg {
+            Value::Number(_) => {},
+            Value::List(l) => {
+                if length.is_some_and(|n| n != l.len()) {
+                    return Err(EvaluateError);
+                }
+
+                length = Some(l.len());
+            },
+
+            Value::Points(_) | Value::Complex(_) | Value::Matrix(_) | Value::Decomposition(_) => {
+                return Err(EvaluateError);
+            }
+        }
+    }
+
+    let element = |i: Option<usize>| -> Option<f64> {
+        let numbers: Vec<f64> = args.iter()
+            .map(|arg| match (arg, i) {
+                (Value::List(l), Some(i)) => l[i],
+                (Value::Number(n), _) => *n,
+
+                _ => unreachable!()
+            })
+            .collect();
+
+        f(&numbers)
+    };
+
+    match length {
+        None => element(None).map(Value::Number).ok_or(EvaluateError),
+
+        Some(n) => (0..n)
+            .map(|i| element(Some(i)).ok_or(EvaluateError))
+            .collect::<Result<Vec<f64>, EvaluateError>>()
+            .map(Value::List)
+    }
+}
@@ -1,6 +1,39 @@
 use wasm_graph_calc::*;
-use wasm_graph_calc::roots::find_roots;
 use std::collections::HashMap;
+use wasm_graph_calc::slider::Slider;
+use wasm_graph_calc::definitions::DefinitionError;
+use wasm_graph_calc::session::*;
+use wasm_graph_calc::csv::*;
+use wasm_graph_calc::svg::*;
+use wasm_graph_calc::statistics::*;
+use wasm_graph_calc::regression::*;
+use wasm_graph_calc::complex::*;
+use wasm_graph_calc::format::*;
+use wasm_graph_calc::units::*;
+use wasm_graph_calc::matrix::*;
+use wasm_graph_calc::linalg::*;
+
+// the response to an expression that has to give a single value
+fn value_response(evaluator: &mut Evaluator, input: &str) -> EvaluatorResponse {
+    match evaluator.evaluate_response(input, -10.0, 10.0) {
+        Some(response @ EvaluatorResponse::Value { .. }) => response,
+        other => panic!("expected a value, got {:?}", other)
+    }
+}
+
+fn value(evaluator: &mut Evaluator, input: &str) -> f64 {
+    match value_response(evaluator, input) {
+        EvaluatorResponse::Value { value, .. } => value,
+        _ => unreachable!()
+    }
+}
+
+fn list(evaluator: &mut Evaluator, input: &str) -> Vec<f64> {
+    match evaluator.evaluate_response(input, -10.0, 10.0) {
+        Some(EvaluatorResponse::List { values, .. }) => values,
+        other => panic!("expected a list, got {:?}", other)
+    }
+}
 
 /*
 #[test]
@@ -56,3 +89,1353 @@ fn intercepts() {
     println!("{:?}", find_roots(f, 0.0, 20.0, 0.01, 0.00001));
 }
 */
+
+#[test]
+fn limits() {
+    use wasm_graph_calc::calculus::*;
+
+    let sinc = limit(|x: f64| x.sin() / x, 0.0);
+    assert_eq!(sinc.limit, LimitEstimate::Value { value: 1.0 });
+    assert!(sinc.sides_agree);
+
+    let reciprocal = limit(|x: f64| 1.0 / x, 0.0);
+    assert_eq!(reciprocal.left, LimitEstimate::NegativeInfinity);
+    assert_eq!(reciprocal.right, LimitEstimate::PositiveInfinity);
+    assert_eq!(reciprocal.limit, LimitEstimate::DoesNotExist);
+    assert!(!reciprocal.sides_agree);
+
+    let oscillating = limit(|x: f64| (1.0 / x).sin(), 0.0);
+    assert_eq!(oscillating.right, LimitEstimate::DoesNotExist);
+
+    // slowly converging, each step is only a little smaller than the last
+    match limit(|x: f64| x.powf(0.1), 0.0).right {
+        LimitEstimate::Value { value } => assert!(value.abs() < 1e-6),
+        other => panic!("expected a value, got {:?}", other)
+    }
+
+    assert_eq!(limit(|x: f64| x.ln(), 0.0).right, LimitEstimate::NegativeInfinity);
+
+    // small answers are judged by their own size
+    let small_jump = limit(|x: f64| 1e-7 * x.signum(), 0.0);
+    assert!(!small_jump.sides_agree);
+    assert_eq!(small_jump.limit, LimitEstimate::DoesNotExist);
+
+    match limit(|x: f64| x + 5e-10, 0.0).limit {
+        LimitEstimate::Value { value } => assert!((value - 5e-10).abs() < 1e-15),
+        other => panic!("expected a value, got {:?}", other)
+    }
+
+    let tokens = lex("lim(sin(x)/x, x, 0)").unwrap();
+    let tree = ParseTree::new(&tokens, &HashMap::new()).unwrap();
+    assert_eq!(tree.evaluate(None, &HashMap::new(), AngleMode::Radians).unwrap(), 1.0);
+
+    let tokens = lex("lim(sqrt(x), x, 0, 1)").unwrap();
+    let tree = ParseTree::new(&tokens, &HashMap::new()).unwrap();
+    assert!(tree.evaluate(None, &HashMap::new(), AngleMode::Radians).unwrap().abs() < 1e-6);
+
+    let tokens = lex("lim(sqrt(x), x, 0)").unwrap();
+    let tree = ParseTree::new(&tokens, &HashMap::new()).unwrap();
+    assert!(tree.evaluate(None, &HashMap::new(), AngleMode::Radians).is_err());
+}
+
+#[test]
+fn implicit_multiplication() {
+    let vars: HashMap<char, f64> = HashMap::from([('t', 2.0)]);
+    let graphs: HashMap<char, ParseTree> = HashMap::new();
+
+    let evaluate = |s: &str| ParseTree::new(&lex(s).unwrap(), &graphs)
+        .unwrap()
+        .evaluate(Some(3.0), &vars, AngleMode::Radians)
+        .unwrap();
+
+    assert_eq!(evaluate("3t"), 6.0);
+    assert_eq!(evaluate("2x + 1"), 7.0);
+    assert_eq!(evaluate("(x+1)(x-1)"), 8.0);
+    assert_eq!(evaluate("2(t+1)/3"), 2.0);
+}
+
+#[test]
+fn parametric_curves() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_parametric_range(0.0, 1.0, 11);
+
+    let response = evaluator.evaluate_response("p(t) = (cos(3t), sin(2t))", -10.0, 10.0);
+
+    let segments = match response {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 1);
+
+    let points = &segments[0];
+    let last = points[points.len() - 1];
+
+    assert!(points.len() >= 11);
+    assert_eq!(points[0], [1.0, 0.0]);
+    assert!((last[0] - 3f64.cos()).abs() < 1e-12);
+    assert!((last[1] - 2f64.sin()).abs() < 1e-12);
+
+    assert!(evaluator.evaluate_response("q(t) = (t, t, t)", -10.0, 10.0).is_none());
+}
+
+#[test]
+fn polar_curves() {
+    let mut evaluator = Evaluator::new();
+
+    // cardioid, area 3π/2
+    let response = evaluator.evaluate_response("r(θ) = 1 + cos(θ)", -10.0, 10.0);
+    assert!(matches!(response, Some(EvaluatorResponse::Graph { ref segments }) if segments.len() == 1));
+
+    let area = evaluator.polar_area('r', 0.0, 2.0 * std::f64::consts::PI).unwrap();
+    assert!((area - 1.5 * std::f64::consts::PI).abs() < 1e-6);
+
+    // the line x = 1 runs off to infinity at θ = π/2 and 3π/2
+    evaluator.set_polar_range(0.0, std::f64::consts::PI);
+    let segments = match evaluator.evaluate_response("r = 1/cos(θ)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 2);
+    assert!(segments.iter().flatten().all(|p| (p[0] - 1.0).abs() < 1e-9));
+}
+
+#[test]
+fn graphs_break_where_undefined() {
+    let mut evaluator = Evaluator::new();
+
+    let segments = match evaluator.evaluate_response("f(x) = sqrt(x)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0][0][0] < 1e-3);
+
+    // tan has asymptotes at ±π/2 and ±3π/2 within the range
+    let segments = match evaluator.evaluate_response("g(x) = tan(x)", -6.0, 6.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 5);
+}
+
+#[test]
+fn powers() {
+    let graphs: HashMap<char, ParseTree> = HashMap::new();
+    let vars: HashMap<char, f64> = HashMap::new();
+
+    let evaluate = |s: &str| ParseTree::new(&lex(s).unwrap(), &graphs)
+        .unwrap()
+        .evaluate(Some(3.0), &vars, AngleMode::Radians)
+        .unwrap();
+
+    assert_eq!(evaluate("x^2"), 9.0);
+    assert_eq!(evaluate("2x^2 + 1"), 19.0);
+    assert_eq!(evaluate("2^3^2"), 512.0);
+    assert_eq!(evaluate("(x+1)^2/4"), 4.0);
+    assert!((evaluate("sin(x)^2 + cos(x)^2") - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn implicit_curves() {
+    let mut evaluator = Evaluator::new();
+
+    let segments = match evaluator.evaluate_response("x^2 + y^2 = 25", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    // one closed loop around the circle
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].first(), segments[0].last());
+    assert!(segments[0].iter().all(|p| ((p[0] * p[0] + p[1] * p[1]).sqrt() - 5.0).abs() < 1e-2));
+
+    // no line should be drawn down the asymptote
+    let segments = match evaluator.evaluate_response("y = 1/x", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 2);
+    assert!(segments.iter().flatten().all(|p| (p[0] * p[1] - 1.0).abs() < 1e-2));
+
+    // single letters other than x and y are still variables
+    assert!(matches!(
+        evaluator.evaluate_response("a = 2", -10.0, 10.0),
+        Some(EvaluatorResponse::Value { .. })
+    ));
+
+    // undefined names are an error rather than an empty graph
+    assert!(evaluator.evaluate_response("x^2 + y^2 = k", -10.0, 10.0).is_none());
+
+    let results = evaluator.update_line(1, "x^2 + y^2 = k");
+    assert!(results[0].response.is_none() && results[0].error.is_some());
+
+    let results = evaluator.update_line(2, "k = 25");
+    assert!(results.iter().any(|r| r.id == 1 && matches!(
+        &r.response,
+        Some(EvaluatorResponse::Graph { segments }) if !segments.is_empty()
+    )));
+}
+
+#[test]
+fn comparisons() {
+    let graphs: HashMap<char, ParseTree> = HashMap::new();
+    let vars: HashMap<char, f64> = HashMap::new();
+
+    let evaluate = |s: &str| ParseTree::new(&lex(s).unwrap(), &graphs)
+        .unwrap()
+        .evaluate(Some(2.0), &vars, AngleMode::Radians)
+        .unwrap();
+
+    assert_eq!(evaluate("x > 1"), 1.0);
+    assert_eq!(evaluate("x^2 <= 3"), 0.0);
+    assert_eq!(evaluate("0 <= x <= 3"), 1.0);
+    assert_eq!(evaluate("0 <= x < 2"), 0.0);
+}
+
+#[test]
+fn inequality_regions() {
+    let mut evaluator = Evaluator::new();
+
+    let (raster, columns, boundaries) = match evaluator.evaluate_response("x^2 + y^2 <= 9", -10.0, 10.0) {
+        Some(EvaluatorResponse::Region { raster, columns, boundaries, .. }) => (raster, columns, boundaries),
+        _ => panic!("expected a region")
+    };
+
+    // the middle of the viewport is inside, the corner is not
+    assert!(raster[50 * columns + 50]);
+    assert!(!raster[0]);
+
+    assert_eq!(boundaries.len(), 1);
+    assert!(!boundaries[0].strict);
+
+    // a feasible region from linear programming, a triangle with one strict edge
+    let boundaries = match evaluator.evaluate_response("x >= 0, y >= 0, x + y < 5", -10.0, 10.0) {
+        Some(EvaluatorResponse::Region { boundaries, .. }) => boundaries,
+        _ => panic!("expected a region")
+    };
+
+    assert_eq!(boundaries.len(), 3);
+    assert_eq!(boundaries.iter().filter(|b| b.strict).count(), 1);
+
+    // only the edges of the triangle are kept
+    for point in boundaries.iter().flat_map(|b| b.segments.iter().flatten()) {
+        assert!(point[0] >= -1e-6 && point[1] >= -1e-6 && point[0] + point[1] <= 5.0 + 1e-6);
+    }
+
+    // without x or y a comparison is just a value
+    assert!(matches!(
+        evaluator.evaluate_response("3 > 2", -10.0, 10.0),
+        Some(EvaluatorResponse::Value { value, .. }) if value == 1.0
+    ));
+
+    // undefined names are an error rather than an empty region
+    assert!(evaluator.evaluate_response("x < k", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("y < kx", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("x < 2 and", -10.0, 10.0).is_none());
+
+    let results = evaluator.update_line(1, "y < kx");
+    assert!(results[0].response.is_none() && results[0].error.is_some());
+
+    let results = evaluator.update_line(2, "k = 2");
+    assert!(results.iter().any(|r| r.id == 1 && matches!(r.response, Some(EvaluatorResponse::Region { .. }))));
+}
+
+#[test]
+fn negation() {
+    let graphs: HashMap<char, ParseTree> = HashMap::new();
+    let vars: HashMap<char, f64> = HashMap::new();
+
+    let evaluate = |s: &str| ParseTree::new(&lex(s).unwrap(), &graphs)
+        .unwrap()
+        .evaluate(Some(3.0), &vars, AngleMode::Radians)
+        .unwrap();
+
+    assert_eq!(evaluate("-x"), -3.0);
+    assert_eq!(evaluate("-x^2"), -9.0);
+    assert_eq!(evaluate("2*-3"), -6.0);
+    assert_eq!(evaluate("x - -1"), 4.0);
+    assert_eq!(evaluate("2^-1"), 0.5);
+    assert_eq!(evaluate("2(-x+1)"), -4.0);
+    assert_eq!(evaluate("(x)-1"), 2.0);
+    assert_eq!(evaluate("-(x+1)*2"), -8.0);
+}
+
+#[test]
+fn piecewise() {
+    let graphs: HashMap<char, ParseTree> = HashMap::new();
+    let vars: HashMap<char, f64> = HashMap::new();
+
+    let tree = ParseTree::new(&lex("{x < 0: -x, x >= 0: x^2}").unwrap(), &graphs).unwrap();
+    assert_eq!(tree.evaluate(Some(-2.0), &vars, AngleMode::Radians).unwrap(), 2.0);
+    assert_eq!(tree.evaluate(Some(3.0), &vars, AngleMode::Radians).unwrap(), 9.0);
+
+    // a branch without a condition is the fallback
+    let tree = ParseTree::new(&lex("2{x < 0: 1, 5}").unwrap(), &graphs).unwrap();
+    assert_eq!(tree.evaluate(Some(-1.0), &vars, AngleMode::Radians).unwrap(), 2.0);
+    assert_eq!(tree.evaluate(Some(1.0), &vars, AngleMode::Radians).unwrap(), 10.0);
+
+    // between the branches the graph is undefined, so the line breaks
+    let mut evaluator = Evaluator::new();
+    let segments = match evaluator.evaluate_response("f(x) = {x < -1: -x, x > 1: x^2}", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 2);
+    assert!(segments.iter().flatten().all(|p| p[0].abs() >= 1.0));
+}
+
+#[test]
+fn domain_restrictions() {
+    let mut evaluator = Evaluator::new();
+
+    evaluator.evaluate_response("a = 3", -10.0, 10.0);
+
+    let segments = match evaluator.evaluate_response("f(x) = x^2 {0 <= x <= a}", -10.0, 10.0) {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0].iter().all(|p| p[0] >= 0.0 && p[0] <= 3.0));
+
+    // only inside the domain, so ∫ from -3 to 3 is ∫ from 0 to 3
+    let area = match evaluator.evaluate_response("int(f(x), -3, 3)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, .. }) => value,
+        _ => panic!("expected a value")
+    };
+
+    assert!((area - 9.0).abs() < 1e-2);
+
+    // failing for any other reason is not treated as outside of the domain
+    assert!(evaluator.evaluate_response("int(x + q, 0, 1)", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("int(f(x) + q, -3, 3)", -10.0, 10.0).is_none());
+
+    // y = 1 meets x^2 at -1 and 1, but only 1 is inside the domain
+    evaluator.evaluate_response("g(x) = 1", -10.0, 10.0);
+    let intercepts = evaluator.find_intercepts('f', 'g', -10.0, 10.0).unwrap();
+
+    assert!(!intercepts.is_empty());
+    assert!(intercepts.chunks(2).all(|p| (p[0] - 1.0).abs() < 1e-3));
+
+    // the minimum of cos(x) at π is cut off by the domain, the maximum at 0 is not
+    evaluator.evaluate_response("h(x) = cos(x) {x > -3, x < 3}", -10.0, 10.0);
+    let extrema = evaluator.find_extrema('h', -10.0, 10.0).unwrap();
+
+    assert_eq!(extrema.len(), 2);
+    assert!(extrema[0].abs() < 1e-6);
+    assert!((extrema[1] - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn sliders() {
+    let mut evaluator = Evaluator::new();
+
+    let slider = match evaluator.evaluate_response("a = 2 {0..10 step 0.5}", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, slider, .. }) => {
+            assert_eq!(value, 2.0);
+            slider.unwrap()
+        },
+        _ => panic!("expected a value")
+    };
+
+    assert_eq!(slider, Slider { min: 0.0, max: 10.0, step: Some(0.5) });
+    assert_eq!(slider.frames().len(), 21);
+
+    evaluator.evaluate_response("f(x) = a*x", -10.0, 10.0);
+    evaluator.evaluate_response("g(x) = x^2", -10.0, 10.0);
+
+    // only f depends on a
+    let frames = evaluator.animation_frames('a', &[1.0, 3.0], -1.0, 1.0).unwrap();
+
+    assert_eq!(frames.len(), 2);
+
+    for frame in &frames {
+        assert_eq!(frame.graphs.len(), 1);
+        assert_eq!(frame.graphs[0].name, "f");
+
+        let end = frame.graphs[0].segments[0].last().unwrap();
+        assert!((end[1] - frame.value).abs() < 1e-9);
+    }
+
+    // no values means the whole slider range
+    assert_eq!(evaluator.animation_frames('a', &[], -1.0, 1.0).unwrap().len(), 21);
+
+    // animating doesn't change the variable
+    let value = match evaluator.evaluate_response("a", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, .. }) => value,
+        _ => panic!("expected a value")
+    };
+
+    assert_eq!(value, 2.0);
+
+    // reassigning without a range removes the slider
+    evaluator.evaluate_response("a = 4", -10.0, 10.0);
+    assert!(evaluator.animation_frames('a', &[], -1.0, 1.0).is_none());
+
+    // a step that would give more frames than can be held is rejected
+    assert!(evaluator.evaluate_response("a = 2 {0..1000000000 step 0.000000001}", -10.0, 10.0).is_none());
+    assert!(evaluator.animation_frames('a', &[], -1.0, 1.0).is_none());
+
+    // graphs using a through another variable are animated too, with the variable worked out again each frame
+    let mut evaluator = Evaluator::new();
+
+    evaluator.update_line(0, "a = 1 {0..5}");
+    evaluator.update_line(1, "b = 2a");
+    evaluator.update_line(2, "f(x) = bx");
+    evaluator.update_line(3, "g(x) = ax");
+    evaluator.update_line(4, "h(x) = x^2");
+
+    let frames = evaluator.animation_frames('a', &[3.0], -1.0, 1.0).unwrap();
+    let names: Vec<&str> = frames[0].graphs.iter().map(|graph| graph.name.as_str()).collect();
+    assert_eq!(names, vec!["f", "g"]);
+
+    let end = frames[0].graphs[0].segments[0].last().unwrap();
+    assert!((end[1] - 6.0).abs() < 1e-9);
+}
+
+#[test]
+fn incremental_lines() {
+    let mut evaluator = Evaluator::new();
+
+    // used before it is defined
+    let results = evaluator.update_line(0, "f(x) = a*x");
+    assert_eq!(results[0].error, Some(DefinitionError::Invalid));
+
+    let results = evaluator.update_line(1, "a = 2");
+    assert_eq!(results.iter().map(|r| r.id).collect::<Vec<u32>>(), vec![0, 1]);
+    assert!(results.iter().all(|r| r.error.is_none()));
+
+    // an unrelated line leaves the others alone
+    let results = evaluator.update_line(2, "b = 5");
+    assert_eq!(results.len(), 1);
+
+    // changing a redraws f, which uses it
+    let results = evaluator.update_line(1, "a = 3");
+    assert_eq!(results.len(), 2);
+
+    let segments = match &results[0].response {
+        Some(EvaluatorResponse::Graph { segments }) => segments,
+        _ => panic!("expected a graph")
+    };
+    assert!((segments[0].last().unwrap()[1] - 30.0).abs() < 1e-9);
+
+    // a and b now depend on each other
+    let results = evaluator.update_line(2, "b = a + 1");
+    assert_eq!(results.len(), 1);
+    let results = evaluator.update_line(1, "a = b");
+
+    for result in &results {
+        assert!(result.response.is_none());
+    }
+
+    assert_eq!(results[1].error, Some(DefinitionError::Circular { names: vec!["a".to_string(), "b".to_string()] }));
+    assert_eq!(results[2].error, Some(DefinitionError::Circular { names: vec!["a".to_string(), "b".to_string()] }));
+
+    // removing the loop lets everything evaluate again
+    evaluator.delete_line(2);
+    let results = evaluator.update_line(1, "a = 1");
+    assert!(results.iter().all(|r| r.error.is_none()));
+
+    // the same name twice
+    let results = evaluator.update_line(3, "a = 4");
+    assert!(results.iter().any(|r| r.id == 3 && r.error == Some(DefinitionError::Duplicate { name: "a".to_string() })));
+
+    // x inside a statistic, int or lim isn't a coordinate, so these are variables that get ordered
+    evaluator.update_line(4, "c = m + 1");
+    evaluator.update_line(5, "m = max(L)");
+    let results = evaluator.update_line(6, "L = [1, 2, 3]");
+    assert!(results.iter().any(|r| r.id == 4 && r.error.is_none()));
+
+    let results = evaluator.update_line(7, "n = int(x^2, 0, 3) + lim(sin(x)/x, x, 0)");
+    assert!(results.iter().any(|r| r.id == 7 && r.error.is_none()));
+
+    let results = evaluator.update_line(8, "n = 2");
+    assert!(results.iter().any(|r| r.id == 8 && r.error == Some(DefinitionError::Duplicate { name: "n".to_string() })));
+}
+
+#[test]
+fn sessions() {
+    let mut evaluator = Evaluator::new();
+
+    evaluator.update_viewport(-5.0, 5.0, -2.0, 2.0);
+    evaluator.update_angle_mode(AngleMode::Degrees);
+    evaluator.update_line(0, "a = 30 {0..90 step 15}");
+    evaluator.update_line(1, "b = sin(a)");
+    evaluator.set_style(1, "red".to_string(), 2.0, true, true);
+
+    let json = evaluator.save_session();
+
+    let mut loaded = Evaluator::new();
+    let results = loaded.import_session(&json).unwrap();
+
+    assert_eq!(loaded.session(), evaluator.session());
+    assert_eq!(loaded.line_style(1).colour, "red");
+
+    // sin is in degrees
+    match &results[1].response {
+        Some(EvaluatorResponse::Value { value, .. }) => assert!((value - 0.5).abs() < 1e-9),
+        _ => panic!("expected a value")
+    }
+
+    // switching back redraws every line in radians
+    let results = loaded.update_angle_mode(AngleMode::Radians);
+
+    match &results[1].response {
+        Some(EvaluatorResponse::Value { value, .. }) => assert!((value - 30.0_f64.sin()).abs() < 1e-9),
+        _ => panic!("expected a value")
+    }
+
+    loaded.import_session(&json).unwrap();
+    assert_eq!(loaded.session().viewport, Viewport { min_x: -5.0, max_x: 5.0, min_y: -2.0, max_y: 2.0 });
+
+    // malformed files are rejected with a reason, leaving the session alone
+    assert_eq!(loaded.import_session(r#"{"lines": []}"#).unwrap_err(), SessionError::MissingVersion);
+    assert_eq!(loaded.import_session(r#"{"version": 9}"#).unwrap_err(), SessionError::UnsupportedVersion(9));
+
+    match loaded.import_session("{\"version\": 1,\n\"lines\": 3}") {
+        Err(SessionError::Json(message)) => assert!(message.contains("line 2")),
+        _ => panic!("expected a json error")
+    }
+
+    let backwards = json.replace("\"min_x\": -5.0", "\"min_x\": 50.0");
+    assert_eq!(loaded.import_session(&backwards).unwrap_err(), SessionError::InvalidViewport);
+
+    assert_eq!(loaded.session().lines.len(), 2);
+}
+
+#[test]
+fn tables() {
+    let mut evaluator = Evaluator::new();
+
+    evaluator.evaluate_response("f(x) = sqrt(x)", -10.0, 10.0);
+    evaluator.evaluate_response("g(x) = 1/x", -10.0, 10.0);
+
+    let table = evaluator.table_of_values(&["f", "g(x)", "2x"], -1.0, 1.0, 3).unwrap();
+
+    assert_eq!(table.columns, vec!["f", "g(x)", "2x"]);
+    assert_eq!(table.rows.len(), 3);
+
+    // undefined cells are blank without affecting the rest of the row
+    assert_eq!(table.rows[0].values, vec![None, Some(-1.0), Some(-2.0)]);
+    assert_eq!(table.rows[1].values, vec![Some(0.0), None, Some(0.0)]);
+    assert_eq!(table.rows[2].values, vec![Some(1.0), Some(1.0), Some(2.0)]);
+
+    // h isn't defined, so every cell is blank
+    let table = evaluator.table_of_values(&["h"], 0.0, 1.0, 3).unwrap();
+    assert!(table.rows.iter().all(|row| row.values == vec![None]));
+
+    assert!(evaluator.table_of_values(&["(("], 0.0, 1.0, 3).is_none());
+
+    // expressions can use list variables like graphs can
+    evaluator.evaluate_response("L = [1, 2, 3]", -10.0, 10.0);
+    let table = evaluator.table_of_values(&["x*L[2]", "x + mean(L)"], 0.0, 1.0, 3).unwrap();
+
+    assert_eq!(table.rows[2].values, vec![Some(4.0), Some(4.0)]);
+}
+
+#[test]
+fn csv_export() {
+    let mut evaluator = Evaluator::new();
+
+    evaluator.evaluate_response("f(x) = 1/x", -10.0, 10.0);
+    evaluator.evaluate_response("g(x) = x", -10.0, 10.0);
+
+    let table = evaluator.table_of_values(&["f", "g"], -1.0, 1.0, 3).unwrap();
+    let options = CsvOptions { delimiter: ';', precision: Some(2), header: true };
+
+    assert_eq!(table_to_csv(&table, &options), "x;f;g\r\n-1.00;-1.00;-1.00\r\n0.00;;0.00\r\n1.00;1.00;1.00\r\n");
+
+    // fields holding the delimiter or quotes are quoted
+    let header = ["a,b".to_string(), "say \"hi\"".to_string()];
+    assert_eq!(write_csv(&header, &[vec![Some(0.5), None]], &CsvOptions::default()), "\"a,b\",\"say \"\"hi\"\"\"\r\n0.5,\r\n");
+
+    // quotes and line breaks can't separate fields
+    assert_eq!(CsvOptions::new('"', None, true), None);
+    assert_eq!(CsvOptions::new('\n', None, true), None);
+    assert_eq!(CsvOptions::new('\r', None, true), None);
+    assert_eq!(CsvOptions::new('\t', None, true), Some(CsvOptions { delimiter: '\t', precision: None, header: true }));
+
+    evaluator.set_csv_options(',', Some(3), false).unwrap();
+
+    let intercepts = evaluator.intercepts_csv('f', 'g', 0.5, 2.0).unwrap();
+    assert_eq!(intercepts, "1.000,1.000\r\n");
+
+    // 1/x breaks at 0, so the samples come in two numbered segments
+    let samples = evaluator.graph_csv('f', -1.0, 1.0).unwrap();
+    assert!(samples.starts_with("0,-1.000,-1.000\r\n"));
+    assert!(samples.ends_with("1,1.000,1.000\r\n"));
+}
+
+#[test]
+fn svg_export() {
+    let mut evaluator = Evaluator::new();
+
+    evaluator.update_viewport(-5.0, 5.0, -5.0, 5.0);
+    evaluator.update_line(0, "f(x) = x^2 - 1");
+    evaluator.update_line(1, "g(x) = 0");
+    evaluator.set_style(0, "blue".to_string(), 2.0, false, true);
+
+    let svg = evaluator.render_plot(&PlotSettings::default(), Some(('f', 'g')));
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    // both graphs, in their own styles, with a legend entry each
+    assert!(svg.contains("stroke=\"blue\" stroke-width=\"2\""));
+    assert!(svg.contains(">f(x)</text>"));
+    assert!(svg.contains(">g(x)</text>"));
+
+    // x^2 - 1 crosses 0 at -1 and 1
+    assert_eq!(svg.matches("<circle").count(), 2);
+
+    // ticks every unit across a range of 10, with 0 left to the axes
+    assert!(svg.contains(">-4</text>"));
+    assert!(svg.contains(">5</text>"));
+    assert!(!svg.contains(">0</text>"));
+
+    let bare = evaluator.render_plot(&PlotSettings { grid: false, legend: false, ..PlotSettings::default() }, None);
+    assert!(!bare.contains("<circle"));
+    assert!(!bare.contains(">f(x)</text>"));
+    assert!(!bare.contains("#e0e0e0"));
+
+    // a spacing that is zero or would give billions of ticks falls back to the automatic one
+    for tick_spacing in [0.0, -1.0, f64::NAN, 1e-12] {
+        let svg = evaluator.render_plot(&PlotSettings { tick_spacing: Some(tick_spacing), ..PlotSettings::default() }, None);
+        assert!(svg.contains(">-4</text>"));
+    }
+
+    // implicit curves, shaded inequalities with dashed strict edges and scatter plots are all drawn
+    evaluator.update_line(2, "x^2 + y^2 = 4");
+    evaluator.update_line(3, "y > x + 1");
+    evaluator.update_line(4, "P = [(1, 2), (3, 4), (-2, 0)]");
+    evaluator.set_style(3, "green".to_string(), 1.0, false, true);
+
+    let svg = evaluator.render_plot(&PlotSettings::default(), None);
+
+    assert!(svg.contains(">x^2 + y^2 = 4</text>"));
+    assert!(svg.contains("fill=\"green\" fill-opacity="));
+    assert!(svg.contains("stroke=\"green\" stroke-width=\"1\" stroke-dasharray"));
+    assert_eq!(svg.matches("<circle").count(), 3);
+}
+
+#[test]
+fn lists() {
+    let mut evaluator = Evaluator::new();
+
+    assert_eq!(list(&mut evaluator, "L = [1, 2, 3.5, 7]"), vec![1.0, 2.0, 3.5, 7.0]);
+
+    // counting from 1
+    assert_eq!(value(&mut evaluator, "L[2]"), 2.0);
+    assert_eq!(value(&mut evaluator, "L[1] + L[4]"), 8.0);
+    assert!(evaluator.evaluate_response("L[5]", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("L[0]", -10.0, 10.0).is_none());
+
+    // arithmetic is element by element
+    assert_eq!(list(&mut evaluator, "2L + 1"), vec![3.0, 5.0, 8.0, 15.0]);
+    assert_eq!(list(&mut evaluator, "L - [1, 1, 1, 1]"), vec![0.0, 1.0, 2.5, 6.0]);
+    assert!(evaluator.evaluate_response("L + [1, 2]", -10.0, 10.0).is_none());
+
+    // and so are graphs and functions
+    evaluator.evaluate_response("f(x) = x^2", -10.0, 10.0);
+    assert_eq!(list(&mut evaluator, "f(L)"), vec![1.0, 4.0, 12.25, 49.0]);
+    assert_eq!(list(&mut evaluator, "sqrt([4, 9])"), vec![2.0, 3.0]);
+    assert_eq!(value(&mut evaluator, "f(3)"), 9.0);
+
+    // a name that isn't a graph before a bracket still multiplies
+    evaluator.evaluate_response("a = 2", -10.0, 10.0);
+    assert_eq!(value(&mut evaluator, "a(3 + 1)"), 8.0);
+    assert_eq!(value(&mut evaluator, "2^a(1)"), 4.0);
+
+    // pairs of lists and lists of points are scattered
+    evaluator.evaluate_response("M = f(L)", -10.0, 10.0);
+
+    match evaluator.evaluate_response("(L, M)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Scatter { points, .. }) => assert_eq!(points[2], [3.5, 12.25]),
+        other => panic!("expected a scatter, got {:?}", other)
+    }
+
+    // indexing and statistics of a list give numbers, so graphs using them can be drawn
+    let graph = evaluator.evaluate_response("g(x) = x + L[2] + mean(L) x", -10.0, 10.0);
+    assert!(matches!(graph, Some(EvaluatorResponse::Graph { .. })));
+    assert_eq!(value(&mut evaluator, "g(1)"), 1.0 + 2.0 + 13.5 / 4.0);
+
+    match evaluator.evaluate_response("P = [(1, 2), (3, a)]", -10.0, 10.0) {
+        Some(EvaluatorResponse::Scatter { points, var_name }) => {
+            assert_eq!(points, vec![[1.0, 2.0], [3.0, 2.0]]);
+            assert_eq!(var_name, Some("P".to_string()));
+        },
+        other => panic!("expected a scatter, got {:?}", other)
+    }
+}
+
+#[test]
+fn statistics() {
+    let mut evaluator = Evaluator::new();
+
+    let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+
+    assert_eq!(mean(&data), Ok(5.0));
+    assert_eq!(median(&data), Ok(4.5));
+    assert_eq!(mode(&data), Ok(4.0));
+    assert_eq!(variance(&data, Estimate::Population), Ok(4.0));
+    assert_eq!(standard_deviation(&data, Estimate::Population), Ok(2.0));
+    assert!((variance(&data, Estimate::Sample).unwrap() - 32.0 / 7.0).abs() < 1e-12);
+    assert_eq!(quartiles(&data), Ok((4.0, 4.5, 6.0)));
+    assert_eq!(quartiles(&[1.0, 2.0, 3.0, 4.0, 5.0]), Ok((1.5, 3.0, 4.5)));
+
+    // empty and degenerate lists
+    assert_eq!(mean(&[]), Err(StatsError::Empty));
+    assert_eq!(variance(&[3.0], Estimate::Sample), Err(StatsError::TooFewValues));
+    assert_eq!(variance(&[3.0], Estimate::Population), Ok(0.0));
+    assert_eq!(mode(&[1.0, 2.0, 3.0]), Err(StatsError::NoMode));
+
+    // used like any other function
+    evaluator.evaluate_response("L = [2, 4, 4, 4, 5, 5, 7, 9]", -10.0, 10.0);
+    assert_eq!(value(&mut evaluator, "mean(L)"), 5.0);
+    assert_eq!(value(&mut evaluator, "2stdevp(L) + 1"), 5.0);
+    assert_eq!(value(&mut evaluator, "sum(L) + prod([1, 2, 3])"), 46.0);
+    assert_eq!(value(&mut evaluator, "product(L) / prod(L)"), 1.0);
+    assert_eq!(value(&mut evaluator, "product(2, [3, 4])"), 24.0);
+    assert_eq!(value(&mut evaluator, "max(3, 8, 1)"), 8.0);
+    assert_eq!(value(&mut evaluator, "count(L)"), 8.0);
+    assert!(evaluator.evaluate_response("mean([])", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("var([1])", -10.0, 10.0).is_none());
+
+    match evaluator.evaluate_response("summary(L)", -10.0, 10.0) {
+        Some(EvaluatorResponse::List { values, .. }) => assert_eq!(values, vec![2.0, 4.0, 4.5, 6.0, 9.0]),
+        other => panic!("expected a list, got {:?}", other)
+    }
+
+    // a statistic can define a variable that graphs then use
+    evaluator.evaluate_response("m = median(L)", -10.0, 10.0);
+    assert_eq!(value(&mut evaluator, "m"), 4.5);
+}
+
+#[test]
+fn regression() {
+    let mut evaluator = Evaluator::new();
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+
+    // y = 2x + 1 with some noise
+    evaluator.evaluate_response("L = [0, 1, 2, 3, 4]", -10.0, 10.0);
+    evaluator.evaluate_response("M = [1.1, 2.9, 5.2, 6.8, 9.0]", -10.0, 10.0);
+
+    let linear = evaluator.fit_regression('f', Model::Polynomial { degree: 1 }, "(L, M)").unwrap();
+    assert!(close(linear.coefficients[0], 1.06) && close(linear.coefficients[1], 1.97));
+    assert!(linear.r_squared > 0.99 && linear.r_squared < 1.0);
+    assert_eq!(linear.residuals.len(), 5);
+    assert!(close(linear.residuals.iter().sum::<f64>(), 0.0));
+
+    // the fit is a graph like any other
+    evaluator.evaluate_response("g(x) = 0", -10.0, 10.0);
+    let intercepts = evaluator.find_intercepts('f', 'g', -10.0, 10.0).unwrap();
+    assert!((intercepts[0] + 1.06 / 1.97).abs() < 1e-3);
+
+    match evaluator.evaluate_response("int(f(x), 0, 1)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, .. }) => assert!((value - 2.045).abs() < 1e-3),
+        other => panic!("expected a value, got {:?}", other)
+    }
+
+    // exact data comes back exactly
+    let quadratic = fit(Model::Polynomial { degree: 2 }, &[[-1.0, 6.0], [0.0, 1.0], [1.0, 0.0], [2.0, 3.0]]).unwrap();
+    assert!(quadratic.coefficients.iter().zip([1.0, -3.0, 2.0]).all(|(a, b)| close(*a, b)));
+    assert!(close(quadratic.r_squared, 1.0));
+
+    let exact = |model: Model, f: fn(f64) -> f64| {
+        let points: Vec<[f64; 2]> = (1..=8).map(|x| [x as f64, f(x as f64)]).collect();
+        fit(model, &points).unwrap().coefficients
+    };
+
+    let exponential = exact(Model::Exponential, |x| 3.0 * (0.5 * x).exp());
+    assert!(close(exponential[0], 3.0) && close(exponential[1], 0.5));
+
+    let logarithmic = exact(Model::Logarithmic, |x| 2.0 - 4.0 * x.ln());
+    assert!(close(logarithmic[0], 2.0) && close(logarithmic[1], -4.0));
+
+    let power = exact(Model::Power, |x| 1.5 * x.powf(2.5));
+    assert!(close(power[0], 1.5) && close(power[1], 2.5));
+
+    let logistic = exact(Model::Logistic, |x| 10.0 / (1.0 + 50.0 * (-1.2 * x).exp()));
+    assert!((logistic[0] - 50.0).abs() < 1e-3 && (logistic[1] - 1.2).abs() < 1e-4 && (logistic[2] - 10.0).abs() < 1e-4);
+
+    // data the model can't describe
+    assert_eq!(fit(Model::Logarithmic, &[[-1.0, 1.0], [2.0, 3.0]]), Err(FitError::InvalidData));
+    assert_eq!(fit(Model::Polynomial { degree: 3 }, &[[1.0, 1.0], [2.0, 3.0]]), Err(FitError::TooFewPoints));
+    assert_eq!(fit(Model::Polynomial { degree: 1 }, &[[1.0, 1.0], [1.0, 3.0]]), Err(FitError::Singular));
+    assert!(evaluator.fit_regression('h', Model::Exponential, "(L, [1, 2])").is_err());
+
+    assert_eq!(Model::parse("quadratic", None), Some(Model::Polynomial { degree: 2 }));
+    assert_eq!(Model::parse("polynomial", Some(4)), Some(Model::Polynomial { degree: 4 }));
+    assert_eq!(Model::parse("sideways", None), None);
+}
+
+#[test]
+fn model_fitting() {
+    let mut evaluator = Evaluator::new();
+
+    let xs: Vec<String> = (0..30).map(|i| (i as f64 * 0.25).to_string()).collect();
+
+    evaluator.update_line(1, &format!("L = [{}]", xs.join(", ")));
+    evaluator.update_line(2, "M = 2sin(1.5L + 0.3)");
+
+    // initial guesses, and a graph using them, a whole number guess is kept exactly until it is fitted
+    evaluator.update_line(3, "a = 3");
+    evaluator.update_line(4, "b = 1.4");
+    evaluator.update_line(5, "g(x) = a sin(bx + c)");
+    evaluator.update_line(6, "d = 2a");
+
+    let results = evaluator.update_line(7, "M ~ a sin(bL + c)");
+
+    match &evaluator.line_result(7).unwrap().response {
+        Some(EvaluatorResponse::Fit { names, parameters, r_squared, residuals, .. }) => {
+            assert_eq!(names, &vec!['a', 'b', 'c']);
+            assert!(parameters.iter().zip([2.0, 1.5, 0.3]).all(|(p, expected)| (p - expected).abs() < 1e-6));
+            assert!((r_squared - 1.0).abs() < 1e-9);
+            assert_eq!(residuals.len(), 30);
+        },
+        other => panic!("expected a fit, got {:?}", other)
+    }
+
+    // the fitted values replace the guesses, and lines using them are redrawn
+    assert!(results.iter().any(|result| result.id == 5));
+    assert!(results.iter().all(|result| result.id != 3));
+
+    assert!((value(&mut evaluator, "g(0)") - 2.0 * 0.3_f64.sin()).abs() < 1e-6);
+    assert!((value(&mut evaluator, "d") - 4.0).abs() < 1e-6);
+    assert!((value(&mut evaluator, "a") - 2.0).abs() < 1e-6);
+
+    // data that isn't a list, and models without anything to fit
+    assert!(evaluator.evaluate_response("3 ~ a L", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("M ~ 2L", -10.0, 10.0).is_none());
+
+    match evaluator.fit_model("M ~ k L + q") {
+        Ok((names, fit)) => {
+            assert_eq!(names, vec!['k', 'q']);
+            assert!(fit.r_squared < 1.0);
+        },
+        Err(e) => panic!("expected a fit, got {:?}", e)
+    }
+}
+
+#[test]
+fn complex_numbers() {
+    let mut evaluator = Evaluator::new();
+
+    let complex = |evaluator: &mut Evaluator, input: &str| match value_response(evaluator, input) {
+        EvaluatorResponse::Value { value, imaginary, .. } => Complex::new(value, imaginary.unwrap_or(0.0)),
+        _ => unreachable!()
+    };
+
+    let close = |a: Complex, re: f64, im: f64| (a.re - re).abs() < 1e-12 && (a.im - im).abs() < 1e-12;
+
+    // off by default
+    match evaluator.evaluate_response("sqrt(-1)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, imaginary, .. }) => assert!(value.is_nan() && imaginary.is_none()),
+        other => panic!("expected a value, got {:?}", other)
+    }
+
+    evaluator.update_complex_mode(true);
+
+    assert!(close(complex(&mut evaluator, "sqrt(-1)"), 0.0, 1.0));
+    assert!(close(complex(&mut evaluator, "sqrt(-4i)"), 2.0_f64.sqrt(), -(2.0_f64.sqrt())));
+    assert!(close(complex(&mut evaluator, "ln(-2)"), 2.0_f64.ln(), std::f64::consts::PI));
+    assert!(close(complex(&mut evaluator, "i^2"), -1.0, 0.0));
+    assert!(close(complex(&mut evaluator, "(1 + 2i)(3 - i)"), 5.0, 5.0));
+    assert!(close(complex(&mut evaluator, "(1 + i) / (1 - i)"), 0.0, 1.0));
+    assert!(close(complex(&mut evaluator, "(-8)^(1/3)"), 1.0, 3.0_f64.sqrt()));
+    assert!(close(complex(&mut evaluator, "sin(i)"), 0.0, 1.0_f64.sinh()));
+
+    // real results are plain numbers
+    match evaluator.evaluate_response("abs(3 + 4i)", -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { value, imaginary, .. }) => assert_eq!((value, imaginary), (5.0, None)),
+        other => panic!("expected a value, got {:?}", other)
+    }
+
+    // complex variables
+    evaluator.evaluate_response("z = 3 - 4i", -10.0, 10.0);
+    assert!(close(complex(&mut evaluator, "conj(z)"), 3.0, 4.0));
+    assert!(close(complex(&mut evaluator, "re(z) + im(z)"), -1.0, 0.0));
+    assert!(close(complex(&mut evaluator, "arg(i)"), std::f64::consts::FRAC_PI_2, 0.0));
+    assert!(close(complex(&mut evaluator, "z z"), -7.0, -24.0));
+
+    // complex numbers can't be compared
+    assert!(evaluator.evaluate_response("z < 2", -10.0, 10.0).is_none());
+
+    // graphs stay real
+    evaluator.evaluate_response("f(x) = sqrt(x)", -10.0, 10.0);
+    assert!(complex(&mut evaluator, "f(-1)").re.is_nan());
+
+    // i can't be given a value while it is the imaginary unit
+    assert!(evaluator.evaluate_response("i = 5", -10.0, 10.0).is_none());
+    assert!(close(complex(&mut evaluator, "i^2"), -1.0, 0.0));
+
+    let mut evaluator = Evaluator::new();
+    evaluator.update_line(1, "i = 5");
+    evaluator.update_line(2, "b = i + 1");
+
+    assert!(matches!(
+        &evaluator.line_result(2).unwrap().response,
+        Some(EvaluatorResponse::Value { value, imaginary: None, .. }) if *value == 6.0
+    ));
+
+    evaluator.update_complex_mode(true);
+
+    assert_eq!(evaluator.line_result(1).unwrap().error, Some(DefinitionError::ImaginaryUnit));
+    assert!(matches!(
+        &evaluator.line_result(2).unwrap().response,
+        Some(EvaluatorResponse::Value { value, imaginary: Some(im), .. }) if *value == 1.0 && *im == 1.0
+    ));
+}
+
+#[test]
+fn exact_fractions() {
+    let mut evaluator = Evaluator::new();
+
+    let answer = |evaluator: &mut Evaluator, input: &str| match value_response(evaluator, input) {
+        EvaluatorResponse::Value { value, fraction, mixed, .. } => (value, fraction, mixed),
+        _ => unreachable!()
+    };
+
+    assert_eq!(answer(&mut evaluator, "1/3 + 1/6"), (0.5, Some("1/2".to_string()), None));
+    assert_eq!(answer(&mut evaluator, "-7/4"), (-1.75, Some("-7/4".to_string()), Some("-1 3/4".to_string())));
+    assert_eq!(answer(&mut evaluator, "(2 - 8) / 3 * 2"), (-4.0, None, None));
+    assert_eq!(answer(&mut evaluator, "0.1 + 0.2").1, None);
+
+    // -2^127 / -1 doesn't fit, so the answer falls back to f64
+    assert_eq!(answer(&mut evaluator, "-633825300114114700748351602688*268435456/(-1)"), (2f64.powi(127), None, None));
+
+    // exact variables stay exact
+    evaluator.evaluate_response("a = 1/3", -10.0, 10.0);
+    evaluator.evaluate_response("b = 2a + 1/10", -10.0, 10.0);
+    assert_eq!(answer(&mut evaluator, "b"), (23.0 / 30.0, Some("23/30".to_string()), None));
+    assert_eq!(answer(&mut evaluator, "3a"), (1.0, None, None));
+
+    // anything else falls back to f64
+    let (value, fraction, _) = answer(&mut evaluator, "sqrt(2) / 2");
+    assert!((value - 0.5_f64.sqrt()).abs() < 1e-12 && fraction.is_none());
+    assert!(answer(&mut evaluator, "2^(1/2)").1.is_none());
+    assert!(answer(&mut evaluator, "1/0").0.is_infinite());
+
+    // the exact value goes when the variable isn't exact anymore
+    evaluator.evaluate_response("a = sqrt(2)", -10.0, 10.0);
+    assert_eq!(answer(&mut evaluator, "a + 1/2").1, None);
+}
+
+#[cfg(feature = "precision")]
+#[test]
+fn arbitrary_precision() {
+    use wasm_graph_calc::precise::BigFloat;
+
+    let mut evaluator = Evaluator::new();
+
+    let precise = |evaluator: &mut Evaluator, input: &str| match value_response(evaluator, input) {
+        EvaluatorResponse::Value { precise, .. } => precise,
+        _ => unreachable!()
+    };
+
+    // off until a precision is chosen
+    assert_eq!(precise(&mut evaluator, "sqrt(2)"), None);
+
+    evaluator.update_precision(Some(50));
+
+    assert_eq!(precise(&mut evaluator, "sqrt(2)").unwrap(), "1.4142135623730950488016887242096980785696718753769");
+    assert_eq!(precise(&mut evaluator, "ln(3)").unwrap(), "1.0986122886681096913952452369225257046474905578227");
+    assert_eq!(precise(&mut evaluator, "sin(1)").unwrap(), "0.84147098480789650665250232163029899962256306079837");
+    assert_eq!(precise(&mut evaluator, "2^(1/2) - sqrt(2)").unwrap(), "0");
+    assert_eq!(precise(&mut evaluator, "0.1 + 0.2").unwrap(), "0.3");
+    assert_eq!(precise(&mut evaluator, "1/3").unwrap(), "0.33333333333333333333333333333333333333333333333333");
+
+    // variables keep their digits
+    evaluator.evaluate_response("a = sqrt(2)", -10.0, 10.0);
+    assert_eq!(precise(&mut evaluator, "a + 1").unwrap(), "2.4142135623730950488016887242096980785696718753769");
+
+    // exact rounding of the basic operations, ties go to even
+    let third = BigFloat::from_integer(1).div(&BigFloat::from_integer(3), 5).unwrap();
+    assert_eq!(third.to_string(), "0.33333");
+    assert_eq!(BigFloat::parse("2.5").unwrap().add(&BigFloat::zero(), 1).to_string(), "2");
+    assert_eq!(BigFloat::parse("3.5").unwrap().add(&BigFloat::zero(), 1).to_string(), "4");
+    assert_eq!(BigFloat::from_integer(2).div(&BigFloat::from_integer(3), 3).unwrap().to_string(), "0.667");
+    assert_eq!(BigFloat::pi(30).to_string(), "3.14159265358979323846264338328");
+    assert_eq!(BigFloat::from_integer(1).exp(30).unwrap().to_string(), "2.71828182845904523536028747135");
+    assert_eq!(BigFloat::parse("1e-40").unwrap().to_string(), "1e-40");
+    assert!(BigFloat::from_integer(-1).sqrt(10).is_none());
+
+    evaluator.update_precision(None);
+    assert_eq!(precise(&mut evaluator, "sqrt(2)"), None);
+}
+
+#[test]
+fn number_literals() {
+    let mut evaluator = Evaluator::new();
+
+    assert_eq!(value(&mut evaluator, "6.02e23"), 6.02e23);
+    assert_eq!(value(&mut evaluator, "1.5E-3"), 1.5e-3);
+    assert_eq!(value(&mut evaluator, "1e3 + 1"), 1001.0);
+    assert_eq!(value(&mut evaluator, "1_000_000"), 1e6);
+    assert_eq!(value(&mut evaluator, "0xFF"), 255.0);
+    assert_eq!(value(&mut evaluator, "0b1010"), 10.0);
+    assert_eq!(value(&mut evaluator, "0o17"), 15.0);
+    assert_eq!(value(&mut evaluator, ".5"), 0.5);
+
+    // e without a digit after it is still a variable
+    evaluator.evaluate_response("e = 2", -10.0, 10.0);
+    assert_eq!(value(&mut evaluator, "3e"), 6.0);
+    assert_eq!(value(&mut evaluator, "3e-1"), 0.3);
+    assert_eq!(value(&mut evaluator, "3e - 1"), 5.0);
+
+    for malformed in ["1.2.3", "1__0", "1_", "0b102", "0xF.8", "2e5.5", "."] {
+        assert!(
+            matches!(lex(malformed), Err(LexError::MalformedNumber { .. })),
+            "{} should be malformed", malformed
+        );
+    }
+
+    assert_eq!(
+        lex("sin(x) + 1.2.3").unwrap_err(),
+        LexError::MalformedNumber { literal: "1.2.3".to_string(), start: 9, end: 14 }
+    );
+
+    // and the line says where
+    evaluator.update_line(1, "a = 2 * 1.2.3");
+    assert_eq!(
+        evaluator.line_result(1).unwrap().error,
+        Some(DefinitionError::MalformedNumber { literal: "1.2.3".to_string(), start: 8, end: 13 })
+    );
+}
+
+#[test]
+fn number_formatting() {
+    assert_eq!(format_number(0.1 + 0.2, NumberFormat::Auto), "0.3");
+    assert_eq!(format_number(2.0 / 3.0, NumberFormat::Auto), "0.6666666667");
+    assert_eq!(format_number(6.02e23, NumberFormat::Auto), "6.02e23");
+    assert_eq!(format_number(-1.5e-7, NumberFormat::Auto), "-1.5e-7");
+    assert_eq!(format_number(f64::NAN, NumberFormat::Auto), "undefined");
+
+    assert_eq!(format_number(1.23456, NumberFormat::Fixed { decimals: 2 }), "1.23");
+    assert_eq!(format_number(2.0, NumberFormat::Fixed { decimals: 3 }), "2.000");
+
+    assert_eq!(format_number(123456.0, NumberFormat::Significant { figures: 3 }), "123000");
+    assert_eq!(format_number(0.0012345, NumberFormat::Significant { figures: 2 }), "0.0012");
+    assert_eq!(format_number(9.99, NumberFormat::Significant { figures: 2 }), "10");
+
+    assert_eq!(format_number(123456.0, NumberFormat::Scientific { figures: 3 }), "1.23e5");
+    assert_eq!(format_number(9.99, NumberFormat::Scientific { figures: 2 }), "1.0e1");
+
+    assert_eq!(format_number(123456.0, NumberFormat::Engineering { figures: 3 }), "123e3");
+    assert_eq!(format_number(0.0045, NumberFormat::Engineering { figures: 2 }), "4.5e-3");
+    assert_eq!(format_number(12000.0, NumberFormat::Engineering { figures: 3 }), "12.0e3");
+
+    assert_eq!(format_number(255.0, NumberFormat::Hex), "0xFF");
+    assert_eq!(format_number(-10.0, NumberFormat::Binary), "-0b1010");
+    assert_eq!(format_number(8.0, NumberFormat::Octal), "0o10");
+
+    // only integers have another base
+    assert_eq!(format_number(2.5, NumberFormat::Hex), "2.5");
+
+    assert_eq!(NumberFormat::parse("Fixed", Some(2)), Some(NumberFormat::Fixed { decimals: 2 }));
+    assert_eq!(NumberFormat::parse("scientific", None), None);
+    assert_eq!(NumberFormat::parse("roman", None), None);
+
+    // the evaluator writes values in its format
+    let mut evaluator = Evaluator::new();
+
+    let formatted = |evaluator: &mut Evaluator, input: &str| match evaluator.evaluate_response(input, -10.0, 10.0) {
+        Some(EvaluatorResponse::Value { formatted, .. }) |
+        Some(EvaluatorResponse::List { formatted, .. }) |
+        Some(EvaluatorResponse::Matrix { formatted, .. }) |
+        Some(EvaluatorResponse::Decomposition { formatted, .. }) |
+        Some(EvaluatorResponse::Fit { formatted, .. }) => formatted,
+        other => panic!("expected a value, got {:?}", other)
+    };
+
+    assert_eq!(formatted(&mut evaluator, "1/3"), "0.3333333333");
+
+    evaluator.update_line(1, "a = 200 + 55");
+    let results = evaluator.update_number_format(NumberFormat::Hex);
+
+    assert!(matches!(
+        &results[0].response,
+        Some(EvaluatorResponse::Value { formatted, .. }) if formatted == "0xFF"
+    ));
+
+    evaluator.update_number_format(NumberFormat::Fixed { decimals: 1 });
+    evaluator.update_complex_mode(true);
+    assert_eq!(formatted(&mut evaluator, "sqrt(-4) - 1"), "-1.0 + 2.0i");
+    evaluator.update_complex_mode(false);
+
+    // as are lists, matrices, decompositions and fits
+    assert_eq!(formatted(&mut evaluator, "[1/3, 2]"), "[0.3, 2.0]");
+    assert_eq!(formatted(&mut evaluator, "[[1, 0.26], [0, 2]]"), "[[1.0, 0.3], [0.0, 2.0]]");
+    assert_eq!(formatted(&mut evaluator, "eig([[2, 0], [0, 3]])"), "values = [2.0, 3.0], vectors = [[1.0, 0.0], [0.0, 1.0]]");
+
+    evaluator.update_line(2, "L = [1, 2, 3]");
+    evaluator.update_line(3, "M = [2, 4, 6]");
+    evaluator.update_line(4, "k = 1");
+    assert_eq!(formatted(&mut evaluator, "M ~ k L"), "k = 2.0, R² = 1.0");
+}
+
+#[test]
+fn units() {
+    let mut evaluator = Evaluator::new();
+    evaluator.update_units_mode(true);
+
+    let measured = |evaluator: &mut Evaluator, input: &str| match value_response(evaluator, input) {
+        EvaluatorResponse::Value { value, unit, .. } => (value, unit),
+        _ => unreachable!()
+    };
+
+    let close = |(value, unit): (f64, Option<String>), expected: f64, expected_unit: &str| {
+        (value - expected).abs() < 1e-9 && unit.as_deref() == Some(expected_unit)
+    };
+
+    assert!(close(measured(&mut evaluator, "5 m / 2 s"), 2.5, "m/s"));
+    assert!(close(measured(&mut evaluator, "3 kg * 2 m/s^2"), 6.0, "N"));
+    assert!(close(measured(&mut evaluator, "2 km + 300 m"), 2300.0, "m"));
+    assert!(close(measured(&mut evaluator, "3 m^2"), 3.0, "m^2"));
+    assert!(close(measured(&mut evaluator, "sqrt(16 m^2)"), 4.0, "m"));
+    assert!(close(measured(&mut evaluator, "500 g / (2 L)"), 250.0, "kg/m^3"));
+    assert!(close(measured(&mut evaluator, "90 km/h to m/s"), 25.0, "m/s"));
+    assert!(close(measured(&mut evaluator, "10 m/s to km/h"), 36.0, "km/h"));
+    assert!(close(measured(&mut evaluator, "1 ms to μs"), 1000.0, "μs"));
+
+    // without any units the answer is a plain number
+    assert_eq!(measured(&mut evaluator, "2 + 3"), (5.0, None));
+    assert_eq!(measured(&mut evaluator, "6 m / 3 m"), (2.0, None));
+
+    // units have to agree
+    assert!(evaluator.evaluate_response("3 kg + 2 m", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("5 m < 2 s", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("sin(2 m)", -10.0, 10.0).is_none());
+
+    // powers too large to keep
+    assert!(evaluator.evaluate_response("(m^2000000000)*(m^2000000000)", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("(m^2000000000)^2", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("2^(3 s)", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("sqrt(2 m)", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("5 m to s", -10.0, 10.0).is_none());
+
+    // variables keep their units
+    evaluator.update_line(1, "v = 36 km/h");
+    evaluator.update_line(2, "d = v * 2 min");
+    assert!(close(measured(&mut evaluator, "d"), 1200.0, "m"));
+
+    evaluator.update_line(3, "w = 3 kg + 2 m");
+    assert_eq!(
+        evaluator.line_result(3).unwrap().error,
+        Some(DefinitionError::IncompatibleUnits { left: "kg".to_string(), right: "m".to_string() })
+    );
+
+    // defined names win over units, so lists and fits keep working
+    evaluator.update_line(4, "L = [1, 2, 3]");
+    evaluator.update_line(5, "M = 3L");
+    evaluator.update_line(6, "M ~ k L");
+
+    match &evaluator.line_result(5).unwrap().response {
+        Some(EvaluatorResponse::List { values, .. }) => assert_eq!(values, &vec![3.0, 6.0, 9.0]),
+        other => panic!("expected a list, got {:?}", other)
+    }
+
+    match &evaluator.line_result(6).unwrap().response {
+        Some(EvaluatorResponse::Fit { parameters, .. }) => assert!((parameters[0] - 3.0).abs() < 1e-6),
+        other => panic!("expected a fit, got {:?}", other)
+    }
+
+    evaluator.update_line(7, "h = 2");
+    assert_eq!(measured(&mut evaluator, "3 h"), (6.0, None));
+
+    assert_eq!(find_unit("kg"), Some((1.0, Dimension::new([0, 1, 0, 0, 0, 0, 0]))));
+    assert_eq!(find_unit("mmin"), None);
+    assert_eq!(Dimension::new([1, 1, -2, 0, 0, 0, 0]).to_string(), "N");
+    assert_eq!(Dimension::new([1, 0, -1, 0, 0, 0, 0]).to_string(), "m/s");
+
+    // with units off, m and s are variables again
+    evaluator.update_units_mode(false);
+    evaluator.evaluate_response("m = 4", -10.0, 10.0);
+    assert_eq!(measured(&mut evaluator, "2m"), (8.0, None));
+}
+
+#[test]
+fn matrices() {
+    let mut evaluator = Evaluator::new();
+
+    let rows = |evaluator: &mut Evaluator, input: &str| match evaluator.evaluate_response(input, -10.0, 10.0) {
+        Some(EvaluatorResponse::Matrix { rows, .. }) => rows,
+        other => panic!("expected a matrix, got {:?}", other)
+    };
+
+    evaluator.update_line(1, "A = [[1, 2], [3, 4]]");
+    evaluator.update_line(2, "B = [[0, 1], [1, 0]]");
+    evaluator.update_line(3, "v = [1, 1]");
+
+    assert_eq!(rows(&mut evaluator, "A + B"), vec![vec![1.0, 3.0], vec![4.0, 4.0]]);
+    assert_eq!(rows(&mut evaluator, "A B"), vec![vec![2.0, 1.0], vec![4.0, 3.0]]);
+    assert_eq!(rows(&mut evaluator, "2A - 1"), vec![vec![1.0, 3.0], vec![5.0, 7.0]]);
+    assert_eq!(rows(&mut evaluator, "A^2"), vec![vec![7.0, 10.0], vec![15.0, 22.0]]);
+    assert_eq!(rows(&mut evaluator, "B^2000000001"), vec![vec![0.0, 1.0], vec![1.0, 0.0]]);
+    assert_eq!(rows(&mut evaluator, "[[1, 1], [0, 1]]^-5"), vec![vec![1.0, -5.0], vec![0.0, 1.0]]);
+    assert_eq!(rows(&mut evaluator, "transpose(A)"), vec![vec![1.0, 3.0], vec![2.0, 4.0]]);
+    assert_eq!(rows(&mut evaluator, "-B"), vec![vec![-0.0, -1.0], vec![-1.0, -0.0]]);
+    assert_eq!(list(&mut evaluator, "A v"), vec![3.0, 7.0]);
+    assert_eq!(list(&mut evaluator, "A[2]"), vec![3.0, 4.0]);
+
+    assert!((value(&mut evaluator, "det(A)") + 2.0).abs() < 1e-12);
+    assert_eq!(value(&mut evaluator, "rank(A)"), 2.0);
+    assert_eq!(value(&mut evaluator, "rank([[1, 2], [2, 4]])"), 1.0);
+    assert_eq!(value(&mut evaluator, "det([[1, 2], [2, 4]])"), 0.0);
+    assert_eq!(value(&mut evaluator, "dot(v, [3, 4])"), 7.0);
+    assert_eq!(list(&mut evaluator, "cross([1, 0, 0], [0, 1, 0])"), vec![0.0, 0.0, 1.0]);
+
+    let inverse = rows(&mut evaluator, "inv(A)");
+    let expected = [[-2.0, 1.0], [1.5, -0.5]];
+    assert!(inverse.iter().flatten().zip(expected.iter().flatten()).all(|(a, b)| (a - b).abs() < 1e-12));
+
+    let solution = list(&mut evaluator, "solve(A, [5, 11])");
+    assert!((solution[0] - 1.0).abs() < 1e-12 && (solution[1] - 2.0).abs() < 1e-12);
+
+    // matrix functions giving numbers work in graphs
+    evaluator.evaluate_response("f(x) = det([[x, 1], [1, x]])", -10.0, 10.0);
+    assert_eq!(value(&mut evaluator, "f(3)"), 8.0);
+
+    // shapes that don't fit are reported rather than panicking
+    for input in ["[[1, 2], [3]]", "A + [[1, 2, 3]]", "A [1, 2, 3]", "det([[1, 2, 3]])", "inv([[1, 2], [2, 4]])", "cross(v, v)"] {
+        assert!(evaluator.evaluate_response(input, -10.0, 10.0).is_none(), "{} should fail", input);
+    }
+
+    evaluator.update_line(4, "C = A [[1, 2, 3]]");
+    assert_eq!(
+        evaluator.line_result(4).unwrap().error,
+        Some(DefinitionError::Matrix { reason: "can't combine 2x2 with 1x3".to_string() })
+    );
+
+    let singular = Matrix::new(vec![vec![1.0, 2.0], vec![2.0, 4.0]]).unwrap();
+    assert_eq!(singular.inverse(), Err(MatrixError::Singular));
+    assert_eq!(Matrix::new(vec![vec![1.0], vec![]]), Err(MatrixError::Ragged));
+    assert_eq!(singular.power(-1), Err(MatrixError::Singular));
+}
+
+#[test]
+fn linear_algebra() {
+    let mut evaluator = Evaluator::new();
+
+    let close = |a: &Matrix, b: &Matrix| a.rows().iter().flatten().zip(b.rows().iter().flatten()).all(|(a, b)| (a - b).abs() < 1e-9);
+    let matrix = |rows: Vec<Vec<f64>>| Matrix::new(rows).unwrap();
+
+    let a = matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+
+    match lu(&a).unwrap() {
+        Decomposition::Lu { p, l, u } => {
+            assert_eq!(p.rows(), &[vec![0.0, 1.0], vec![1.0, 0.0]]);
+            assert_eq!(l.rows()[0], vec![1.0, 0.0]);
+            assert_eq!(u.rows()[1][0], 0.0);
+            assert!(close(&p.product(&a).unwrap(), &l.product(&u).unwrap()));
+        },
+        other => panic!("expected lu, got {:?}", other)
+    }
+
+    let tall = matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]);
+
+    match qr(&tall).unwrap() {
+        Decomposition::Qr { q, r } => {
+            assert!(close(&q.product(&r).unwrap(), &tall));
+            assert!(close(&q.transpose().product(&q).unwrap(), &Matrix::identity(3)));
+            assert!(r.rows()[1][0] == 0.0 && r.rows()[2][0] == 0.0 && r.rows()[2][1] == 0.0);
+        },
+        other => panic!("expected qr, got {:?}", other)
+    }
+
+    // symmetric, with eigenvalues 2 - sqrt(2), 2 and 2 + sqrt(2)
+    let symmetric = matrix(vec![vec![2.0, -1.0, 0.0], vec![-1.0, 2.0, -1.0], vec![0.0, -1.0, 2.0]]);
+    let (values, vectors) = symmetric_eigen(&symmetric).unwrap();
+    let expected = [2.0 - 2f64.sqrt(), 2.0, 2.0 + 2f64.sqrt()];
+    assert!(values.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9));
+
+    let scaled = matrix(vectors.rows().iter().map(|row| row.iter().zip(&values).map(|(v, l)| v * l).collect()).collect());
+    assert!(close(&symmetric.product(&vectors).unwrap(), &scaled));
+    assert!(close(&vectors.transpose().product(&vectors).unwrap(), &Matrix::identity(3)));
+
+    // the companion matrix of (x - 1)(x - 2)(x^2 + 1)
+    let companion = matrix(vec![
+        vec![3.0, -3.0, 3.0, -2.0],
+        vec![1.0, 0.0, 0.0, 0.0],
+        vec![0.0, 1.0, 0.0, 0.0],
+        vec![0.0, 0.0, 1.0, 0.0]
+    ]);
+
+    let values = eigenvalues(&companion).unwrap();
+    let expected = [Complex::new(0.0, -1.0), Complex::new(0.0, 1.0), Complex::new(1.0, 0.0), Complex::new(2.0, 0.0)];
+    assert!(values.iter().zip(expected).all(|(a, b)| (a.re - b.re).abs() < 1e-9 && (a.im - b.im).abs() < 1e-9));
+
+    // a general matrix with real eigenvalues 2 and 5 still gets its eigenvectors
+    match eigen(&matrix(vec![vec![4.0, 1.0], vec![2.0, 3.0]])).unwrap() {
+        Decomposition::Eigen { values, vectors: Some(vectors) } => {
+            assert!((values[0].re - 2.0).abs() < 1e-9 && (values[1].re - 5.0).abs() < 1e-9);
+
+            let v = vectors.transpose().rows()[1].clone();
+            assert!((v[0] - v[1]).abs() < 1e-9);
+        },
+        other => panic!("expected eigenvectors, got {:?}", other)
+    }
+
+    // a repeated eigenvalue gets a whole eigenspace rather than the same vector twice
+    let repeated = matrix(vec![vec![2.0, 0.0, 0.0], vec![0.0, 2.0, 1.0], vec![0.0, 0.0, 3.0]]);
+
+    match eigen(&repeated).unwrap() {
+        Decomposition::Eigen { values, vectors: Some(vectors) } => {
+            let values: Vec<f64> = values.iter().map(|v| v.re).collect();
+            let scaled = matrix(vectors.rows().iter().map(|row| row.iter().zip(&values).map(|(v, l)| v * l).collect()).collect());
+
+            assert!(close(&repeated.product(&vectors).unwrap(), &scaled));
+            assert_eq!(vectors.rank(), 3);
+        },
+        other => panic!("expected eigenvectors, got {:?}", other)
+    }
+
+    // a defective matrix doesn't have enough eigenvectors to form a basis
+    match eigen(&matrix(vec![vec![1.0, 1.0], vec![0.0, 1.0]])).unwrap() {
+        Decomposition::Eigen { values, vectors } => {
+            assert!(values.iter().all(|v| (v.re - 1.0).abs() < 1e-6 && v.im == 0.0));
+            assert_eq!(vectors, None);
+        },
+        other => panic!("expected eigenvalues, got {:?}", other)
+    }
+
+    let wide = matrix(vec![vec![3.0, 2.0, 2.0], vec![2.0, 3.0, -2.0]]);
+
+    match svd(&wide).unwrap() {
+        Decomposition::Svd { u, s, v } => {
+            assert!((s[0] - 5.0).abs() < 1e-9 && (s[1] - 3.0).abs() < 1e-9);
+
+            let scaled = matrix(u.rows().iter().map(|row| row.iter().zip(&s).map(|(u, s)| u * s).collect()).collect());
+            assert!(close(&scaled.product(&v.transpose()).unwrap(), &wide));
+        },
+        other => panic!("expected svd, got {:?}", other)
+    }
+
+    // a rotation has eigenvalues -i and i and no real eigenvectors
+    match evaluator.evaluate_response("eig([[0, -1], [1, 0]])", -10.0, 10.0) {
+        Some(EvaluatorResponse::Decomposition { decomposition: Decomposition::Eigen { values, vectors }, .. }) => {
+            assert_eq!(values, vec![Complex::new(0.0, -1.0), Complex::new(0.0, 1.0)]);
+            assert_eq!(vectors, None);
+        },
+        other => panic!("expected eigenvalues, got {:?}", other)
+    }
+
+    evaluator.update_line(1, "D = svd([[3, 0], [0, -2]])");
+    assert_eq!(evaluator.line_result(1).unwrap().error, None);
+
+    evaluator.update_line(2, "E = lu([[1, 2, 3]])");
+    assert_eq!(
+        evaluator.line_result(2).unwrap().error,
+        Some(DefinitionError::Matrix { reason: "a 1x3 matrix isn't square".to_string() })
+    );
+
+    assert!(evaluator.evaluate_response("qr(5)", -10.0, 10.0).is_none());
+    assert!(evaluator.evaluate_response("eig([[1, 2, 3]]) + 1", -10.0, 10.0).is_none());
+}